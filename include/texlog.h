@@ -0,0 +1,38 @@
+/*
+ * C interface of texlog, for embedding it in editors.
+ *
+ * Build the library with
+ *
+ *     cargo rustc --lib --release --features ffi --crate-type cdylib
+ *
+ * and link against target/release/libtexlog.so. The functions return the
+ * document of the `json` output format of `texlog --format json`, which the
+ * caller frees with texlog_free_string, or NULL if the log could not be read.
+ * texlog_last_error then returns the reason.
+ */
+
+#ifndef TEXLOG_H
+#define TEXLOG_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Parse the text of a log. */
+char *texlog_parse(const char *text);
+
+/* Read and parse a log file. */
+char *texlog_parse_file(const char *path);
+
+/* Why the last parse on this thread returned NULL, or NULL if it succeeded.
+ * The string belongs to texlog and is valid until the next parse. */
+const char *texlog_last_error(void);
+
+/* Free a string returned by texlog. NULL is ignored. */
+void texlog_free_string(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TEXLOG_H */
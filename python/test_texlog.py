@@ -0,0 +1,56 @@
+"""Tests of the texlog package. Run them from the repository root with
+
+    python3 -m unittest discover python
+
+They build the library with cargo unless TEXLOG_LIBRARY points at one.
+"""
+
+import os
+import subprocess
+import tempfile
+import unittest
+from pathlib import Path
+
+ROOT = Path(__file__).resolve().parent.parent
+
+if "TEXLOG_LIBRARY" not in os.environ:
+    subprocess.run(
+        ["cargo", "rustc", "--lib", "--features", "ffi", "--crate-type", "cdylib"],
+        cwd=ROOT,
+        check=True,
+    )
+    os.environ["TEXLOG_LIBRARY"] = str(ROOT / "target" / "debug" / "libtexlog.so")
+
+import texlog  # noqa: E402
+
+LOG = "(./main.tex\n! Undefined control sequence.\nl.3 \\foo\n\n)"
+
+
+class TestTexlog(unittest.TestCase):
+    def test_parse_text(self):
+        [d] = texlog.parse_text(LOG)
+        self.assertEqual(d.level, "error")
+        self.assertEqual(d.code, "E0021")
+        self.assertEqual(d.details, {"argument": "\\foo"})
+        self.assertEqual(d.line, 3)
+        self.assertEqual(d.trace, ["./main.tex"])
+
+    def test_parse(self):
+        with tempfile.TemporaryDirectory() as dir:
+            path = Path(dir) / "main.log"
+            path.write_text(LOG)
+            [d] = texlog.parse(path)
+        self.assertEqual(d.file, "./main.tex")
+
+    def test_parse_error(self):
+        with self.assertRaises(texlog.ParseError) as e:
+            texlog.parse("/nonexistent/main.log")
+        self.assertIn("could not read /nonexistent/main.log", str(e.exception))
+
+        with self.assertRaises(texlog.ParseError) as e:
+            texlog.parse_text("no files here")
+        self.assertEqual(str(e.exception), "the log does not open any file")
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,102 @@
+"""Parse LaTeX log files into diagnostics.
+
+    import texlog
+    for d in texlog.parse("main.log"):
+        print(d.level, d.file, d.line, d.message)
+
+The diagnostics come from the texlog library through its C functions. Build it with
+
+    cargo rustc --lib --release --features ffi --crate-type cdylib
+
+and point TEXLOG_LIBRARY at target/release/libtexlog.so, or install it where the
+dynamic linker finds it.
+"""
+
+import ctypes
+import ctypes.util
+import json
+import os
+from dataclasses import dataclass, field
+from typing import List, Optional, Tuple
+
+__all__ = ["Diagnostic", "ParseError", "parse", "parse_text"]
+
+
+class ParseError(Exception):
+    """The log could not be read, with the reason given by texlog."""
+
+
+@dataclass
+class Diagnostic:
+    """A warning or error in a log, with the fields of `texlog --format json`."""
+
+    level: str
+    kind: str
+    code: str
+    message: str
+    hint: Optional[str] = None
+    suggestion: Optional[str] = None
+    count: int = 1
+    file: Optional[str] = None
+    line: Optional[int] = None
+    page: Optional[int] = None
+    span: Tuple[int, int] = (0, 0)
+    trace: List[str] = field(default_factory=list)
+    details: Optional[dict] = None
+
+    @classmethod
+    def from_json(cls, d: dict) -> "Diagnostic":
+        return cls(
+            level=d["level"],
+            kind=d["kind"],
+            code=d["code"],
+            message=d["message"],
+            hint=d.get("hint"),
+            suggestion=d.get("suggestion"),
+            count=d.get("count", 1),
+            file=d.get("file"),
+            line=d.get("line"),
+            page=d.get("page"),
+            span=(d["span"]["start"], d["span"]["end"]),
+            trace=d.get("trace", []),
+            details=d.get("details"),
+        )
+
+
+def _load():
+    path = os.environ.get("TEXLOG_LIBRARY") or ctypes.util.find_library("texlog")
+    if path is None:
+        raise ImportError("the texlog library was not found; set TEXLOG_LIBRARY")
+    lib = ctypes.CDLL(path)
+    for name in ["texlog_parse", "texlog_parse_file"]:
+        getattr(lib, name).argtypes = [ctypes.c_char_p]
+        getattr(lib, name).restype = ctypes.c_void_p
+    lib.texlog_last_error.argtypes = []
+    lib.texlog_last_error.restype = ctypes.c_char_p
+    lib.texlog_free_string.argtypes = [ctypes.c_void_p]
+    lib.texlog_free_string.restype = None
+    return lib
+
+
+_lib = _load()
+
+
+def _diagnostics(ptr) -> List[Diagnostic]:
+    if not ptr:
+        error = _lib.texlog_last_error()
+        raise ParseError(error.decode() if error else "texlog could not read the log")
+    try:
+        report = json.loads(ctypes.string_at(ptr).decode())
+    finally:
+        _lib.texlog_free_string(ptr)
+    return [Diagnostic.from_json(d) for d in report["diagnostics"]]
+
+
+def parse(path) -> List[Diagnostic]:
+    """The diagnostics of a log file, in the order texlog prints them."""
+    return _diagnostics(_lib.texlog_parse_file(os.fsencode(path)))
+
+
+def parse_text(text: str) -> List[Diagnostic]:
+    """The diagnostics of the text of a log."""
+    return _diagnostics(_lib.texlog_parse(text.encode()))
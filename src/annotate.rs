@@ -0,0 +1,120 @@
+use crate::{
+    ansi,
+    log::Log,
+    parser::{DiagnosticLevel, Node, TexDiagnostic, Visitor},
+};
+
+/// A place in the log where texlog detected something
+enum Marker<'a> {
+    Open(&'a Node),
+    Close(&'a Node),
+    Start(&'a TexDiagnostic),
+    End(&'a TexDiagnostic),
+}
+
+impl Marker<'_> {
+    /// Order of markers at the same position, so that diagnostics and files nest
+    fn rank(&self) -> u8 {
+        match self {
+            Marker::End(_) => 0,
+            Marker::Close(_) => 1,
+            Marker::Open(_) => 2,
+            Marker::Start(_) => 3,
+        }
+    }
+
+    fn render(&self, color: bool) -> String {
+        let (code, text) = match self {
+            Marker::Open(node) => (ansi::BLUE, format!("<<file {}>>", node.file())),
+            Marker::Close(node) => (ansi::BLUE, format!("<</file {}>>", node.file())),
+            Marker::Start(d) => (
+                level_color(d.level()),
+                format!("<<{} {} {}>>", d.level(), d.kind().name(), d.kind().code()),
+            ),
+            Marker::End(d) => (level_color(d.level()), format!("<</{}>>", d.level())),
+        };
+        match color {
+            true => format!("{}{}{}", code, text, ansi::RESET),
+            false => text,
+        }
+    }
+}
+
+fn level_color(level: DiagnosticLevel) -> &'static str {
+    match level {
+        DiagnosticLevel::Info => ansi::CYAN,
+        DiagnosticLevel::Warning => ansi::YELLOW,
+        DiagnosticLevel::Error => ansi::RED,
+    }
+}
+
+/// Collects the markers of every file and diagnostic with their character positions
+#[derive(Default)]
+struct MarkerCollector<'a> {
+    markers: Vec<(usize, Marker<'a>)>,
+}
+
+impl<'a> Visitor<'a> for MarkerCollector<'a> {
+    fn enter_node(&mut self, node: &'a Node) {
+        self.markers.push((node.span().start, Marker::Open(node)));
+        for d in node.diagnostics() {
+            self.markers.push((d.span().start, Marker::Start(d)));
+            self.markers.push((d.span().end, Marker::End(d)));
+        }
+    }
+
+    fn leave_node(&mut self, node: &'a Node) {
+        self.markers.push((node.span().end, Marker::Close(node)));
+    }
+}
+
+impl Log {
+    /// The text of the log with markers where texlog saw files being opened and closed, and
+    /// where every diagnostic starts and ends, like `<<file ./main.tex>>` and
+    /// `<<warning overfull-hbox W0004>>`. Filters do not apply, so that everything that was
+    /// detected is marked.
+    pub fn annotate(&self, color: bool) -> String {
+        let mut collector = MarkerCollector::default();
+        if self.all_runs {
+            for run in &self.runs {
+                collector.visit_node(run);
+            }
+        }
+        collector.visit_node(self.root_node());
+        let mut markers = collector.markers;
+        markers.sort_by_key(|(pos, marker)| (*pos, marker.rank()));
+
+        let text = self.source().as_str();
+        let mut annotated = String::with_capacity(text.len() + markers.len() * 20);
+        let mut markers = markers.into_iter().peekable();
+        for (i, c) in text.chars().enumerate() {
+            while let Some((_, marker)) = markers.next_if(|(pos, _)| *pos <= i) {
+                annotated += &marker.render(color);
+            }
+            annotated.push(c);
+        }
+        for (_, marker) in markers {
+            annotated += &marker.render(color);
+        }
+        annotated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parser::parse_source, text::SourceText};
+
+    #[test]
+    fn annotate() {
+        let text = "(./main.tex (./a.tex)\n! Undefined control sequence.\nl.3 \\foo\n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let annotated = log.annotate(false);
+        assert!(annotated.starts_with(
+            "<<file ./main.tex>>(./main.tex <<file ./a.tex>>(./a.tex<</file ./a.tex>>)"
+        ));
+        assert!(annotated
+            .contains("\n<<error undefined-control-sequence E0021>>! Undefined control sequence."));
+        assert!(annotated.contains("l.3 \\foo\n<</error>>"));
+        assert!(annotated.ends_with("<</file ./main.tex>>)"));
+    }
+}
@@ -0,0 +1,14 @@
+//! ANSI escape codes for colored output. They are written out so that the library does not need
+//! a terminal crate, which does not build for targets without a terminal like WebAssembly.
+
+pub(crate) const RED: &str = "\x1b[38;5;1m";
+pub(crate) const YELLOW: &str = "\x1b[38;5;3m";
+pub(crate) const BLUE: &str = "\x1b[38;5;4m";
+pub(crate) const CYAN: &str = "\x1b[38;5;6m";
+pub(crate) const LIGHT_GREEN: &str = "\x1b[38;5;10m";
+
+/// Back to the default foreground color
+pub(crate) const RESET: &str = "\x1b[39m";
+
+pub(crate) const FAINT: &str = "\x1b[2m";
+pub(crate) const NO_FAINT: &str = "\x1b[22m";
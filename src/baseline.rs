@@ -0,0 +1,251 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+    error::{Error, Result},
+    json::Json,
+    log::{Log, TracedTexDiagnostic},
+};
+
+/// Version written to and expected in baseline files
+const BASELINE_VERSION: usize = 1;
+
+/// Phrases that are followed by page and line numbers in messages
+const NUMBERED: [&str; 5] = ["page ", "line ", "lines ", "--", "\nl."];
+
+/// The text without the page and line numbers that change when text is added above a diagnostic
+fn strip_numbers(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_digit() && NUMBERED.iter().any(|n| stripped.ends_with(n)) {
+            continue;
+        }
+        stripped.push(c);
+    }
+    stripped
+}
+
+/// What makes two diagnostics of different runs of a document the same: the kind with its
+/// argument, the message without page and line numbers, and the file as written in the log. A
+/// warning that moved to another page is then still the same warning.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct DiagnosticKey {
+    kind: String,
+    message: String,
+    file: Option<String>,
+}
+
+impl DiagnosticKey {
+    fn new(kind: &str, message: &str, file: Option<&str>) -> Self {
+        Self {
+            kind: strip_numbers(kind),
+            message: strip_numbers(message),
+            file: file.map(str::to_string),
+        }
+    }
+}
+
+impl From<&TracedTexDiagnostic> for DiagnosticKey {
+    fn from(d: &TracedTexDiagnostic) -> Self {
+        Entry::from(d).key()
+    }
+}
+
+/// A diagnostic recorded in a baseline
+#[derive(Clone, Debug, PartialEq)]
+struct Entry {
+    kind: String,
+    message: String,
+    file: Option<String>,
+}
+
+impl Entry {
+    fn key(&self) -> DiagnosticKey {
+        DiagnosticKey::new(&self.kind, &self.message, self.file.as_deref())
+    }
+}
+
+impl From<&TracedTexDiagnostic> for Entry {
+    fn from(d: &TracedTexDiagnostic) -> Self {
+        Self {
+            kind: d.diagnostic().kind().to_string(),
+            message: d.diagnostic().message().to_string(),
+            file: d.logged_file().map(|f| f.display().to_string()),
+        }
+    }
+}
+
+/// Known diagnostics that should not be reported again, so that a project with many old warnings
+/// can start checking for new ones. Diagnostics are matched by `DiagnosticKey`, and a diagnostic
+/// recorded n times hides n occurrences of it, so that another copy of a known warning is new.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Baseline {
+    entries: Vec<Entry>,
+
+    /// Number of entries with every key
+    counts: HashMap<DiagnosticKey, usize>,
+}
+
+impl Baseline {
+    fn from_entries(entries: impl IntoIterator<Item = Entry>) -> Self {
+        let mut baseline = Self::default();
+        for entry in entries {
+            *baseline.counts.entry(entry.key()).or_default() += 1;
+            baseline.entries.push(entry);
+        }
+        baseline
+    }
+
+    pub fn from_diagnostics(diagnostics: &[TracedTexDiagnostic]) -> Self {
+        Self::from_entries(diagnostics.iter().map(Entry::from))
+    }
+
+    pub fn parse(source: &str) -> std::result::Result<Self, String> {
+        let json = Json::parse(source)?;
+        match json.get("version") {
+            Some(Json::Number(v)) if *v == BASELINE_VERSION as f64 => {}
+            _ => return Err(format!("expected version {}", BASELINE_VERSION)),
+        }
+        let diagnostics = json
+            .get("diagnostics")
+            .and_then(Json::as_array)
+            .ok_or("missing the list of diagnostics")?;
+        let entries = diagnostics
+            .iter()
+            .map(|d| {
+                let field = |key| {
+                    d.get(key)
+                        .and_then(Json::as_str)
+                        .map(str::to_string)
+                        .ok_or_else(|| format!("diagnostic without a {}", key))
+                };
+                Ok(Entry {
+                    kind: field("kind")?,
+                    message: field("message")?,
+                    file: field("file").ok(),
+                })
+            })
+            .collect::<std::result::Result<Vec<_>, String>>()?;
+        Ok(Self::from_entries(entries))
+    }
+
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::parse(&source).map_err(|message| Error::Parse {
+            path: path.to_path_buf(),
+            message,
+        })
+    }
+
+    /// Write the baseline as JSON, with one diagnostic per line so that changes are easy to review
+    pub fn write(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_string()).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// The diagnostics that are not recorded in the baseline. The first occurrences of a
+    /// diagnostic are hidden, as many as are recorded.
+    pub fn unknown(&self, diagnostics: Vec<TracedTexDiagnostic>) -> Vec<TracedTexDiagnostic> {
+        let mut remaining = self.counts.clone();
+        diagnostics
+            .into_iter()
+            .filter(|d| match remaining.get_mut(&DiagnosticKey::from(d)) {
+                Some(n) if *n > 0 => {
+                    *n -= 1;
+                    false
+                }
+                _ => true,
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Log {
+    /// A baseline with every diagnostic that is reported for the log, including repeated ones
+    /// from other files
+    pub fn to_baseline(&self) -> Baseline {
+        Baseline::from_diagnostics(&self.get_undeduped_diagnostics())
+    }
+}
+
+impl std::fmt::Display for Baseline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{{\"version\":{},\"diagnostics\":[", BASELINE_VERSION)?;
+        for (i, entry) in self.entries.iter().enumerate() {
+            let json = Json::object([
+                ("kind", Json::string(&entry.kind)),
+                ("message", Json::string(&entry.message)),
+                ("file", entry.file.as_ref().map(Json::string).into()),
+            ]);
+            let comma = if i + 1 < self.entries.len() { "," } else { "" };
+            writeln!(f, "  {}{}", json, comma)?;
+        }
+        writeln!(f, "]}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{log::PathStyle, parser::parse_source, text::SourceText};
+
+    fn log(body: &str) -> Log {
+        let text = format!("(./main.tex\n\n{}\n\n)", body);
+        parse_source(SourceText::new(text)).unwrap()
+    }
+
+    #[test]
+    fn baseline() {
+        let old = "LaTeX Warning: Reference `a' on page 1 undefined on input line 3.";
+        let new = "LaTeX Warning: Reference `b' on page 2 undefined on input line 9.";
+        let baseline = log(old).to_baseline();
+        let baseline = Baseline::parse(&baseline.to_string()).unwrap();
+        assert_eq!(baseline.len(), 1);
+
+        let log = log(&format!("{}\n\n{}", old, new)).with_baseline(baseline);
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 1);
+        assert!(ds[0].diagnostic().message().contains("`b'"));
+
+        assert!(Baseline::parse("{\"version\":2,\"diagnostics\":[]}").is_err());
+    }
+
+    #[test]
+    fn moved_warning() {
+        // Text added above a known warning moves it to another page and line
+        let old = "LaTeX Warning: Reference `a' on page 1 undefined on input line 3.";
+        let moved = "LaTeX Warning: Reference `a' on page 2 undefined on input line 9.";
+        let baseline = Baseline::parse(&log(old).to_baseline().to_string()).unwrap();
+        let log = log(moved).with_baseline(baseline);
+        assert!(log.get_diagnostics().is_empty());
+
+        // Paths are matched as they are written in the log
+        let log = log.with_paths(PathStyle::Canonical);
+        assert!(log.get_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn repeated_warning() {
+        // Another copy of a known warning is new
+        let warning = "LaTeX Warning: Reference `a' on page 1 undefined on input line 3.";
+        let baseline = Baseline::parse(&log(warning).to_baseline().to_string()).unwrap();
+        let log = log(&format!("{}\n\n{}", warning, warning)).with_baseline(baseline.clone());
+        assert_eq!(log.get_diagnostics().len(), 1);
+
+        let baseline = Baseline::parse(&log.to_baseline().to_string()).unwrap();
+        assert_eq!(baseline.len(), 2);
+        assert!(log.with_baseline(baseline).get_diagnostics().is_empty());
+    }
+}
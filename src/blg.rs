@@ -0,0 +1,155 @@
+use std::path::PathBuf;
+
+use crate::{
+    log::Log,
+    parser::{Node, TexDiagnostic, TexDiagnosticKind},
+    text::SourceText,
+};
+
+/// Parse a `.blg` log written by Biber. Every line has the form
+/// `[300] Utils.pm:409> WARN - I didn't find a database entry for 'smith2020' (section 0)`,
+/// and the `WARN` and `ERROR` lines become diagnostics of the log file `file`.
+pub fn parse_blg(source: SourceText, file: String) -> Log {
+    let mut diagnostics = vec![];
+    let mut data_sources: Vec<String> = vec![];
+
+    let mut pos = 0;
+    for line in source.as_str().lines() {
+        let line_pos = pos;
+        pos += line.chars().count() + 1;
+
+        let Some((level, message)) = parse_line(line) else {
+            continue;
+        };
+        let kind = match level {
+            "WARN" => TexDiagnosticKind::Biber(entry_key(message).unwrap_or_default()),
+            "ERROR" => TexDiagnosticKind::BiberError(entry_key(message).unwrap_or_default()),
+            _ => {
+                if let Some(source) = data_source(message) {
+                    data_sources.push(source);
+                }
+                continue;
+            }
+        };
+
+        let mut diagnostic = TexDiagnostic::new(kind, message.to_string(), line_pos);
+        diagnostic.source_file = data_sources
+            .iter()
+            .find(|s| mentions_data_source(message, s))
+            .map(PathBuf::from);
+        if diagnostic.source_file.is_some() {
+            diagnostic.source_line = source_line(message);
+        }
+        diagnostics.push(diagnostic);
+    }
+
+    Log::new(source, Node::new(file, diagnostics))
+}
+
+impl Log {
+    /// The `.blg` file next to a `.log` file, if Biber was run for the document
+    pub fn blg_path(&self) -> Option<PathBuf> {
+        let path = self.path.as_ref()?;
+        if path.extension().is_none_or(|e| e != "log") {
+            return None;
+        }
+        let path = path.with_extension("blg");
+        path.is_file().then_some(path)
+    }
+}
+
+/// The level and message of a line
+fn parse_line(line: &str) -> Option<(&str, &str)> {
+    if !line.starts_with('[') {
+        return None;
+    }
+    let (_, rest) = line.split_once("> ")?;
+    rest.split_once(" - ")
+}
+
+/// The `refs.bib` of `Found BibTeX data source 'refs.bib'`
+fn data_source(message: &str) -> Option<String> {
+    let rest = message.strip_prefix("Found ")?;
+    let start = rest.find("data source '")? + "data source '".len();
+    let end = rest[start..].find('\'')?;
+    Some(rest[start..start + end].to_string())
+}
+
+/// Whether a message is about a data source. Biber reports syntax errors in a temporary copy
+/// of the file, like `/tmp/biber_tmp_x/refs.bib_123.utf8`.
+fn mentions_data_source(message: &str, source: &str) -> bool {
+    message.contains(&format!("'{}'", source))
+        || message.contains(&format!("({})", source))
+        || message.contains(&format!("/{}_", source))
+}
+
+/// The `14` of `..., line 14, syntax error`
+fn source_line(message: &str) -> Option<usize> {
+    let start = message.find(", line ")? + ", line ".len();
+    let digits: String = message[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+/// The key of the entry a message is about, like the `smith2020` of `I didn't find a database
+/// entry for 'smith2020'` or the `doe2019` of `Entry 'doe2019' (refs.bib): Invalid field`
+fn entry_key(message: &str) -> Option<String> {
+    let start = ["entry for '", "Entry '", "entry '", "key '"]
+        .iter()
+        .find_map(|p| message.find(p).map(|i| i + p.len()))?;
+    let end = message[start..].find('\'')?;
+    Some(message[start..start + end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn biber() {
+        let source = r#"[0] Config.pm:307> INFO - This is Biber 2.17
+[146] Biber.pm:994> INFO - Found 12 citekeys in bib section 0
+[220] bibtex.pm:1494> INFO - Found BibTeX data source 'refs.bib'
+[300] Utils.pm:409> WARN - I didn't find a database entry for 'smith2020' (section 0)
+[310] Utils.pm:409> WARN - Datamodel: Entry 'doe2019' (refs.bib): Invalid field 'journal' for entrytype 'book'
+[320] Utils.pm:424> ERROR - BibTeX subsystem: /tmp/biber_tmp_x/refs.bib_123.utf8, line 14, syntax error: found "}", expected end of entry
+[400] Biber.pm:133> INFO - WARNINGS: 2
+"#;
+        let log = parse_blg(SourceText::new(source.to_string()), "main.blg".to_string());
+        let ds = log.get_diagnostics();
+        let kinds: Vec<_> = ds.iter().map(|d| d.diagnostic().kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TexDiagnosticKind::Biber("smith2020".to_string()),
+                TexDiagnosticKind::Biber("doe2019".to_string()),
+                TexDiagnosticKind::BiberError(String::new()),
+            ]
+        );
+        assert_eq!(ds[0].diagnostic().source_file(), None);
+        assert_eq!(
+            ds[2].diagnostic().source_file(),
+            Some(std::path::Path::new("refs.bib"))
+        );
+        assert_eq!(ds[2].diagnostic().source_line(), Some(14));
+        assert_eq!(ds[0].call_stack(), &[PathBuf::from("main.blg")]);
+    }
+
+    #[test]
+    fn blg_path() {
+        let dir = std::env::temp_dir().join(format!("texlog-blg-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("main.log"), "(./main.tex)").unwrap();
+        let without = Log::from_path(dir.join("main.log")).unwrap().blg_path();
+        std::fs::write(dir.join("main.blg"), "").unwrap();
+        let with = Log::from_path(dir.join("main.log")).unwrap().blg_path();
+        let of_blg = Log::from_path(dir.join("main.blg")).unwrap().blg_path();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(without, None);
+        assert_eq!(with, Some(dir.join("main.blg")));
+        assert_eq!(of_blg, None);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{log::Log, parser::Node};
+
+/// Lines that end a log, and are kept in the cleaned log
+const FINAL_LINES: [&str; 2] = ["Output written on ", "No pages of output."];
+
+/// Either a diagnostic or a file that a node calls, to write them in the order of the log
+enum Item<'a> {
+    Diagnostic(&'a str),
+    Call(&'a Node),
+}
+
+/// Write a node with its diagnostics and the files it calls. Files without diagnostics are
+/// written on a single line, and diagnostics are followed by a blank line as in the log.
+fn write_node(node: &Node, clean: &mut String) {
+    let mut items: Vec<(usize, Item)> = node
+        .diagnostics()
+        .iter()
+        .map(|d| (d.span().start, Item::Diagnostic(d.message())))
+        .chain(
+            node.children()
+                .iter()
+                .map(|c| (c.span().start, Item::Call(c))),
+        )
+        .collect();
+    items.sort_by_key(|(pos, _)| *pos);
+
+    *clean += "(";
+    *clean += node.file();
+    if !items.is_empty() {
+        *clean += "\n";
+    }
+    for (_, item) in items {
+        match item {
+            Item::Diagnostic(message) => {
+                *clean += message.trim_matches('\n');
+                *clean += "\n\n";
+            }
+            Item::Call(call) => {
+                write_node(call, clean);
+                *clean += "\n";
+            }
+        }
+    }
+    *clean += ")";
+}
+
+impl Log {
+    /// A condensed copy of the final run for bug reports: the banner, the files that were
+    /// opened and the diagnostics in them, and the line about the output. Everything else, like
+    /// font and package info, is left out. The result is still a log that texlog reads with the
+    /// same diagnostics and tree.
+    pub fn clean(&self) -> String {
+        let mut clean = String::new();
+        for line in self.info.lines().filter(|l| !l.trim().is_empty()) {
+            clean += line;
+            clean += "\n";
+        }
+        write_node(self.root_node(), &mut clean);
+        clean += "\n";
+        let final_line = self
+            .final_run()
+            .lines()
+            .rev()
+            .find(|l| FINAL_LINES.iter().any(|p| l.starts_with(p)));
+        if let Some(line) = final_line {
+            clean += line;
+            clean += "\n";
+        }
+        clean
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parser::parse_source, text::SourceText};
+
+    #[test]
+    fn clean() {
+        let text = "This is pdfTeX, Version 3.14159265-2.6-1.40.21 (TeX Live 2020) (preloaded format=pdflatex)\n\
+                    (./main.tex\nLaTeX2e <2020-02-02> patch level 2\n\
+                    (/usr/share/texmf/tex/latex/base/article.cls\nDocument Class: article 2019/12/20 v1.4l Standard LaTeX document class\n)\n\
+                    (./intro.tex\nsome text\n\nLaTeX Warning: Reference `a' on page 1 undefined on input line 3.\n\n)\n\
+                    ! Undefined control sequence.\nl.8 \\foo\n\n\
+                    [1{/var/lib/texmf/fonts/map/pdftex/updmap/pdftex.map}] )\n\
+                    Output written on main.pdf (1 page, 12345 bytes).\n";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let clean = log.clean();
+        assert_eq!(
+            clean,
+            "This is pdfTeX, Version 3.14159265-2.6-1.40.21 (TeX Live 2020) (preloaded format=pdflatex)\n\
+             (./main.tex\n(/usr/share/texmf/tex/latex/base/article.cls)\n\
+             (./intro.tex\nLaTeX Warning: Reference `a' on page 1 undefined on input line 3.\n\n)\n\
+             ! Undefined control sequence.\nl.8 \\foo\n\n)\n\
+             Output written on main.pdf (1 page, 12345 bytes).\n"
+        );
+
+        let cleaned = parse_source(SourceText::new(clean)).unwrap();
+        assert_eq!(cleaned.get_diagnostics().len(), log.get_diagnostics().len());
+        assert_eq!(cleaned.root_node().children().len(), 2);
+    }
+}
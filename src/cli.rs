@@ -1,10 +1,448 @@
-use clap::Parser;
+use std::io::{self, IsTerminal};
 
-/// Parser for latex log files
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::{
+    locate,
+    log::{PathStyle, SortOrder},
+    parser::DiagnosticLevel,
+};
+
+/// Parser for latex log files. Without a subcommand the diagnostics of the log are reported, as
+/// with `check`. The options apply to every subcommand that reads a log.
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_override_self = true)]
 pub(crate) struct Args {
-    /// Latex log file
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+
+    /// Latex log files, or directories to search for them. The log of a `.tex` file is looked
+    /// up next to it and in the build directories. Leave out to use the log of the document in
+    /// the current directory, or when piping to read from standard input.
     #[clap(index = 1)]
-    pub(crate) file: String,
+    pub(crate) files: Vec<String>,
+
+    /// Config file to use instead of searching for `texlog.toml`
+    #[clap(long, value_name = "PATH", global = true)]
+    pub(crate) config: Option<String>,
+
+    /// Do not load a config or ignore file
+    #[clap(long, conflicts_with = "config", global = true)]
+    pub(crate) no_config: bool,
+
+    /// Report diagnostics while the log is being written, until the compilation finishes
+    #[clap(short, long, global = true)]
+    pub(crate) follow: bool,
+
+    /// Do not print diagnostics to the terminal. The exit code is still set.
+    #[clap(short, long, global = true)]
+    pub(crate) quiet: bool,
+
+    /// Print a final line with the number of errors and warnings
+    #[clap(long, global = true)]
+    pub(crate) summary: bool,
+
+    /// When to use colors. `auto` disables colors when piping or if NO_COLOR is set.
+    #[clap(long, value_enum, default_value_t = ColorMode::Auto, global = true)]
+    pub(crate) color: ColorMode,
+
+    /// When to make file names clickable with terminal hyperlinks. `auto` enables them when
+    /// printing to a terminal.
+    #[clap(long, value_enum, default_value_t = ColorMode::Auto, global = true)]
+    pub(crate) hyperlinks: ColorMode,
+
+    /// Collapse the files of the TeX distribution, like classes and packages, in call traces
+    #[clap(long, global = true)]
+    pub(crate) user_files_only: bool,
+
+    /// Only print this many files of the call trace, counting from the file the diagnostic is
+    /// in
+    #[clap(long, value_name = "N", global = true)]
+    pub(crate) trace_depth: Option<usize>,
+
+    /// Only print the first N warnings of the same kind in the same file, and count the others
+    /// on a single line. 0 prints all of them, as does `--verbose`.
+    #[clap(long, value_name = "N", default_value_t = 5, global = true)]
+    pub(crate) collapse: usize,
+
+    /// Print the log itself, with markers where texlog detected files being opened and closed
+    /// and where every diagnostic starts and ends
+    #[clap(long, global = true)]
+    pub(crate) annotate: bool,
+
+    /// Print N lines of the log before and after every diagnostic, for the context that the
+    /// message leaves out
+    #[clap(long, value_name = "N", default_value_t = 0, global = true)]
+    pub(crate) context: usize,
+
+    /// Width of the printed title bars. Defaults to the terminal width.
+    #[clap(long, value_name = "N", global = true)]
+    pub(crate) width: Option<usize>,
+
+    /// How diagnostics are printed
+    #[clap(long, value_enum, default_value_t = Format::Pretty, global = true)]
+    pub(crate) format: Format,
+
+    /// Additional output as `<format>:<path>` (formats: pretty, short, json, jsonl, sarif, lsp,
+    /// html).
+    /// Can be given multiple times.
+    #[clap(long = "output", value_name = "FORMAT:PATH", global = true)]
+    pub(crate) outputs: Vec<String>,
+
+    /// Lowest diagnostic level that results in a non-zero exit code
+    #[clap(long, value_enum, default_value_t = FailOn::Error, global = true)]
+    pub(crate) fail_on: FailOn,
+
+    /// Exit with a non-zero code if there are more than this many warnings
+    #[clap(long, value_name = "N", global = true)]
+    pub(crate) max_warnings: Option<usize>,
+
+    /// Hide diagnostics below this level
+    #[clap(long, value_enum, default_value_t = Level::Warning, global = true)]
+    pub(crate) min_level: Level,
+
+    /// Also show the info messages of LaTeX and packages. Same as `--min-level info`.
+    #[clap(short, long, global = true)]
+    pub(crate) verbose: bool,
+
+    /// Change the level of diagnostics of a kind (e.g. `font=error`). Can be given multiple
+    /// times.
+    #[clap(long, value_name = "KIND=LEVEL", global = true)]
+    pub(crate) severity: Vec<String>,
+
+    /// Only show diagnostics of these kinds (e.g. `overfull,font,package:hyperref`)
+    #[clap(long, value_delimiter = ',', value_name = "KINDS", global = true)]
+    pub(crate) only: Vec<String>,
+
+    /// Hide diagnostics of these kinds (e.g. `underfull,package:hyperref`)
+    #[clap(long, value_delimiter = ',', value_name = "KINDS", global = true)]
+    pub(crate) ignore: Vec<String>,
+
+    /// Hide diagnostics matching a rule: `kind:<kind>`, `package:<name>`, `message:<regex>` or
+    /// `file:<glob>`. Rules are also read from `.texlogignore`. Can be given multiple times.
+    #[clap(long, value_name = "RULE", global = true)]
+    pub(crate) ignore_rule: Vec<String>,
+
+    /// Hide warnings from these packages (e.g. `wrapfig,microtype`)
+    #[clap(long, value_delimiter = ',', value_name = "PACKAGES", global = true)]
+    pub(crate) ignore_package: Vec<String>,
+
+    /// Only show diagnostics from files matching this glob (e.g. `chapters/*.tex`).
+    /// Can be given multiple times.
+    #[clap(long, value_name = "GLOB", global = true)]
+    pub(crate) path_filter: Vec<String>,
+
+    /// Hide overfull boxes that stick out less than this many points
+    #[clap(long, value_name = "PT", global = true)]
+    pub(crate) min_overfull_pt: Option<f32>,
+
+    /// Hide underfull boxes with a badness below this
+    #[clap(long, value_name = "N", global = true)]
+    pub(crate) min_badness: Option<u32>,
+
+    /// Show repeated diagnostics individually instead of merging them
+    #[clap(long, global = true)]
+    pub(crate) no_dedup: bool,
+
+    /// Order of the printed diagnostics. By default warnings are printed before errors.
+    #[clap(long, value_enum, global = true)]
+    pub(crate) sort: Option<Sort>,
+
+    /// Keep `^^` escaped characters in messages instead of decoding them
+    #[clap(long, global = true)]
+    pub(crate) raw_escapes: bool,
+
+    /// How file paths are reported
+    #[clap(long, value_enum, default_value_t = Paths::Resolved, global = true)]
+    pub(crate) paths: Paths,
+
+    /// Read the terminal output of the engine or latexmk instead of a log file, as in
+    /// `pdflatex main.tex | texlog --console`
+    #[clap(long, global = true)]
+    pub(crate) console: bool,
+
+    /// Report diagnostics from every run in a log with several runs, not just the final one
+    #[clap(long, global = true)]
+    pub(crate) all_runs: bool,
+
+    /// Recorder file used to correct paths in the log. Defaults to the `.fls` file next to the
+    /// log if TeX was run with `-recorder`.
+    #[clap(long, value_name = "FILE", global = true)]
+    pub(crate) fls: Option<String>,
+
+    /// SyncTeX file used to find where diagnostics are in the PDF. Defaults to the
+    /// `.synctex.gz` file next to the log if TeX was run with `-synctex=1`.
+    #[clap(long, value_name = "FILE", global = true)]
+    pub(crate) synctex: Option<String>,
+
+    /// Do not report the diagnostics recorded in this baseline file
+    #[clap(long, value_name = "FILE", global = true)]
+    pub(crate) baseline: Option<String>,
+
+    /// Record the current diagnostics in the baseline file instead of reporting them
+    #[clap(long, requires = "baseline", global = true)]
+    pub(crate) write_baseline: bool,
+
+    /// Report diagnostics on lines marked with a `% texlog: ignore ...` comment in the tex
+    /// sources
+    #[clap(long, global = true)]
+    pub(crate) no_magic_comments: bool,
+
+    /// Print the time spent lexing, parsing and collecting diagnostics to stderr
+    #[clap(long, global = true)]
+    pub(crate) timing: bool,
+}
+
+impl Args {
+    /// The log file given on the command line, for the subcommands that read one
+    pub(crate) fn log_file(&self) -> Option<&str> {
+        match &self.command {
+            Some(Command::Check { files }) => files.first().map(String::as_str),
+            Some(Command::Stats { file })
+            | Some(Command::Packages { file })
+            | Some(Command::Fonts { file })
+            | Some(Command::Tui { file })
+            | Some(Command::Grep { file, .. })
+            | Some(Command::Clean { file })
+            | Some(Command::Tree { file, .. }) => file.as_deref(),
+            Some(Command::Watch { file }) | Some(Command::Trace { file, .. }) => Some(file),
+            Some(Command::Diff { new, .. }) => Some(new),
+            Some(Command::Run { .. }) | Some(Command::Explain { .. }) => None,
+            None => self.files.first().map(String::as_str),
+        }
+    }
+
+    /// Replace the `.tex` files given on the command line by their logs
+    pub(crate) fn with_located_logs(mut self) -> Self {
+        let find = |file: &mut String| *file = locate::log_path(file);
+        match &mut self.command {
+            Some(Command::Check { files }) => files.iter_mut().for_each(find),
+            Some(Command::Stats { file })
+            | Some(Command::Packages { file })
+            | Some(Command::Fonts { file })
+            | Some(Command::Tui { file })
+            | Some(Command::Grep { file, .. })
+            | Some(Command::Clean { file })
+            | Some(Command::Tree { file, .. }) => file.iter_mut().for_each(find),
+            Some(Command::Watch { file }) | Some(Command::Trace { file, .. }) => find(file),
+            Some(Command::Diff { old, new }) => [old, new].into_iter().for_each(find),
+            Some(Command::Run { .. }) | Some(Command::Explain { .. }) | None => {}
+        }
+        self.files.iter_mut().for_each(find);
+        self
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum Command {
+    /// Report the diagnostics of the log. This is the default.
+    Check {
+        /// Latex log files, or directories to search for them. Leave out when piping to read
+        /// from standard input.
+        files: Vec<String>,
+    },
+
+    /// Reprint the diagnostics every time the log file changes
+    Watch {
+        /// Latex log file
+        file: String,
+    },
+
+    /// Print the number of diagnostics by kind and by file
+    Stats {
+        /// Latex log file. Use `-` to read from standard input.
+        file: Option<String>,
+    },
+
+    /// Print the packages that were loaded, with their versions and the files that loaded them
+    Packages {
+        /// Latex log file. Use `-` to read from standard input.
+        file: Option<String>,
+    },
+
+    /// Print the font shapes that were loaded, and the ones that were substituted with others
+    Fonts {
+        /// Latex log file. Use `-` to read from standard input.
+        file: Option<String>,
+    },
+
+    /// Explain what a kind of diagnostic means and how to fix it, like `texlog explain W0004`
+    /// or `texlog explain overfull-hbox`. Lists every kind if none is given.
+    Explain {
+        /// Code or name of the kind
+        kind: Option<String>,
+    },
+
+    /// Print the files that were open at a line of the log, outermost first
+    Trace {
+        /// Latex log file
+        file: String,
+
+        /// Line of the log, optionally with a column as `LINE:COLUMN`
+        #[clap(value_name = "LINE[:COLUMN]")]
+        position: String,
+    },
+
+    /// Search the diagnostics and other messages of the log, printing every matching line with
+    /// the files that were open at it
+    Grep {
+        /// Regular expression to search for
+        pattern: String,
+
+        /// Latex log file. Use `-` to read from standard input.
+        file: Option<String>,
+    },
+
+    /// Print a condensed copy of the log with only the banner, the files that were opened and
+    /// the diagnostics, for attaching to bug reports
+    Clean {
+        /// Latex log file. Use `-` to read from standard input.
+        file: Option<String>,
+    },
+
+    /// Browse the file inclusion tree and its diagnostics interactively
+    Tui {
+        /// Latex log file. Use `-` to read from standard input.
+        file: Option<String>,
+    },
+
+    /// Print the file inclusion tree with the number of diagnostics in every file
+    Tree {
+        /// Latex log file. Use `-` to read from standard input.
+        file: Option<String>,
+
+        /// Leave out files nested deeper than this. The root file has depth 0.
+        #[clap(long, value_name = "N")]
+        depth: Option<usize>,
+
+        /// Print a Graphviz DOT graph, with files colored by their number of diagnostics
+        #[clap(long)]
+        dot: bool,
+    },
+
+    /// Show the diagnostics that were introduced and fixed between two runs. Exits with a
+    /// non-zero code if there are new diagnostics.
+    Diff {
+        /// Log of the earlier run
+        old: String,
+
+        /// Log of the later run
+        new: String,
+    },
+
+    /// Run a TeX engine and report the diagnostics in its log, like `texlog run -- pdflatex
+    /// main.tex`. The engine's exit code is passed on if it fails.
+    Run {
+        /// The engine and its arguments
+        #[clap(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub(crate) enum Format {
+    /// Colored blocks with message and call trace
+    Pretty,
+    /// One `file:line:col: level: title` line per diagnostic
+    Short,
+    /// One JSON object per line for every diagnostic, written as soon as it is found. Pairs
+    /// well with `--follow`.
+    Jsonl,
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub(crate) enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /// Whether colors should be written to standard output
+    pub(crate) fn enabled(&self) -> bool {
+        match self {
+            ColorMode::Auto => {
+                io::stdout().is_terminal()
+                    && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+            }
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        }
+    }
+
+    /// Whether other escape codes, like hyperlinks, may be written to standard output
+    pub(crate) fn allowed(&self) -> bool {
+        match self {
+            ColorMode::Auto => io::stdout().is_terminal(),
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub(crate) enum FailOn {
+    /// Fail if there are any errors
+    Error,
+    /// Fail if there are any warnings or errors
+    Warning,
+    /// Always exit successfully
+    Never,
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub(crate) enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+impl From<Level> for DiagnosticLevel {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Info => DiagnosticLevel::Info,
+            Level::Warning => DiagnosticLevel::Warning,
+            Level::Error => DiagnosticLevel::Error,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub(crate) enum Sort {
+    /// Errors first
+    Severity,
+    /// Grouped by file
+    File,
+    /// Order of appearance in the log
+    Position,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Paths {
+    /// As written in the log
+    Logged,
+    /// Relative to the directory of the log file
+    Resolved,
+    /// Absolute, with symlinks followed
+    Canonical,
+}
+
+impl From<Paths> for PathStyle {
+    fn from(paths: Paths) -> Self {
+        match paths {
+            Paths::Logged => PathStyle::Logged,
+            Paths::Resolved => PathStyle::Resolved,
+            Paths::Canonical => PathStyle::Canonical,
+        }
+    }
+}
+
+impl From<Sort> for SortOrder {
+    fn from(sort: Sort) -> Self {
+        match sort {
+            Sort::Severity => SortOrder::Severity,
+            Sort::File => SortOrder::File,
+            Sort::Position => SortOrder::Position,
+        }
+    }
 }
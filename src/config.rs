@@ -0,0 +1,394 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Name of the configuration file searched for in the project
+pub(crate) const CONFIG_FILE_NAME: &str = "texlog.toml";
+
+/// Name of the ignore file searched for in the project
+pub(crate) const IGNORE_FILE_NAME: &str = ".texlogignore";
+
+/// A value in the supported subset of TOML
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    fn as_cli_arg(&self) -> Option<String> {
+        match self {
+            Value::String(s) => Some(s.clone()),
+            Value::Integer(n) => Some(n.to_string()),
+            Value::Float(n) => Some(n.to_string()),
+            Value::Bool(b) => Some(b.to_string()),
+            Value::Array(_) => None,
+        }
+    }
+}
+
+/// A `key = value` entry and the table it is in
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Entry {
+    pub(crate) table: String,
+    pub(crate) key: String,
+    pub(crate) value: Value,
+}
+
+/// Parser for the small part of TOML used by config files: tables, quoted and bare keys, strings,
+/// numbers, booleans and (multi-line) arrays
+struct TomlParser<'a> {
+    chars: Vec<char>,
+    cursor: usize,
+    source: &'a str,
+}
+
+impl<'a> TomlParser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            chars: source.chars().collect(),
+            cursor: 0,
+            source,
+        }
+    }
+
+    fn current(&self) -> Option<char> {
+        self.chars.get(self.cursor).copied()
+    }
+
+    fn line(&self) -> usize {
+        self.chars[..self.cursor.min(self.chars.len())]
+            .iter()
+            .filter(|c| **c == '\n')
+            .count()
+            + 1
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("line {}: {}", self.line(), message)
+    }
+
+    /// Skip spaces, and newlines and comments if `newlines` is set
+    fn skip_whitespace(&mut self, newlines: bool) {
+        while let Some(c) = self.current() {
+            match c {
+                ' ' | '\t' | '\r' => self.cursor += 1,
+                '\n' if newlines => self.cursor += 1,
+                '#' if newlines => {
+                    while self.current().is_some_and(|c| c != '\n') {
+                        self.cursor += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Expect the end of a line, allowing a trailing comment
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_whitespace(false);
+        match self.current() {
+            None | Some('\n') => Ok(()),
+            Some('#') => {
+                self.skip_whitespace(true);
+                Ok(())
+            }
+            Some(c) => Err(self.error(&format!("unexpected '{}'", c))),
+        }
+    }
+
+    fn parse_key(&mut self) -> Result<String, String> {
+        match self.current() {
+            Some('"') | Some('\'') => self.parse_string(),
+            _ => {
+                let start = self.cursor;
+                while self
+                    .current()
+                    .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-')
+                {
+                    self.cursor += 1;
+                }
+                if start == self.cursor {
+                    return Err(self.error("expected a key"));
+                }
+                Ok(self.chars[start..self.cursor].iter().collect())
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        let quote = self
+            .current()
+            .ok_or_else(|| self.error("expected a string"))?;
+        self.cursor += 1;
+        let mut s = String::new();
+        loop {
+            let c = self
+                .current()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.cursor += 1;
+            match c {
+                '\n' => return Err(self.error("unterminated string")),
+                c if c == quote => return Ok(s),
+                '\\' if quote == '"' => {
+                    let escaped = self
+                        .current()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.cursor += 1;
+                    s.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        '\\' => '\\',
+                        '"' => '"',
+                        c => return Err(self.error(&format!("unknown escape '\\{}'", c))),
+                    });
+                }
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value, String> {
+        match self.current() {
+            Some('"') | Some('\'') => Ok(Value::String(self.parse_string()?)),
+            Some('[') => {
+                self.cursor += 1;
+                let mut items = vec![];
+                loop {
+                    self.skip_whitespace(true);
+                    if self.current() == Some(']') {
+                        self.cursor += 1;
+                        return Ok(Value::Array(items));
+                    }
+                    items.push(self.parse_value()?);
+                    self.skip_whitespace(true);
+                    match self.current() {
+                        Some(',') => self.cursor += 1,
+                        Some(']') => {}
+                        _ => return Err(self.error("expected ',' or ']' in array")),
+                    }
+                }
+            }
+            _ => {
+                let start = self.cursor;
+                while self
+                    .current()
+                    .is_some_and(|c| c.is_alphanumeric() || "-+_.".contains(c))
+                {
+                    self.cursor += 1;
+                }
+                let word: String = self.chars[start..self.cursor].iter().collect();
+                match word.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    w => {
+                        let number = w.replace('_', "");
+                        if let Ok(n) = number.parse() {
+                            Ok(Value::Integer(n))
+                        } else if let Ok(n) = number.parse() {
+                            Ok(Value::Float(n))
+                        } else {
+                            Err(self.error(&format!("invalid value '{}'", w)))
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn parse(mut self) -> Result<Vec<Entry>, String> {
+        let mut entries = vec![];
+        let mut table = String::new();
+        loop {
+            self.skip_whitespace(true);
+            match self.current() {
+                None => return Ok(entries),
+                Some('[') => {
+                    self.cursor += 1;
+                    self.skip_whitespace(false);
+                    table = self.parse_key()?;
+                    self.skip_whitespace(false);
+                    if self.current() != Some(']') {
+                        return Err(self.error("expected ']'"));
+                    }
+                    self.cursor += 1;
+                    self.end_of_line()?;
+                }
+                Some(_) => {
+                    let key = self.parse_key()?;
+                    self.skip_whitespace(false);
+                    if self.current() != Some('=') {
+                        return Err(self.error(&format!("expected '=' after '{}'", key)));
+                    }
+                    self.cursor += 1;
+                    self.skip_whitespace(false);
+                    let value = self.parse_value()?;
+                    self.end_of_line()?;
+                    entries.push(Entry {
+                        table: table.clone(),
+                        key,
+                        value,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Settings loaded from a `texlog.toml` file
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Config {
+    pub(crate) path: PathBuf,
+    pub(crate) entries: Vec<Entry>,
+}
+
+impl Config {
+    pub(crate) fn parse(path: PathBuf, source: &str) -> Result<Self, String> {
+        let entries = TomlParser::new(source)
+            .parse()
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Self { path, entries })
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        Self::parse(path.to_path_buf(), &source)
+    }
+
+    /// Find a file named `name` in `dir` or the closest parent directory
+    pub(crate) fn discover(dir: &Path, name: &str) -> Option<PathBuf> {
+        let dir = dir.canonicalize().ok()?;
+        dir.ancestors().map(|d| d.join(name)).find(|p| p.is_file())
+    }
+
+    /// The command line arguments equivalent to the config
+    pub(crate) fn to_args(&self) -> Result<Vec<String>, String> {
+        let mut args = vec![];
+        for entry in &self.entries {
+            let error = |message: &str| {
+                format!(
+                    "{}: {}{}: {}",
+                    self.path.display(),
+                    match entry.table.as_str() {
+                        "" => "".to_string(),
+                        t => format!("[{}] ", t),
+                    },
+                    entry.key,
+                    message
+                )
+            };
+
+            let values = match &entry.value {
+                Value::Array(items) => items
+                    .iter()
+                    .map(|v| {
+                        v.as_cli_arg()
+                            .ok_or_else(|| error("nested arrays are not supported"))
+                    })
+                    .collect::<Result<Vec<String>, String>>()?,
+                v => vec![v.as_cli_arg().expect("Only arrays have no argument form")],
+            };
+
+            match (entry.table.as_str(), entry.key.as_str()) {
+                ("ignore", field @ ("kinds" | "packages" | "messages" | "files")) => {
+                    for value in values {
+                        args.push(format!(
+                            "--ignore-rule={}:{}",
+                            &field[..field.len() - 1],
+                            value
+                        ));
+                    }
+                }
+                ("severity", pattern) => {
+                    for level in values {
+                        args.push(format!("--severity={}={}", pattern, level));
+                    }
+                }
+                ("", "dedup") => match entry.value {
+                    Value::Bool(true) => {}
+                    Value::Bool(false) => args.push("--no-dedup".to_string()),
+                    _ => return Err(error("expected true or false")),
+                },
+                (
+                    "",
+                    key @ ("quiet" | "verbose" | "summary" | "raw_escapes" | "all_runs"
+                    | "annotate" | "user_files_only"),
+                ) => match entry.value {
+                    Value::Bool(true) => args.push(format!("--{}", key.replace('_', "-"))),
+                    Value::Bool(false) => {}
+                    _ => return Err(error("expected true or false")),
+                },
+                (
+                    "",
+                    key @ ("format" | "min_level" | "only" | "ignore" | "ignore_package"
+                    | "ignore_packages" | "path_filter" | "sort" | "fail_on"
+                    | "max_warnings" | "color" | "hyperlinks" | "trace_depth" | "collapse"
+                    | "context" | "width" | "output" | "min_overfull_pt" | "min_badness"
+                    | "paths" | "fls" | "synctex" | "baseline"),
+                ) => {
+                    let flag = match key {
+                        "ignore_packages" => "ignore-package".to_string(),
+                        key => key.replace('_', "-"),
+                    };
+                    for value in values {
+                        args.push(format!("--{}={}", flag, value));
+                    }
+                }
+                _ => return Err(error("unknown setting")),
+            }
+        }
+        Ok(args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_to_args() {
+        let source = r#"
+# Project defaults
+format = "short"
+ignore_packages = [
+    "wrapfig", # Always warns
+    "microtype",
+]
+max_warnings = 10
+min_overfull_pt = 2.5
+dedup = false
+
+[severity]
+"package:hyperref" = 'error'
+
+[ignore]
+messages = ['^Font shape `OMS']
+"#;
+        let config = Config::parse(PathBuf::from("texlog.toml"), source).unwrap();
+        assert_eq!(
+            config.to_args().unwrap(),
+            vec![
+                "--format=short",
+                "--ignore-package=wrapfig",
+                "--ignore-package=microtype",
+                "--max-warnings=10",
+                "--min-overfull-pt=2.5",
+                "--no-dedup",
+                "--severity=package:hyperref=error",
+                "--ignore-rule=message:^Font shape `OMS",
+            ]
+        );
+    }
+
+    #[test]
+    fn config_errors() {
+        let config = Config::parse(PathBuf::from("texlog.toml"), "colour = \"never\"").unwrap();
+        assert!(config.to_args().is_err());
+        assert!(Config::parse(PathBuf::from("texlog.toml"), "format = \"short").is_err());
+    }
+}
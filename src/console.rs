@@ -0,0 +1,88 @@
+use crate::{
+    error::Result,
+    lexer::MAX_PRINT_LINE,
+    log::Log,
+    parser::parse_text,
+    tectonic::{is_tectonic_output, parse_tectonic},
+    text::SourceText,
+};
+
+/// Starts of the lines that latexmk prints between the runs of the engine
+const LATEXMK_PREFIXES: [&str; 10] = [
+    "Latexmk: ",
+    "Rc files read:",
+    "Run number ",
+    "Running '",
+    "------------",
+    "=== ",
+    "Collected error summary",
+    "For rule '",
+    "Transcript written on ",
+    "Latexmk found ",
+];
+
+/// A line in the `-file-line-error` style, like `./main.tex:6: Undefined control sequence.`,
+/// turned into the `! Undefined control sequence.` form of the default style
+fn plain_error(line: &str) -> Option<String> {
+    let (file, rest) = line.split_once(':')?;
+    let (number, message) = rest.split_once(": ")?;
+    let is_file = file.contains('.') && !file.contains(char::is_whitespace);
+    let is_number = !number.is_empty() && number.chars().all(|c| c.is_ascii_digit());
+    (is_file && is_number).then(|| format!("! {}", message))
+}
+
+/// Parse what an engine, or latexmk running it, prints to the terminal. This mostly matches the
+/// log, but latexmk adds its own progress lines, errors may be written in the
+/// `-file-line-error` style, and lines are often not wrapped. The output of Tectonic, which
+/// reformats the messages of the engine, is recognized and parsed on its own.
+pub fn parse_console(source: SourceText) -> Result<Log> {
+    if is_tectonic_output(source.as_str()) {
+        return Ok(parse_tectonic(source));
+    }
+    let mut text = String::with_capacity(source.as_str().len());
+    let mut wrapped = true;
+    for line in source.as_str().split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        if LATEXMK_PREFIXES.iter().any(|p| content.starts_with(p)) {
+            continue;
+        }
+        if content.chars().count() > MAX_PRINT_LINE && content.len() > MAX_PRINT_LINE {
+            wrapped = false;
+        }
+        match plain_error(content) {
+            Some(error) => {
+                text += &error;
+                text += "\n";
+            }
+            None => text += line,
+        }
+    }
+    parse_text(SourceText::new(text), wrapped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::TexDiagnosticKind;
+
+    #[test]
+    fn console() {
+        let long_path = format!("./{}.tex", "a".repeat(MAX_PRINT_LINE));
+        let text = format!(
+            "Rc files read:\n  NONE\nLatexmk: applying rule 'pdflatex' (because of missing file)\n\
+             (./main.tex ({}\n./main.tex:6: Undefined control sequence.\nl.6 \\dtae\n\n)\n\n\
+             LaTeX Warning: Reference `a' on page 1 undefined on input line 3.\n\n)\n\
+             Latexmk: Errors, so I did not complete making targets\n",
+            long_path
+        );
+        let log = parse_console(SourceText::new(text)).unwrap();
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 2);
+        assert_eq!(ds[1].file().unwrap().to_str(), Some(long_path.as_str()));
+        assert_eq!(ds[1].diagnostic().source_line(), Some(6));
+        assert_eq!(
+            ds[0].diagnostic().kind(),
+            &TexDiagnosticKind::Reference("a".to_string())
+        );
+    }
+}
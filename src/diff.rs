@@ -0,0 +1,80 @@
+use crate::{
+    baseline::DiagnosticKey,
+    log::{Log, TracedTexDiagnostic},
+};
+
+/// The diagnostics that changed between two runs of the same document. Diagnostics are matched
+/// like in a baseline, by their kind, message and file without page and line numbers, so a
+/// warning that moved to another page is still the same warning.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LogDiff {
+    /// Diagnostics of the new log that are not in the old one
+    pub(crate) introduced: Vec<TracedTexDiagnostic>,
+
+    /// Diagnostics of the old log that are not in the new one
+    pub(crate) fixed: Vec<TracedTexDiagnostic>,
+}
+
+impl LogDiff {
+    pub fn new(old: &Log, new: &Log) -> Self {
+        let mut unmatched: Vec<_> = old
+            .get_diagnostics()
+            .into_iter()
+            .map(|d| (DiagnosticKey::from(&d), d))
+            .collect();
+        let mut introduced = vec![];
+        for d in new.get_diagnostics() {
+            let key = DiagnosticKey::from(&d);
+            match unmatched.iter().position(|(k, _)| *k == key) {
+                Some(i) => {
+                    unmatched.remove(i);
+                }
+                None => introduced.push(d),
+            }
+        }
+        Self {
+            introduced,
+            fixed: unmatched.into_iter().map(|(_, d)| d).collect(),
+        }
+    }
+
+    pub fn introduced(&self) -> &[TracedTexDiagnostic] {
+        &self.introduced
+    }
+
+    pub fn fixed(&self) -> &[TracedTexDiagnostic] {
+        &self.fixed
+    }
+
+    /// Both logs have the same diagnostics
+    pub fn is_empty(&self) -> bool {
+        self.introduced.is_empty() && self.fixed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::parse_source, text::SourceText};
+
+    fn log(body: &str) -> Log {
+        let text = format!("(./main.tex\n\n{}\n\n)", body);
+        parse_source(SourceText::new(text)).unwrap()
+    }
+
+    #[test]
+    fn diff() {
+        let old = log("LaTeX Warning: Reference `a' on page 1 undefined on input line 3.\n\nLaTeX Warning: Reference `b' on page 1 undefined on input line 4.");
+        let new = log("LaTeX Warning: Reference `b' on page 1 undefined on input line 4.\n\nLaTeX Warning: Reference `c' on page 2 undefined on input line 9.");
+        let diff = LogDiff::new(&old, &new);
+        assert_eq!(diff.introduced().len(), 1);
+        assert!(diff.introduced()[0].diagnostic().message().contains("`c'"));
+        assert_eq!(diff.fixed().len(), 1);
+        assert!(diff.fixed()[0].diagnostic().message().contains("`a'"));
+        assert!(LogDiff::new(&new, &new).is_empty());
+
+        // The same warning on another page and line
+        let moved = log("LaTeX Warning: Reference `b' on page 3 undefined on input line 14.\n\nLaTeX Warning: Reference `c' on page 4 undefined on input line 19.");
+        assert!(LogDiff::new(&new, &moved).is_empty());
+    }
+}
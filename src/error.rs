@@ -0,0 +1,37 @@
+use std::{fmt::Display, io, path::PathBuf};
+
+/// Errors from reading and parsing logs
+#[derive(Debug)]
+pub enum Error {
+    /// A file could not be read
+    Io { path: PathBuf, source: io::Error },
+
+    /// A file was read but its contents are not valid
+    Parse { path: PathBuf, message: String },
+
+    /// The log never opens the root file of the document, so it is not a TeX log
+    NoRootFile,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io { path, source } => {
+                write!(f, "could not read {}: {}", path.display(), source)
+            }
+            Error::Parse { path, message } => write!(f, "{}: {}", path.display(), message),
+            Error::NoRootFile => write!(f, "the log does not open any file"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
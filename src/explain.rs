@@ -0,0 +1,342 @@
+use std::fmt::Display;
+
+use crate::parser::TexDiagnosticKind;
+
+/// A detailed description of a diagnostic kind, shown by `texlog explain`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explanation {
+    /// Code printed next to diagnostics of the kind, like `W0004`. The letter is the level of the
+    /// kind, and the number is unique across all kinds.
+    pub code: &'static str,
+
+    /// Name of the kind, as in `TexDiagnosticKind::name`
+    pub name: &'static str,
+
+    /// What the diagnostic means and how it is usually fixed
+    pub text: &'static str,
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} ({})\n", self.code, self.name)?;
+        write!(f, "{}", self.text)
+    }
+}
+
+const fn explanation(code: &'static str, name: &'static str, text: &'static str) -> Explanation {
+    Explanation { code, name, text }
+}
+
+/// Explanations of every diagnostic kind
+pub const EXPLANATIONS: [Explanation; 35] = [
+    explanation(
+        "W0001",
+        "font",
+        "LaTeX could not find the font shape the document asked for, and used another one in its
+place. This happens when a font family does not have a combination of series, shape and size,
+like a bold small caps variant or a size between the design sizes.
+
+Load a font package that provides the missing shape, or switch to a scalable font like Latin
+Modern with `\\usepackage{lmodern}`. Warnings about sizes are usually harmless.",
+    ),
+    explanation(
+        "W0002",
+        "package",
+        "A package reported something it considers wrong, but not bad enough to stop. The message
+names the package, and its documentation explains the warning.
+
+Read the message carefully: it often says what option or command is missing.",
+    ),
+    explanation(
+        "W0003",
+        "underfull-hbox",
+        "TeX had to stretch the spaces of a line more than it likes, so the line looks loose. The
+badness measures how much: 10000 is the worst.
+
+Common causes are `\\\\` or `\\newline` at the end of a paragraph, narrow columns, and long words
+that cannot be hyphenated. Remove forced line breaks, rephrase the paragraph, or load
+`microtype` to give TeX more freedom.",
+    ),
+    explanation(
+        "W0004",
+        "overfull-hbox",
+        "A line is wider than the text, so it sticks into the margin by the given amount. TeX found
+no acceptable place to break it, often because of a long word, URL or inline formula.
+
+Let TeX hyphenate the word with `\\-`, break URLs with the `url` or `hyperref` packages, load
+`microtype`, or rephrase the sentence. Overflows of a few points are usually invisible and can
+be hidden with `--min-overfull-pt`.",
+    ),
+    explanation(
+        "W0005",
+        "underfull-vbox",
+        "A page or box had to be stretched vertically more than TeX likes, which leaves large gaps
+between paragraphs. It typically happens when a large float or an unbreakable block does not
+fit on the page and the rest of the page is spread out.
+
+Use `\\raggedbottom` to let pages end short, or move the float.",
+    ),
+    explanation(
+        "W0006",
+        "overfull-vbox",
+        "Something is taller than the space it was put in, like a figure that is taller than the
+text height or a page with too much unbreakable material.
+
+Scale the figure down, for example with `height=0.9\\textheight`, or allow page breaks in the
+material.",
+    ),
+    explanation(
+        "W0007",
+        "pdftex",
+        "pdfTeX reported a problem while writing the PDF, often about an included image or PDF,
+like a newer PDF version than the output or an image inclusion that lost information.
+
+Set `\\pdfminorversion` to the version of the included files, or convert the images.",
+    ),
+    explanation(
+        "E0008",
+        "error",
+        "TeX stopped at an error. In non-interactive mode it tries to recover and continue, but the
+output after the first error can be wrong, and later errors are often caused by the first.
+
+Fix the first error before looking at the others. The `l.N` line of the message shows where in
+the source TeX was when it gave up.",
+    ),
+    explanation(
+        "W0009",
+        "reference",
+        "A `\\ref`, `\\pageref` or `\\cite` points to a key that is not defined, and prints as `??`.
+On the first run this is normal, because the labels are only written to the `.aux` file at the
+end.
+
+Run LaTeX again. If the warning stays, check the spelling of the key, and that the `\\label`
+comes after the `\\caption` in floats.",
+    ),
+    explanation(
+        "W0010",
+        "label",
+        "The same label is defined twice, so references to it point to the last one.
+
+Rename one of the labels. Labels in files that are `\\include`d twice, or in environments that
+are typeset twice like `tabularx`, also cause this.",
+    ),
+    explanation(
+        "W0011",
+        "rerun",
+        "Labels or citations changed during this run, so references may point to the wrong place.
+
+Run LaTeX again, or use `latexmk`, which runs LaTeX until the references settle.",
+    ),
+    explanation(
+        "W0012",
+        "class",
+        "The document class reported something it considers wrong. The documentation of the class
+explains the warning.",
+    ),
+    explanation(
+        "E0013",
+        "class-error",
+        "The document class found an error, like an unknown option or a command used in the wrong
+place. The help text after the message usually says what the class expected.",
+    ),
+    explanation(
+        "E0014",
+        "package-error",
+        "A package found an error, like an unknown option, a missing argument or a clash with
+another package. The help text after the message, and the package documentation, usually say
+how to fix it.",
+    ),
+    explanation(
+        "E0015",
+        "pdftex-error",
+        "pdfTeX could not write part of the PDF, usually because an included file is missing or
+broken. Check that the file exists and that it opens in other programs.",
+    ),
+    explanation(
+        "E0016",
+        "missing-font",
+        "A font file is not installed. The document uses a font that the TeX distribution cannot
+find, or that the engine cannot load.
+
+Install the font, or the TeX package that provides it. With XeLaTeX and LuaLaTeX, check the
+name given to `\\setmainfont` against the installed system fonts.",
+    ),
+    explanation(
+        "W0017",
+        "hyperref-token",
+        "hyperref had to drop a command from a PDF string, like a bookmark or the document title,
+because PDF strings can only hold text.
+
+Give a plain text version with `\\texorpdfstring{TeX version}{text version}`.",
+    ),
+    explanation(
+        "W0018",
+        "duplicate-destination",
+        "Two places in the document got the same link target, so links may jump to the wrong one.
+This is usually caused by page or figure counters that are reset, like a title page and the
+first page both being page 1.
+
+Use `\\frontmatter` and `\\mainmatter`, or the `hypertexnames=false` option of hyperref.",
+    ),
+    explanation(
+        "E0019",
+        "runaway",
+        "The argument of a command did not end where TeX expected, usually because of a missing `}`.
+TeX read on until a blank line or the end of the file.
+
+Look for an unbalanced brace at the start of the argument shown in the message.",
+    ),
+    explanation(
+        "E0020",
+        "fatal",
+        "TeX stopped without producing output. A fatal error is often caused by an earlier error,
+or by a missing `\\end{document}`.
+
+Fix the first error in the log.",
+    ),
+    explanation(
+        "E0021",
+        "undefined-control-sequence",
+        "A command is used that is not defined. It is either misspelled, or it comes from a package
+that is not loaded.
+
+Check the spelling of the command shown at the end of the `l.N` line, and load the package
+that defines it.",
+    ),
+    explanation(
+        "E0022",
+        "lua-error",
+        "Lua code run by LuaTeX failed. The message holds the Lua error and traceback. If it comes
+from a package, updating the TeX distribution often helps.",
+    ),
+    explanation(
+        "W0023",
+        "missing-character",
+        "The current font has no glyph for a character, so the character is left out of the PDF.
+
+Use a font that covers the character, for example with `fontspec` in XeLaTeX or LuaLaTeX, or
+load `fontenc` and `inputenc` with pdfLaTeX.",
+    ),
+    explanation(
+        "W0024",
+        "xdvipdfmx",
+        "xdvipdfmx, which turns the output of XeTeX into a PDF, reported a problem, usually with
+an included image or font.",
+    ),
+    explanation(
+        "E0025",
+        "xdvipdfmx-error",
+        "xdvipdfmx could not turn the output of XeTeX into a PDF. Check the included images and
+fonts named in the message.",
+    ),
+    explanation(
+        "W0026",
+        "biber",
+        "Biber reported a problem with the bibliography, like a missing field or an entry that is
+cited but not found in the `.bib` files.",
+    ),
+    explanation(
+        "E0027",
+        "biber-error",
+        "Biber could not process the bibliography, often because of a syntax error in a `.bib`
+file or a version mismatch between Biber and biblatex.",
+    ),
+    explanation(
+        "W0028",
+        "index",
+        "makeindex or xindy reported a problem with an index entry, usually a malformed `\\index`
+command.",
+    ),
+    explanation(
+        "E0029",
+        "index-error",
+        "makeindex or xindy could not build the index. Check the style file and the entries named
+in the message.",
+    ),
+    explanation(
+        "E0030",
+        "missing-file",
+        "TeX could not find a file the document asked for: a package, class, image or included
+file.
+
+Check the file name. Packages and classes are installed with the package manager of the TeX
+distribution, like `tlmgr install` or the MiKTeX console.",
+    ),
+    explanation(
+        "E0031",
+        "capacity",
+        "TeX ran out of one of its memory pools. A pool for nesting, like the input stack or the
+grouping levels, usually runs out because of a macro that calls itself forever. Other pools
+can be made larger in `texmf.cnf` for large documents.",
+    ),
+    explanation(
+        "W0032",
+        "latex",
+        "The LaTeX kernel reported a problem, like a float that is too large for the page or text
+dropped from a page. The message describes what happened.",
+    ),
+    explanation(
+        "I0033",
+        "latex-info",
+        "An informational message from the LaTeX kernel, like a command being redefined. It is only
+shown with `--verbose`.",
+    ),
+    explanation(
+        "I0034",
+        "package-info",
+        "An informational message from a package. It is only shown with `--verbose`.",
+    ),
+    explanation(
+        "I0035",
+        "class-info",
+        "An informational message from the document class. It is only shown with `--verbose`.",
+    ),
+];
+
+/// The explanation for a code like `W0004` or a kind name like `overfull-hbox`
+pub fn explain(query: &str) -> Option<&'static Explanation> {
+    let query = query.trim();
+    EXPLANATIONS
+        .iter()
+        .find(|e| e.code.eq_ignore_ascii_case(query) || e.name.eq_ignore_ascii_case(query))
+}
+
+impl TexDiagnosticKind {
+    /// Code of the kind for `texlog explain`, like `W0004`
+    pub fn code(&self) -> &'static str {
+        explain(self.name()).map_or("", |e| e.code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::DiagnosticLevel;
+
+    #[test]
+    fn explanations() {
+        assert_eq!(explain("overfull-hbox"), explain("w0004"));
+        let kind = TexDiagnosticKind::Reference("a".to_string());
+        assert_eq!(kind.code(), "W0009");
+        assert_eq!(explain("nonsense"), None);
+
+        // Codes are numbered in order, and every kind is explained once
+        for (i, e) in EXPLANATIONS.iter().enumerate() {
+            assert_eq!(e.code[1..].parse::<usize>(), Ok(i + 1));
+            assert_eq!(EXPLANATIONS.iter().filter(|o| o.name == e.name).count(), 1);
+        }
+
+        // The letter of a code is the level of the kind
+        let kinds = [
+            (TexDiagnosticKind::Fatal, DiagnosticLevel::Error),
+            (TexDiagnosticKind::LatexInfo, DiagnosticLevel::Info),
+            (TexDiagnosticKind::Rerun, DiagnosticLevel::Warning),
+        ];
+        for (kind, level) in kinds {
+            assert_eq!(kind.level(), level);
+            assert!(kind
+                .code()
+                .starts_with(&level.to_string()[..1].to_uppercase()));
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! C functions for embedding texlog in editors, declared in `include/texlog.h`. Build the
+//! library for them with
+//!
+//! ```sh
+//! cargo rustc --lib --release --features ffi --crate-type cdylib
+//! ```
+//!
+//! The parse functions return the document of the `json` output format as a string that the
+//! caller owns and frees with `texlog_free_string`, or null if the log could not be read, in
+//! which case `texlog_last_error` tells why.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    path::Path,
+    ptr,
+};
+
+use crate::{log::Log, parser::parse_source, text::SourceText};
+
+thread_local! {
+    /// Message of the error of the last call on this thread, if it failed
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Remember the error of a call for `texlog_last_error`, and return null
+fn fail(message: &str) -> *mut c_char {
+    LAST_ERROR.with(|e| *e.borrow_mut() = CString::new(message).ok());
+    ptr::null_mut()
+}
+
+/// The JSON of a log as a string for C, or null
+fn into_c_string(log: crate::Result<Log>) -> *mut c_char {
+    match log.map(|log| CString::new(log.to_json())) {
+        Ok(Ok(json)) => {
+            LAST_ERROR.with(|e| *e.borrow_mut() = None);
+            json.into_raw()
+        }
+        Ok(Err(e)) => fail(&e.to_string()),
+        Err(e) => fail(&e.to_string()),
+    }
+}
+
+/// Parse the text of a log.
+///
+/// # Safety
+///
+/// `text` must be null or a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn texlog_parse(text: *const c_char) -> *mut c_char {
+    if text.is_null() {
+        return fail("the text is null");
+    }
+    let text = CStr::from_ptr(text).to_string_lossy().into_owned();
+    into_c_string(parse_source(SourceText::new(text)))
+}
+
+/// Read and parse a log file, like `texlog <file>` does.
+///
+/// # Safety
+///
+/// `path` must be null or a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn texlog_parse_file(path: *const c_char) -> *mut c_char {
+    if path.is_null() {
+        return fail("the path is null");
+    }
+    match CStr::from_ptr(path).to_str() {
+        Ok(path) => into_c_string(Log::from_path(Path::new(path))),
+        Err(_) => fail("the path is not valid UTF-8"),
+    }
+}
+
+/// Why the last parse on this thread returned null, or null if it succeeded. The string belongs
+/// to texlog and is valid until the next parse on the thread.
+#[no_mangle]
+pub extern "C" fn texlog_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// Free a string returned by texlog.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by texlog that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn texlog_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let text =
+            CString::new("(./main.tex\n! Undefined control sequence.\nl.3 \\foo\n\n)").unwrap();
+        unsafe {
+            let json = texlog_parse(text.as_ptr());
+            assert!(!json.is_null());
+            assert!(texlog_last_error().is_null());
+            let s = CStr::from_ptr(json).to_str().unwrap();
+            assert!(s.contains("\"errors\":1"));
+            texlog_free_string(json);
+
+            assert!(texlog_parse(ptr::null()).is_null());
+            let missing = CString::new("/nonexistent/main.log").unwrap();
+            assert!(texlog_parse_file(missing.as_ptr()).is_null());
+            let error = CStr::from_ptr(texlog_last_error()).to_str().unwrap();
+            assert!(error.starts_with("could not read /nonexistent/main.log"));
+        }
+    }
+
+    /// The C declaration of an `extern "C"` function, from its signature after `fn`
+    fn c_declaration(signature: &str) -> String {
+        let c_type = |rust_type: &str| match rust_type.trim() {
+            "*const c_char" => "const char *",
+            "*mut c_char" => "char *",
+            "" => "void ",
+            other => panic!("no C type for {}", other),
+        };
+        let (function, returns) = signature.split_once(" -> ").unwrap_or((signature, ""));
+        let (name, arguments) = function.trim_end_matches(')').split_once('(').unwrap();
+        let arguments = arguments
+            .split(',')
+            .filter(|a| !a.trim().is_empty())
+            .map(|a| {
+                let (name, rust_type) = a.split_once(':').unwrap();
+                format!("{}{}", c_type(rust_type), name.trim())
+            })
+            .collect::<Vec<_>>();
+        let arguments = match arguments.is_empty() {
+            true => "void".to_string(),
+            false => arguments.join(", "),
+        };
+        format!("{}{}({});", c_type(returns), name, arguments)
+    }
+
+    #[test]
+    fn header() {
+        let source = include_str!("ffi.rs").split("#[cfg(test)]").next().unwrap();
+        let header = include_str!("../include/texlog.h");
+        let declarations: Vec<String> = source
+            .split("extern \"C\" fn ")
+            .skip(1)
+            .map(|f| {
+                let signature = f.split(" {").next().unwrap();
+                c_declaration(&signature.split_whitespace().collect::<Vec<_>>().join(" "))
+            })
+            .collect();
+        for declaration in &declarations {
+            assert!(
+                header.lines().any(|l| l == declaration),
+                "include/texlog.h does not declare {}",
+                declaration
+            );
+        }
+        // Every function in the header is defined
+        let declared = header
+            .lines()
+            .filter(|l| l.contains("texlog_") && l.ends_with(");"));
+        assert_eq!(declared.count(), declarations.len());
+    }
+}
@@ -0,0 +1,298 @@
+use std::str::FromStr;
+
+use crate::{
+    log::TracedTexDiagnostic,
+    parser::{DiagnosticLevel, TexDiagnosticKind},
+    regex::Regex,
+};
+
+/// Matches diagnostic kinds by name, like `overfull` or `package:hyperref`
+#[derive(Clone, Debug, PartialEq)]
+pub struct KindPattern {
+    /// Kind name or a prefix of it, like `overfull` for `overfull-hbox`
+    name: String,
+
+    /// Matched against the kind argument (e.g. the package name) if given
+    argument: Option<String>,
+}
+
+impl FromStr for KindPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, argument) = match s.split_once(':') {
+            Some((name, argument)) => (name, Some(argument.trim().to_string())),
+            None => (s, None),
+        };
+        let name = name.trim().to_lowercase();
+        if name.is_empty() {
+            return Err(format!("empty kind in pattern '{}'", s));
+        }
+        Ok(Self { name, argument })
+    }
+}
+
+impl KindPattern {
+    pub fn matches(&self, kind: &TexDiagnosticKind) -> bool {
+        // Warnings split out of the package warnings, like hyperref's PDF string tokens, still
+        // belong to their package
+        if let ("package", Some(pattern), Some(package)) =
+            (self.name.as_str(), &self.argument, kind.package())
+        {
+            return pattern.eq_ignore_ascii_case(package);
+        }
+        let name = kind.name();
+        let name_matches = name == self.name || name.starts_with(&format!("{}-", self.name));
+        let argument_matches = match (&self.argument, kind.argument()) {
+            (None, _) => true,
+            (Some(pattern), Some(argument)) => pattern.eq_ignore_ascii_case(argument),
+            (Some(_), None) => false,
+        };
+        name_matches && argument_matches
+    }
+}
+
+/// A project-level rule that hides matching diagnostics, written as `<field>:<value>`
+#[derive(Clone, Debug, PartialEq)]
+pub enum IgnoreRule {
+    /// `kind:overfull`, or a bare kind pattern
+    Kind(KindPattern),
+    /// `package:wrapfig`
+    Package(String),
+    /// `message:<regex>`
+    Message(Regex),
+    /// `file:<glob>`, matched against the file that emitted the diagnostic
+    File(String),
+}
+
+impl FromStr for IgnoreRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.split_once(':') {
+            Some(("kind", pattern)) => Ok(IgnoreRule::Kind(pattern.parse()?)),
+            Some(("package", name)) => Ok(IgnoreRule::Package(name.trim().to_string())),
+            Some(("message", regex)) => Ok(IgnoreRule::Message(Regex::new(regex.trim())?)),
+            Some(("file", glob)) => Ok(IgnoreRule::File(glob.trim().to_string())),
+            _ => Ok(IgnoreRule::Kind(s.parse()?)),
+        }
+    }
+}
+
+impl IgnoreRule {
+    pub fn matches(&self, d: &TracedTexDiagnostic) -> bool {
+        let kind = &d.diagnostic().kind;
+        match self {
+            IgnoreRule::Kind(pattern) => pattern.matches(kind),
+            IgnoreRule::Package(name) => {
+                kind.package().is_some_and(|p| p.eq_ignore_ascii_case(name))
+            }
+            IgnoreRule::Message(regex) => regex.is_match(&d.diagnostic().message),
+            IgnoreRule::File(glob) => d
+                .file()
+                .is_some_and(|f| glob_match(glob, &f.to_string_lossy())),
+        }
+    }
+
+    /// Parse the rules of an ignore file, with one rule per line and `#` comments
+    pub fn parse_file(source: &str) -> Result<Vec<Self>, String> {
+        source
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(i, line)| line.parse().map_err(|e| format!("line {}: {}", i + 1, e)))
+            .collect()
+    }
+}
+
+/// Match a path against a glob pattern. `*` and `?` do not match `/`, while `**` matches any
+/// number of directories.
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    fn matches(pattern: &[char], path: &[char]) -> bool {
+        match pattern {
+            [] => path.is_empty(),
+            ['*', '*', '/', rest @ ..] => {
+                matches(rest, path)
+                    || path
+                        .iter()
+                        .position(|c| *c == '/')
+                        .is_some_and(|i| matches(pattern, &path[i + 1..]))
+            }
+            ['*', '*', rest @ ..] => (0..=path.len()).any(|i| matches(rest, &path[i..])),
+            ['*', rest @ ..] => (0..=path.len())
+                .take_while(|i| *i == 0 || path[i - 1] != '/')
+                .any(|i| matches(rest, &path[i..])),
+            ['?', rest @ ..] => {
+                path.first().is_some_and(|c| *c != '/') && matches(rest, &path[1..])
+            }
+            [c, rest @ ..] => path.first() == Some(c) && matches(rest, &path[1..]),
+        }
+    }
+
+    let normalize = |s: &str| -> Vec<char> { s.trim_start_matches("./").chars().collect() };
+    matches(&normalize(pattern), &normalize(path))
+}
+
+/// Rules deciding which diagnostics are reported
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiagnosticFilter {
+    /// Diagnostics below this level are hidden
+    pub min_level: DiagnosticLevel,
+
+    /// If not empty, only diagnostics matching one of these are shown
+    pub only: Vec<KindPattern>,
+
+    /// Diagnostics matching any of these are hidden
+    pub ignore: Vec<KindPattern>,
+
+    /// Package warnings from these packages are hidden
+    pub ignored_packages: Vec<String>,
+
+    /// If not empty, only diagnostics emitted in a file matching one of these globs are shown
+    pub path_patterns: Vec<String>,
+
+    /// Merge diagnostics with the same kind and message into the first occurrence
+    pub dedup: bool,
+
+    /// Change the level of diagnostics matching a pattern. The last matching override wins.
+    pub level_overrides: Vec<(KindPattern, DiagnosticLevel)>,
+
+    /// Diagnostics matching any of these project rules are hidden
+    pub ignore_rules: Vec<IgnoreRule>,
+
+    /// Boxes that stick out less than this many points are hidden
+    pub min_overfull_pt: Option<f32>,
+
+    /// Boxes with a badness below this are hidden
+    pub min_badness: Option<u32>,
+}
+
+impl Default for DiagnosticFilter {
+    fn default() -> Self {
+        Self {
+            min_level: DiagnosticLevel::Warning,
+            only: vec![],
+            ignore: vec![],
+            ignored_packages: vec![],
+            path_patterns: vec![],
+            dedup: false,
+            level_overrides: vec![],
+            ignore_rules: vec![],
+            min_overfull_pt: None,
+            min_badness: None,
+        }
+    }
+}
+
+impl DiagnosticFilter {
+    /// The level of a diagnostic after applying overrides
+    pub fn level_of(&self, kind: &TexDiagnosticKind) -> DiagnosticLevel {
+        self.level_overrides
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.matches(kind))
+            .map(|(_, level)| level.clone())
+            .unwrap_or_else(|| kind.level())
+    }
+
+    /// Returns `true` if the diagnostic should be reported
+    pub fn matches(&self, d: &TracedTexDiagnostic) -> bool {
+        let kind = &d.diagnostic().kind;
+        d.diagnostic().level() >= self.min_level
+            && (self.only.is_empty() || self.only.iter().any(|p| p.matches(kind)))
+            && !self.ignore.iter().any(|p| p.matches(kind))
+            && !self.ignores_package(kind)
+            && self.matches_path(d)
+            && !self.ignore_rules.iter().any(|r| r.matches(d))
+            && self.meets_thresholds(d)
+    }
+
+    fn meets_thresholds(&self, d: &TracedTexDiagnostic) -> bool {
+        let overflow_ok = match (self.min_overfull_pt, d.diagnostic().overflow_pt()) {
+            (Some(min), Some(overflow)) => overflow >= min,
+            _ => true,
+        };
+        let badness_ok = match (self.min_badness, d.diagnostic().badness()) {
+            (Some(min), Some(badness)) => badness >= min,
+            _ => true,
+        };
+        overflow_ok && badness_ok
+    }
+
+    fn matches_path(&self, d: &TracedTexDiagnostic) -> bool {
+        if self.path_patterns.is_empty() {
+            return true;
+        }
+        let Some(file) = d.file() else {
+            return false;
+        };
+        let file = file.to_string_lossy();
+        self.path_patterns.iter().any(|p| glob_match(p, &file))
+    }
+
+    fn ignores_package(&self, kind: &TexDiagnosticKind) -> bool {
+        kind.package().is_some_and(|name| {
+            self.ignored_packages
+                .iter()
+                .any(|p| p.eq_ignore_ascii_case(name))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_patterns() {
+        let overfull: KindPattern = "overfull".parse().unwrap();
+        assert!(overfull.matches(&TexDiagnosticKind::OverfullHbox {
+            overflow_pt: 1.0,
+            lines: None
+        }));
+        assert!(!overfull.matches(&TexDiagnosticKind::UnderfullHbox {
+            badness: 10000,
+            lines: None
+        }));
+
+        let hyperref: KindPattern = "package:hyperref".parse().unwrap();
+        assert!(hyperref.matches(&TexDiagnosticKind::Package("hyperref".to_string())));
+        assert!(!hyperref.matches(&TexDiagnosticKind::Package("wrapfig".to_string())));
+        assert!(hyperref.matches(&TexDiagnosticKind::PdfStringToken("math shift".to_string())));
+        let font = TexDiagnosticKind::Font {
+            requested: "OT1/cmr/m/scit".to_string(),
+            substituted: "OT1/cmr/m/sc".to_string(),
+        };
+        assert!(!hyperref.matches(&font));
+        assert!("font:OT1/cmr/m/scit"
+            .parse::<KindPattern>()
+            .unwrap()
+            .matches(&font));
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob_match("chapters/*.tex", "./chapters/intro.tex"));
+        assert!(!glob_match("chapters/*.tex", "./chapters/part/intro.tex"));
+        assert!(glob_match("chapters/**/*.tex", "chapters/part/intro.tex"));
+        assert!(glob_match("chapters/**/*.tex", "chapters/intro.tex"));
+        assert!(glob_match(
+            "**/*.sty",
+            "/usr/share/texmf/tex/latex/hyperref.sty"
+        ));
+        assert!(glob_match("main.te?", "./main.tex"));
+        assert!(!glob_match("main.tex", "./sections/main.tex"));
+    }
+
+    #[test]
+    fn ignore_file() {
+        let rules = IgnoreRule::parse_file(
+            "# Noise\nunderfull\npackage:wrapfig\nmessage:^Font shape\nfile:chapters/*.tex\n",
+        )
+        .unwrap();
+        assert_eq!(rules.len(), 4);
+        assert_eq!(rules[1], IgnoreRule::Package("wrapfig".to_string()));
+        assert!(IgnoreRule::parse_file("message:(").is_err());
+    }
+}
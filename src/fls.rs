@@ -0,0 +1,154 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{Error, Result},
+    log::Log,
+    parser::Node,
+};
+
+/// The files read and written by a run, from the `.fls` file that TeX writes when run with
+/// `-recorder`. Every line has the form `PWD /home/user/thesis`, `INPUT ./main.tex` or
+/// `OUTPUT main.log`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recorder {
+    /// Directory TeX ran in
+    pub(crate) pwd: Option<PathBuf>,
+
+    /// Files read during the run, in the order they were first opened
+    pub(crate) inputs: Vec<String>,
+
+    /// Files written during the run
+    pub(crate) outputs: Vec<String>,
+}
+
+impl Recorder {
+    pub fn parse(source: &str) -> Self {
+        let mut recorder = Recorder::default();
+        for line in source.lines() {
+            let line = line.trim_end_matches('\r');
+            if let Some(pwd) = line.strip_prefix("PWD ") {
+                recorder.pwd = Some(PathBuf::from(pwd));
+            } else if let Some(input) = line.strip_prefix("INPUT ") {
+                if !recorder.inputs.iter().any(|i| i == input) {
+                    recorder.inputs.push(input.to_string());
+                }
+            } else if let Some(output) = line.strip_prefix("OUTPUT ") {
+                if !recorder.outputs.iter().any(|o| o == output) {
+                    recorder.outputs.push(output.to_string());
+                }
+            }
+        }
+        recorder
+    }
+
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(Self::parse(&source))
+    }
+
+    /// The recorded input that a path from the log was cut short from, like
+    /// `./chapters/my chapter.tex` for `./chapters/my`. Returns `None` if the path is complete or
+    /// does not match exactly one input.
+    pub fn correct(&self, logged: &str) -> Option<String> {
+        let strip = |p: &str| p.strip_prefix("./").unwrap_or(p).to_string();
+        let logged_stripped = strip(logged);
+        if self.inputs.iter().any(|i| strip(i) == logged_stripped) {
+            return None;
+        }
+
+        // The lexer stops paths at whitespace, so the rest of the input starts with it
+        let mut candidates = self.inputs.iter().filter(|i| {
+            strip(i)
+                .strip_prefix(&logged_stripped)
+                .and_then(|rest| rest.chars().next())
+                .is_some_and(char::is_whitespace)
+        });
+        let input = candidates.next()?;
+        if candidates.next().is_some() {
+            return None;
+        }
+
+        match logged.starts_with("./") && !input.starts_with("./") && !input.starts_with('/') {
+            true => Some(format!("./{}", input)),
+            false => Some(input.clone()),
+        }
+    }
+
+    fn correct_node(&self, node: &mut Node) {
+        if let Some(file) = self.correct(&node.file) {
+            node.rename(file);
+        }
+        for call in &mut node.calls {
+            self.correct_node(call);
+        }
+    }
+}
+
+impl Log {
+    /// The `.fls` file next to the log, if TeX was run with `-recorder`
+    pub fn recorder_path(&self) -> Option<PathBuf> {
+        let path = self.path.as_ref()?.with_extension("fls");
+        path.is_file().then_some(path)
+    }
+
+    /// Correct the paths of the files in the log that were cut short, using the inputs recorded
+    /// in the `.fls` file
+    pub fn with_recorder(mut self, recorder: &Recorder) -> Self {
+        recorder.correct_node(&mut self.root_node);
+        for run in &mut self.runs {
+            recorder.correct_node(run);
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::parse_source, text::SourceText};
+
+    const FLS: &str = "PWD /home/user/thesis
+INPUT /usr/share/texlive/texmf-dist/web2c/texmf.cnf
+INPUT ./main.tex
+OUTPUT main.log
+INPUT ./chapters/my chapter.tex
+INPUT ./chapters/my chapter.tex
+INPUT /usr/share/texlive/texmf-dist/tex/latex/base/article.cls
+";
+
+    #[test]
+    fn parse() {
+        let recorder = Recorder::parse(FLS);
+        assert_eq!(recorder.pwd, Some(PathBuf::from("/home/user/thesis")));
+        assert_eq!(recorder.inputs.len(), 4);
+        assert_eq!(recorder.outputs, vec!["main.log"]);
+    }
+
+    #[test]
+    fn correct_paths() {
+        let recorder = Recorder::parse(FLS);
+        assert_eq!(
+            recorder.correct("./chapters/my"),
+            Some("./chapters/my chapter.tex".to_string())
+        );
+        assert_eq!(recorder.correct("./main.tex"), None);
+        assert_eq!(recorder.correct("./chapters/other.tex"), None);
+
+        let text = "(./main.tex (./chapters/my chapter.tex\n\nLaTeX Warning: Reference `a' on page 1 undefined on input line 3.\n\n))";
+        let log = parse_source(SourceText::new(text.to_string()))
+            .unwrap()
+            .with_recorder(&recorder);
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 1);
+        assert_eq!(
+            ds[0].source_file(),
+            Some(Path::new("./chapters/my chapter.tex"))
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    thread,
+    time::Duration,
+};
+
+use texlog::{
+    filter::DiagnosticFilter,
+    output::{OutputSink, Summary},
+    stream::LogStream,
+};
+
+use crate::cli::Args;
+
+/// Time to wait for the log to grow
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Report the diagnostics of every complete block of text that has been read, or of all of it
+/// if `all` is set
+fn scan(
+    stream: &mut LogStream,
+    all: bool,
+    sinks: &mut [Box<dyn OutputSink>],
+    filter: &DiagnosticFilter,
+) -> io::Result<Summary> {
+    let diagnostics: Vec<_> = stream
+        .scan(all)
+        .into_iter()
+        .filter(|d| filter.matches(d))
+        .collect();
+    for d in &diagnostics {
+        for sink in sinks.iter_mut() {
+            sink.diagnostic(d)?;
+        }
+    }
+    Ok(Summary::from_diagnostics(&diagnostics))
+}
+
+/// Report diagnostics as they are written to a growing log file. Stops when the document's root
+/// file is closed.
+pub(crate) fn follow(args: &Args, sinks: &mut [Box<dyn OutputSink>]) -> ExitCode {
+    let path = match args.log_file() {
+        Some("-") | None => {
+            eprintln!("Error: --follow requires a log file.");
+            return ExitCode::FAILURE;
+        }
+        Some(path) => Path::new(path),
+    };
+
+    let filter = match crate::build_filter(args) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut file = loop {
+        match File::open(path) {
+            Ok(f) => break f,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                eprintln!("Error: could not open {}: {}", path.display(), e);
+                return ExitCode::FAILURE;
+            }
+        }
+    };
+
+    let base_dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let mut stream = LogStream::new(base_dir)
+        .with_paths(args.paths.into())
+        .with_raw_escapes(args.raw_escapes);
+    let mut summary = Summary::default();
+    let mut buf = vec![0; 8192];
+    let result = loop {
+        let n = match file.read(&mut buf) {
+            Ok(n) => n,
+            Err(e) => break Err(e),
+        };
+        if n == 0 {
+            thread::sleep(POLL_INTERVAL);
+            continue;
+        }
+        stream.feed(&buf[..n]);
+
+        match scan(&mut stream, false, sinks, &filter) {
+            Ok(s) => {
+                summary.warnings += s.warnings;
+                summary.errors += s.errors;
+            }
+            Err(e) => break Err(e),
+        }
+
+        if stream.finished() {
+            break scan(&mut stream, true, sinks, &filter).map(|s| {
+                summary.warnings += s.warnings;
+                summary.errors += s.errors;
+            });
+        }
+    };
+
+    let result = result.and_then(|_| sinks.iter_mut().try_for_each(|sink| sink.summary(&summary)));
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    crate::exit_code(args, &summary)
+}
@@ -0,0 +1,156 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    log::Log,
+    parser::{Node, TexDiagnosticKind},
+};
+
+/// A font shape that the document asked for, from the font messages of LaTeX
+#[derive(Clone, Debug, PartialEq)]
+pub struct FontUse {
+    /// Font shape in NFSS notation, like `T1/lmr/m/n`, with the size appended if it matters
+    pub shape: String,
+
+    /// The shape that was used instead, if the requested one is not available
+    pub substituted: Option<String>,
+
+    pub file: PathBuf,
+    pub line: Option<usize>,
+}
+
+impl Display for FontUse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.shape)?;
+        if let Some(substituted) = &self.substituted {
+            write!(f, " -> {}", substituted)?;
+        }
+        write!(f, " ({}", self.file.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Every text between `` ` `` and `'` in a message
+fn quoted(text: &str) -> Vec<&str> {
+    text.split('`')
+        .skip(1)
+        .filter_map(|s| s.split_once('\'').map(|(q, _)| q))
+        .collect()
+}
+
+/// Every text between `<` and `>` in a message
+fn sizes(text: &str) -> Vec<&str> {
+    text.split('<')
+        .skip(1)
+        .filter_map(|s| s.split_once('>').map(|(q, _)| q))
+        .collect()
+}
+
+/// The `42` of `on input line 42`
+fn input_line(text: &str) -> Option<usize> {
+    let start = text.find("on input line ")? + "on input line ".len();
+    let digits: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+/// The requested and substituted shape of a font message, with the `(Font)` continuation lines
+/// joined to the first one
+fn parse_message(text: &str, file: &Path) -> Option<FontUse> {
+    let (shape, substituted) =
+        if let Some(rest) = text.split("Trying to load font information for ").nth(1) {
+            let family = rest.split_whitespace().next()?;
+            (family.replace('+', "/"), None)
+        } else if text.contains("Font shape `") {
+            let shapes = quoted(text);
+            let sizes = sizes(text);
+            match (shapes.as_slice(), sizes.as_slice()) {
+                // Font shape `A' in size <10.95> not available, size <10> substituted
+                ([shape], [requested, used]) if text.contains("substituted") => (
+                    format!("{}/{}", shape, requested),
+                    Some(format!("{}/{}", shape, used)),
+                ),
+                // Font shape `A' undefined, using `B' instead, or `B' tried instead
+                ([shape, used, ..], _) => (shape.to_string(), Some(used.to_string())),
+                _ => return None,
+            }
+        } else {
+            return None;
+        };
+    Some(FontUse {
+        shape,
+        substituted,
+        file: file.to_path_buf(),
+        line: input_line(text),
+    })
+}
+
+/// Add the fonts of a node and the files it calls
+fn collect(node: &Node, fonts: &mut Vec<FontUse>) {
+    let file = Path::new(node.file());
+    let mut lines = node.messages().lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some(message) = line.strip_prefix("LaTeX Font Info:") else {
+            continue;
+        };
+        let mut message = message.trim().to_string();
+        while let Some(continued) = lines.next_if(|l| l.starts_with("(Font)")) {
+            message += " ";
+            message += continued["(Font)".len()..].trim();
+        }
+        fonts.extend(parse_message(&message, file));
+    }
+    for d in node.diagnostics() {
+        if matches!(d.kind(), TexDiagnosticKind::Font { .. }) {
+            let message = d.message().replace("(Font)", "");
+            fonts.extend(parse_message(&message, file));
+        }
+    }
+    for call in &node.calls {
+        collect(call, fonts);
+    }
+}
+
+impl Log {
+    /// The font shapes that were loaded or substituted in the final run, each once
+    pub fn fonts(&self) -> Vec<FontUse> {
+        let mut fonts = vec![];
+        collect(self.root_node(), &mut fonts);
+        let base_dir = self.base_dir();
+        let mut unique: Vec<FontUse> = vec![];
+        for mut font in fonts {
+            font.file = self.paths.resolve(&base_dir, &font.file);
+            if !unique.contains(&font) {
+                unique.push(font);
+            }
+        }
+        unique
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parser::parse_source, text::SourceText};
+
+    #[test]
+    fn fonts() {
+        let text = "(./main.tex\nLaTeX Font Info:    Trying to load font information for T1+lmr on input line 5.\n\nLaTeX Font Warning: Font shape `OT1/cmr/m/scit' undefined\n(Font)              using `OT1/cmr/m/it' instead on input line 38.\n\n\nLaTeX Font Warning: Font shape `T1/lmr/m/n' in size <10.95> not available\n(Font)              size <10> substituted on input line 40.\n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let fonts: Vec<String> = log.fonts().iter().map(|f| f.to_string()).collect();
+        assert_eq!(
+            fonts,
+            vec![
+                "T1/lmr (./main.tex:5)",
+                "OT1/cmr/m/scit -> OT1/cmr/m/it (./main.tex:38)",
+                "T1/lmr/m/n/10.95 -> T1/lmr/m/n/10 (./main.tex:40)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use std::{fmt::Display, path::PathBuf};
+
+use crate::{
+    log::Log,
+    parser::{DiagnosticLevel, Node},
+    regex::Regex,
+};
+
+/// A line of the log that matches a pattern, with the files that were open at it
+#[derive(Clone, Debug, PartialEq)]
+pub struct GrepMatch {
+    /// The files that were open at the line, outermost first
+    pub call_stack: Vec<PathBuf>,
+
+    /// The matching line
+    pub line: String,
+
+    /// Byte range of the match in `line`
+    pub range: (usize, usize),
+
+    /// Level of the diagnostic the line is part of, if any
+    pub level: Option<DiagnosticLevel>,
+}
+
+impl GrepMatch {
+    /// The files of the call stack, like `./main.tex > ./chapters/intro.tex`
+    pub fn trace(&self) -> String {
+        self.call_stack
+            .iter()
+            .map(|f| f.display().to_string())
+            .collect::<Vec<_>>()
+            .join(" > ")
+    }
+}
+
+impl Display for GrepMatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: ", self.trace())?;
+        if let Some(level) = &self.level {
+            write!(f, "{}: ", level)?;
+        }
+        write!(f, "{}", self.line)
+    }
+}
+
+/// The files that were open at a position in the log, outermost first, and the level of the
+/// diagnostic the position is part of
+fn locate(root: &Node, pos: usize) -> (Vec<PathBuf>, Option<DiagnosticLevel>) {
+    let mut node = root;
+    let mut stack = vec![PathBuf::from(node.file())];
+    while let Some(call) = node.children().iter().find(|c| c.span().contains(pos)) {
+        node = call;
+        stack.push(PathBuf::from(node.file()));
+    }
+    let level = node
+        .diagnostics()
+        .iter()
+        .find(|d| d.span().contains(pos))
+        .map(|d| d.level());
+    (stack, level)
+}
+
+impl Log {
+    /// The lines of the final run that match a regular expression, in the order of the log.
+    /// Fails if the pattern is not a valid regular expression.
+    pub fn grep(&self, pattern: &str) -> Result<Vec<GrepMatch>, String> {
+        let pattern = Regex::new(pattern)?;
+        let root = self.root_node();
+        let mut matches = vec![];
+        let mut pos = 0;
+        for line in self.source().as_str().split('\n') {
+            let line_pos = pos;
+            pos += line.chars().count() + 1;
+            if !root.span().contains(line_pos) {
+                continue;
+            }
+            if let Some(range) = pattern.find(line) {
+                let (call_stack, level) = locate(root, line_pos);
+                matches.push(GrepMatch {
+                    call_stack,
+                    line: line.to_string(),
+                    range,
+                    level,
+                });
+            }
+        }
+        let base_dir = self.base_dir();
+        for m in &mut matches {
+            for file in &mut m.call_stack {
+                *file = self.paths.resolve(&base_dir, file);
+            }
+        }
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::parse_source, text::SourceText};
+
+    #[test]
+    fn grep() {
+        let text = "(./main.tex\n(./intro.tex\nChapter 1.\n\nLaTeX Warning: Reference `fig:a' on page 1 undefined on input line 4.\n\n)\nChapter 2.\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+
+        let matches = log.grep("Chapter [0-9]").unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].trace(), "./main.tex > ./intro.tex");
+        assert_eq!(matches[0].range, (0, 9));
+        assert_eq!(matches[1].to_string(), "./main.tex: Chapter 2.");
+
+        let matches = log.grep("fig:a").unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].level, Some(DiagnosticLevel::Warning));
+        assert!(matches[0]
+            .to_string()
+            .starts_with("./main.tex > ./intro.tex: warning: LaTeX Warning:"));
+    }
+}
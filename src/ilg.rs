@@ -0,0 +1,129 @@
+use std::path::PathBuf;
+
+use crate::{
+    log::Log,
+    parser::{Node, TexDiagnostic, TexDiagnosticKind},
+    text::SourceText,
+};
+
+/// Parse a `.ilg` log written by makeindex or xindy. makeindex reports problems as
+/// `!! Input index error (file = main.idx, line = 12):` or `## Warning (input = main.idx,
+/// line = 7; ...):` followed by `   -- ` lines, and xindy as `WARNING:` and `ERROR:` lines.
+pub fn parse_ilg(source: SourceText, file: String) -> Log {
+    let mut diagnostics: Vec<TexDiagnostic> = vec![];
+
+    let mut pos = 0;
+    let mut lines = source.as_str().lines().peekable();
+    while let Some(line) = lines.next() {
+        let line_pos = pos;
+        pos += line.chars().count() + 1;
+
+        let kind = if line.starts_with("!! ") || line.starts_with("ERROR:") {
+            TexDiagnosticKind::IndexError
+        } else if line.starts_with("## ") || line.starts_with("WARNING:") {
+            TexDiagnosticKind::Index
+        } else {
+            continue;
+        };
+
+        // makeindex explains the problem on the following `   -- ` lines
+        let mut message = line.to_string();
+        while let Some(next) = lines.next_if(|l| l.trim_start().starts_with("-- ")) {
+            pos += next.chars().count() + 1;
+            message.push('\n');
+            message += next;
+        }
+
+        let mut diagnostic = TexDiagnostic::new(kind, message, line_pos);
+        if let Some((input, line)) = input_location(line) {
+            diagnostic.source_file = Some(PathBuf::from(input));
+            diagnostic.source_line = Some(line);
+        }
+        diagnostics.push(diagnostic);
+    }
+
+    Log::new(source, Node::new(file, diagnostics))
+}
+
+/// The input file and line of `(file = main.idx, line = 12)` or
+/// `(input = main.idx, line = 7; output = main.ind, line = 33)`
+fn input_location(line: &str) -> Option<(String, usize)> {
+    let start = line.find('(')? + 1;
+    let end = line[start..].find([';', ')'])? + start;
+    let mut input = None;
+    let mut line_number = None;
+    for field in line[start..end].split(',') {
+        match field.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+            Some(("file" | "input", value)) => input = Some(value.to_string()),
+            Some(("line", value)) => line_number = value.parse().ok(),
+            _ => {}
+        }
+    }
+    Some((input?, line_number?))
+}
+
+impl Log {
+    /// The `.ilg` file next to a `.log` file, if makeindex or xindy was run for the document
+    pub fn ilg_path(&self) -> Option<PathBuf> {
+        let path = self.path.as_ref()?;
+        if path.extension().is_none_or(|e| e != "log") {
+            return None;
+        }
+        let path = path.with_extension("ilg");
+        path.is_file().then_some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn makeindex() {
+        let source = "This is makeindex, version 2.15 [TeX Live 2022] (kpathsea + Thai support).
+Scanning input file main.idx...
+!! Input index error (file = main.idx, line = 12):
+   -- Extra `@' at position 10 of first argument.
+done (42 entries accepted, 1 rejected).
+Generating output file main.ind....
+## Warning (input = main.idx, line = 7; output = main.ind, line = 33):
+   -- Unmatched range opening operator (.
+done (50 lines written, 1 warning).
+";
+        let log = parse_ilg(SourceText::new(source.to_string()), "main.ilg".to_string());
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 2);
+        assert_eq!(ds[0].diagnostic().kind, TexDiagnosticKind::IndexError);
+        assert!(ds[0].diagnostic().message.ends_with("of first argument."));
+        assert_eq!(ds[0].diagnostic().source_line(), Some(12));
+        assert_eq!(ds[1].diagnostic().kind, TexDiagnosticKind::Index);
+        assert_eq!(
+            ds[1].diagnostic().source_file(),
+            Some(Path::new("main.idx"))
+        );
+        assert_eq!(ds[1].diagnostic().source_line(), Some(7));
+    }
+
+    #[test]
+    fn xindy() {
+        let source = "WARNING: unknown cross-reference-class `see'! (ignored)\n";
+        let log = parse_ilg(SourceText::new(source.to_string()), "main.ilg".to_string());
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 1);
+        assert_eq!(ds[0].diagnostic().kind, TexDiagnosticKind::Index);
+    }
+
+    #[test]
+    fn ilg_path() {
+        let dir = std::env::temp_dir().join(format!("texlog-ilg-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("main.log"), "(./main.tex)").unwrap();
+        std::fs::write(dir.join("main.ilg"), "").unwrap();
+        let path = Log::from_path(dir.join("main.log")).unwrap().ilg_path();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(path, Some(dir.join("main.ilg")));
+    }
+}
@@ -0,0 +1,323 @@
+use std::fmt::Display;
+
+/// Minimal JSON value used by the machine readable output formats and the baseline file
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Create an object from key-value pairs
+    pub fn object<const N: usize>(fields: [(&str, Json); N]) -> Self {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+    }
+
+    pub fn string<S: ToString>(s: S) -> Self {
+        Json::String(s.to_string())
+    }
+
+    /// Parse a JSON document
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut parser = JsonParser {
+            chars: source.chars().collect(),
+            cursor: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        match parser.current() {
+            None => Ok(value),
+            Some(c) => Err(parser.error(&format!("unexpected '{}' after the value", c))),
+        }
+    }
+
+    /// Value of a field of an object
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Recursive descent parser for JSON documents
+struct JsonParser {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl JsonParser {
+    fn current(&self) -> Option<char> {
+        self.chars.get(self.cursor).copied()
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("position {}: {}", self.cursor, message)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.current().is_some_and(|c| c.is_whitespace()) {
+            self.cursor += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.current() {
+            Some(c) if c == expected => {
+                self.cursor += 1;
+                Ok(())
+            }
+            _ => Err(self.error(&format!("expected '{}'", expected))),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        for expected in keyword.chars() {
+            if self.current() != Some(expected) {
+                return Err(self.error(&format!("expected '{}'", keyword)));
+            }
+            self.cursor += 1;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.current() {
+            Some('n') => self.keyword("null", Json::Null),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('"') => self.string().map(Json::String),
+            Some('[') => {
+                self.cursor += 1;
+                let mut items = vec![];
+                self.skip_whitespace();
+                if self.current() == Some(']') {
+                    self.cursor += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.current() {
+                        Some(',') => self.cursor += 1,
+                        Some(']') => {
+                            self.cursor += 1;
+                            return Ok(Json::Array(items));
+                        }
+                        _ => return Err(self.error("expected ',' or ']'")),
+                    }
+                }
+            }
+            Some('{') => {
+                self.cursor += 1;
+                let mut fields = vec![];
+                self.skip_whitespace();
+                if self.current() == Some('}') {
+                    self.cursor += 1;
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    fields.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.current() {
+                        Some(',') => self.cursor += 1,
+                        Some('}') => {
+                            self.cursor += 1;
+                            return Ok(Json::Object(fields));
+                        }
+                        _ => return Err(self.error("expected ',' or '}'")),
+                    }
+                }
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.cursor;
+                while self
+                    .current()
+                    .is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+                {
+                    self.cursor += 1;
+                }
+                let number: String = self.chars[start..self.cursor].iter().collect();
+                number
+                    .parse()
+                    .map(Json::Number)
+                    .map_err(|_| self.error(&format!("invalid number '{}'", number)))
+            }
+            Some(c) => Err(self.error(&format!("unexpected '{}'", c))),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.current() != Some('"') {
+            return Err(self.error("expected a string"));
+        }
+        self.cursor += 1;
+        let mut s = String::new();
+        loop {
+            let Some(c) = self.current() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.cursor += 1;
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escaped = self
+                        .current()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.cursor += 1;
+                    match escaped {
+                        'n' => s.push('\n'),
+                        'r' => s.push('\r'),
+                        't' => s.push('\t'),
+                        'b' => s.push('\u{8}'),
+                        'f' => s.push('\u{c}'),
+                        'u' => {
+                            let hex: String = self.chars.iter().skip(self.cursor).take(4).collect();
+                            let code = u32::from_str_radix(&hex, 16)
+                                .map_err(|_| self.error("invalid unicode escape"))?;
+                            self.cursor += 4;
+                            s.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                        }
+                        c => s.push(c),
+                    }
+                }
+                c => s.push(c),
+            }
+        }
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<u32> for Json {
+    fn from(n: u32) -> Self {
+        Json::Number(n.into())
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(v) => v.into(),
+            None => Json::Null,
+        }
+    }
+}
+
+fn write_escaped(f: &mut std::fmt::Formatter<'_>, s: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_escaped(f, s),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_escaped(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize() {
+        let json = Json::object([
+            ("name", Json::string("a \"quoted\"\nline")),
+            ("count", Json::from(3usize)),
+            ("ratio", Json::Number(0.5)),
+            ("list", Json::Array(vec![Json::Null, Json::Bool(true)])),
+        ]);
+        assert_eq!(
+            json.to_string(),
+            r#"{"name":"a \"quoted\"\nline","count":3,"ratio":0.5,"list":[null,true]}"#
+        );
+    }
+
+    #[test]
+    fn parse() {
+        let json = Json::object([
+            ("name", Json::string("a \"quoted\"\nline")),
+            ("count", Json::from(3usize)),
+            ("list", Json::Array(vec![Json::Null, Json::Bool(false)])),
+            ("empty", Json::Object(vec![])),
+        ]);
+        assert_eq!(Json::parse(&json.to_string()), Ok(json.clone()));
+        assert_eq!(
+            Json::parse(" { \"name\" : \"\\u00e9\" } "),
+            Ok(Json::object([("name", Json::string("é"))]))
+        );
+        assert!(Json::parse("[1, 2").is_err());
+        assert!(Json::parse("{} x").is_err());
+    }
+}
@@ -1,50 +1,80 @@
-use std::collections::VecDeque;
+use std::{borrow::Cow, collections::VecDeque, fmt::Display};
 
+use crate::{parser::Engine, span::Span, text::char_width};
+
+/// Kind of a token. The text of words, whitespace and paths borrows from the log, unless it
+/// spans a line that TeX has wrapped.
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Debug, PartialEq)]
-pub enum TokenKind {
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind<'a> {
+    /// `(`, which opens a file when followed by a path
     LeftParen,
+    /// `)`, which closes the innermost open file, or a parenthesis in a message
     RightParen,
+    /// `!`, which starts TeX errors at the start of a line
     ExclamationMark,
-    Path(String),
-    Word(String),
+    /// A path starting with `./` or `/`, as TeX writes it when opening a file. Paths in quotes
+    /// are given without the quotes.
+    Path(Cow<'a, str>),
+    /// A run of letters
+    Word(Cow<'a, str>),
+    /// Any other single character, like digits and symbols
     Punctuation(char),
+    /// A line break that is in the log. Line breaks inserted by TeX's wrapping are skipped.
     Newline,
-    Whitespace(String),
-    EOF, // End of log
+    /// A run of whitespace other than line breaks
+    Whitespace(Cow<'a, str>),
+    /// End of the log, which is always the last token. It is written as nothing.
+    EOF,
 }
 
-impl ToString for TokenKind {
-    fn to_string(&self) -> String {
+impl Display for TokenKind<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            TokenKind::LeftParen => "(".to_string(),
-            TokenKind::RightParen => "(".to_string(),
-            TokenKind::ExclamationMark => "!".to_string(),
-            TokenKind::Path(p) => p.to_owned(),
-            TokenKind::Word(w) => w.to_owned(),
-            TokenKind::Punctuation(p) => String::from_iter(&[*p]),
-            TokenKind::Newline => "\n".to_string(),
-            TokenKind::Whitespace(w) => w.to_owned(),
-            TokenKind::EOF => panic!("EOF should never be converted to string."),
+            TokenKind::LeftParen => write!(f, "("),
+            TokenKind::RightParen => write!(f, ")"),
+            TokenKind::ExclamationMark => write!(f, "!"),
+            TokenKind::Path(p) => write!(f, "{}", p),
+            TokenKind::Word(w) => write!(f, "{}", w),
+            TokenKind::Punctuation(p) => write!(f, "{}", p),
+            TokenKind::Newline => writeln!(f),
+            TokenKind::Whitespace(w) => write!(f, "{}", w),
+            TokenKind::EOF => Ok(()),
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub struct Token {
-    pub kind: TokenKind,
-    pub pos: usize,
+/// A piece of the log with its place in it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token<'a> {
+    pub kind: TokenKind<'a>,
+
+    /// The characters of the log that the token was read from, counted in characters. The span
+    /// includes the line breaks that TeX's wrapping inserted inside the token, so the text of
+    /// the kind can be shorter than the span.
+    pub span: Span,
+
+    /// 1-based line of the log that the token starts on
+    pub line: usize,
+
+    /// 1-based column of the start of the token, counted as by `SourceText::row_col`
+    pub col: usize,
 }
 
-impl ToString for Token {
-    fn to_string(&self) -> String {
-        self.kind.to_string()
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.kind.fmt(f)
     }
 }
 
-impl Token {
-    pub fn new(kind: TokenKind, pos: usize) -> Self {
-        Self { kind, pos }
+impl<'a> Token<'a> {
+    pub fn new(kind: TokenKind<'a>, span: Span, line: usize, col: usize) -> Self {
+        Self {
+            kind,
+            span,
+            line,
+            col,
+        }
     }
 
     pub fn has_kind(&self, kind: &TokenKind) -> bool {
@@ -52,214 +82,307 @@ impl Token {
     }
 }
 
-pub fn tokenize(log: &str) -> Vec<Token> {
-    let lexer = Lexer::new(log);
+/// Length at which TeX wraps lines in the log, set by `max_print_line` in texmf.cnf
+pub const MAX_PRINT_LINE: usize = 79;
+
+/// Split a log into tokens, ending with `TokenKind::EOF`. Lines of exactly `MAX_PRINT_LINE`
+/// characters are taken to be wrapped by TeX, and are joined with the next line. Writing the
+/// kinds of the tokens one after the other gives the log without the wrapping line breaks.
+///
+/// ```
+/// use texlog::{tokenize, TokenKind};
+///
+/// let tokens = tokenize("(./main.tex\n! Undefined");
+/// assert_eq!(tokens[0].kind, TokenKind::LeftParen);
+/// assert_eq!(tokens[1].kind, TokenKind::Path("./main.tex".into()));
+/// assert_eq!((tokens[3].line, tokens[3].col), (2, 1));
+/// assert_eq!(tokens[3].span.start, 12);
+/// assert_eq!(tokens.last().unwrap().kind, TokenKind::EOF);
+/// ```
+pub fn tokenize(log: &str) -> Vec<Token<'_>> {
+    tokenize_for(log, Engine::detect(log))
+}
+
+/// Split a part of a log written by `engine` into tokens, where the banner that `tokenize` detects
+/// the engine from may be missing
+pub(crate) fn tokenize_for(log: &str, engine: Engine) -> Vec<Token<'_>> {
+    Lexer::new(log, engine).collect()
+}
+
+/// Split text whose lines were not wrapped by TeX into tokens, like the terminal output of an
+/// engine with a larger `max_print_line`. No line breaks are skipped.
+pub fn tokenize_unwrapped(log: &str) -> Vec<Token<'_>> {
+    let mut lexer = Lexer::new(log, Engine::default());
+    lexer.wraps.clear();
     lexer.collect()
 }
 
-struct Lexer {
-    /// The log characters
-    chars: Vec<char>,
+/// Starts of lines that begin a message, which TeX never wraps onto
+const LINE_STARTS: [&str; 9] = [
+    "! ",
+    "LaTeX ",
+    "Package ",
+    "Class ",
+    "Overfull",
+    "Underfull",
+    "Runaway",
+    "l.",
+    "(",
+];
+
+/// Whether a line starts a new message rather than continuing a wrapped one
+fn starts_message(line: &str) -> bool {
+    LINE_STARTS
+        .iter()
+        .any(|start| match line.strip_prefix(start) {
+            Some(rest) if *start == "l." => rest.starts_with(|c: char| c.is_ascii_digit()),
+            Some(_) => true,
+            None => false,
+        })
+}
+
+struct Lexer<'a> {
+    source: &'a str,
 
-    // The index of the current character getting lexed
+    /// Byte offsets of the line breaks inserted by TeX's wrapping, which are skipped
+    wraps: Vec<usize>,
+
+    /// Index in `wraps` of the first line break after the cursor
+    next_wrap: usize,
+
+    /// Byte offset of the current character
     cursor: usize,
 
-    queue: VecDeque<Token>,
+    /// Position in the log of the current character, counted in characters
+    pos: usize,
+
+    /// 1-based line and column of the current character
+    line: usize,
+    col: usize,
+
+    queue: VecDeque<Token<'a>>,
 
     placed_eof: bool,
 }
 
-impl Lexer {
+impl<'a> Lexer<'a> {
     /// Create a lexer from a source string
-    fn new(source: &str) -> Self {
+    fn new(source: &'a str, engine: Engine) -> Self {
         Self {
-            chars: source.chars().collect(),
+            source,
+            wraps: Self::find_wraps(source, engine),
+            next_wrap: 0,
             cursor: 0,
+            pos: 0,
+            line: 1,
+            col: 1,
             queue: VecDeque::with_capacity(10),
             placed_eof: false,
         }
     }
 
-    /// Get characters with and offset from the cursor
-    fn peak(&self, offset: isize) -> Option<&char> {
-        let index = self.cursor as isize + offset;
+    /// Find the line breaks that TeX inserted when wrapping lines at `MAX_PRINT_LINE`
+    /// characters. pdfTeX counts bytes rather than characters, so a line is taken to be wrapped if
+    /// either of its lengths is the maximum. A line that happens to have the maximum length looks
+    /// the same, so a break is kept when the next line starts like a message. LuaTeX does not
+    /// split UTF-8 characters, so its lines are also wrapped up to three bytes short of the maximum
+    /// before a character that does not fit.
+    fn find_wraps(source: &str, engine: Engine) -> Vec<usize> {
+        let mut wraps = vec![];
+        let mut line_chars = 0;
+        let mut line_bytes = 0;
+        for (i, c) in source.char_indices() {
+            if c == '\n' {
+                let rest = &source[i + 1..];
+                let overflows = |next: char| line_bytes + next.len_utf8() > MAX_PRINT_LINE;
+                let wrapped = line_chars == MAX_PRINT_LINE
+                    || line_bytes == MAX_PRINT_LINE
+                    || (engine == Engine::LuaTex
+                        && line_bytes < MAX_PRINT_LINE
+                        && rest.chars().next().is_some_and(overflows));
+                let continues = !rest.is_empty() && !rest.starts_with('\n');
+                if wrapped && continues && !starts_message(rest) {
+                    wraps.push(i);
+                }
+                line_chars = 0;
+                line_bytes = 0;
+            } else {
+                line_chars += 1;
+                line_bytes += c.len_utf8();
+            }
+        }
+        wraps
+    }
+
+    /// Position, line and column in the source of the character at the cursor
+    fn source_pos(&self) -> (usize, usize, usize) {
+        (self.pos, self.line, self.col)
+    }
+
+    /// A token from a position returned by `source_pos` up to the cursor
+    fn token(&self, kind: TokenKind<'a>, (pos, line, col): (usize, usize, usize)) -> Token<'a> {
+        Token::new(kind, Span::new(pos, self.pos), line, col)
+    }
 
-        if index < 0 {
-            None
-        } else {
-            self.chars.get(index as usize)
+    /// The text from byte offset `start` up to `end`, without wrapping line breaks
+    fn text(&self, start: usize, end: usize) -> Cow<'a, str> {
+        let first = self.wraps.partition_point(|w| *w < start);
+        let wraps = &self.wraps[first..self.wraps.partition_point(|w| *w < end)];
+        if wraps.is_empty() {
+            return Cow::Borrowed(&self.source[start..end]);
         }
+        let mut text = String::with_capacity(end - start);
+        let mut from = start;
+        for wrap in wraps {
+            text += &self.source[from..*wrap];
+            from = wrap + 1;
+        }
+        text += &self.source[from..end];
+        Cow::Owned(text)
     }
 
-    /// Get the char at the cursor
-    fn current(&self) -> Option<&char> {
-        self.chars.get(self.cursor)
+    /// Get characters with an offset from the cursor
+    fn peak(&self, offset: usize) -> Option<char> {
+        let wraps = &self.wraps[self.next_wrap..];
+        self.source[self.cursor..]
+            .char_indices()
+            .filter(|(i, _)| wraps.binary_search(&(self.cursor + i)).is_err())
+            .nth(offset)
+            .map(|(_, c)| c)
     }
 
-    /// Get the char at the cursor, and increment the cursor
-    fn consume(&mut self) -> Option<&char> {
-        let res = self.chars.get(self.cursor);
-        self.cursor += 1;
-        res
+    /// Get the char at the cursor
+    fn current(&self) -> Option<char> {
+        self.source[self.cursor..].chars().next()
     }
 
-    fn is_whitespace(c: &char) -> bool {
-        c.is_whitespace() && c != &'\n'
+    /// Get the char at the cursor, and move the cursor past it
+    fn consume(&mut self) -> Option<char> {
+        let c = self.current()?;
+        self.cursor += c.len_utf8();
+        self.pos += 1;
+        self.col += char_width(c);
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        }
+        if self.wraps.get(self.next_wrap) == Some(&self.cursor) {
+            self.cursor += 1;
+            self.pos += 1;
+            self.line += 1;
+            self.col = 1;
+            self.next_wrap += 1;
+        }
+        Some(c)
     }
 
-    fn consume_whitespace(&mut self) -> String {
-        let mut whitespace = String::new();
-        while let Some(c) = self.current() {
-            if !Self::is_whitespace(c) {
-                break;
-            }
-            whitespace.push(*c);
+    /// Consume characters while `f` holds, and return them
+    fn consume_while(&mut self, f: impl Fn(char) -> bool) -> Cow<'a, str> {
+        let start = self.cursor;
+        while self.current().is_some_and(&f) {
             self.consume();
         }
-        whitespace
+        self.text(start, self.cursor)
     }
 
-    fn is_word_char(c: &char) -> bool {
-        c.is_alphabetic()
+    fn is_whitespace(c: char) -> bool {
+        c.is_whitespace() && c != '\n'
     }
 
-    fn consume_word(&mut self) -> String {
-        let mut word = String::new();
-        while let Some(c) = self.current() {
-            if !Self::is_word_char(c) {
-                break;
-            }
-            word.push(*c);
-            self.consume();
-        }
-        word
+    fn is_word_char(c: char) -> bool {
+        c.is_alphabetic()
     }
 
     /// Lex next token
-    fn next_token(&mut self) -> Option<Token> {
+    fn next_token(&mut self) -> Option<Token<'a>> {
         if !self.queue.is_empty() {
             return self.queue.pop_front(); // This should always be `Some`
         }
 
-        let pos = self.cursor;
-        match *self.current()? {
+        let pos = self.source_pos();
+        match self.current()? {
             '(' => {
                 self.consume();
-                Some(Token::new(TokenKind::LeftParen, pos))
+                Some(self.token(TokenKind::LeftParen, pos))
             }
             ')' => {
                 self.consume();
-                Some(Token::new(TokenKind::RightParen, pos))
+                Some(self.token(TokenKind::RightParen, pos))
             }
             '!' => {
                 self.consume();
-                Some(Token::new(TokenKind::ExclamationMark, pos))
+                Some(self.token(TokenKind::ExclamationMark, pos))
             }
             '\n' => {
                 self.consume();
-                Some(Token::new(TokenKind::Newline, pos))
+                Some(self.token(TokenKind::Newline, pos))
             }
-            c if Self::is_word_char(&c) => {
-                let word = self.consume_word();
-                Some(Token::new(TokenKind::Word(word), pos))
+            c if Self::is_word_char(c) => {
+                let word = self.consume_while(Self::is_word_char);
+                Some(self.token(TokenKind::Word(word), pos))
             }
-            c if Self::is_whitespace(&c) => {
-                let whitespace = self.consume_whitespace();
-                Some(Token::new(TokenKind::Whitespace(whitespace), pos))
+            c if Self::is_whitespace(c) => {
+                let whitespace = self.consume_while(Self::is_whitespace);
+                Some(self.token(TokenKind::Whitespace(whitespace), pos))
             }
             _ if self.at_path_start() => {
                 let path = self.consume_path();
-                Some(Token::new(TokenKind::Path(path), pos))
+                Some(self.token(TokenKind::Path(path), pos))
             }
             c => {
                 self.consume();
-                Some(Token::new(TokenKind::Punctuation(c), pos))
+                Some(self.token(TokenKind::Punctuation(c), pos))
             }
         }
     }
 
     /// Returns `true` when cursor is at the start of a path
     fn at_path_start(&self) -> bool {
-        match self.current().cloned() {
-            Some('.') => {
-                self.peak(1) == Some(&'/')
-            }
+        self.at_unquoted_path_start(0)
+            || (self.current() == Some('"') && self.at_unquoted_path_start(1))
+    }
+
+    fn at_unquoted_path_start(&self, offset: usize) -> bool {
+        match self.peak(offset) {
+            Some('.') => self.peak(offset + 1) == Some('/'),
             Some('/') => true,
             _ => false,
         }
     }
 
     /// Consume a path
-    fn consume_path(&mut self) -> String {
-        let mut chars = vec![];
-        while self.at_path_start() {
-            chars.push(*self.consume().unwrap());
+    fn consume_path(&mut self) -> Cow<'a, str> {
+        if self.current() == Some('"') {
+            return self.consume_quoted_path();
         }
 
-        loop {
-            match self.current() {
-                Some(&'(') => break,
-                Some(&')') => break,
-                Some(&'<') => break,
-                Some(&'>') => break,
-                Some(&'[') => break,
-                Some(&']') => break,
-                Some(&'!') => break,
-                Some(&'\\') => break,
-
-                // TODO: This is an awful solution
-                // Break if any of these strings are next in the path. Of course,
-                // this means that paths that include these strings will be cut and
-                // reported incorrectly, but i cannot figure out a way to determine
-                // if the paths continue on the next line.
-                Some(_)
-                    if [
-                        "\n! ", // Error
-                        "\nDictionary:",
-                        "\nPackage:",
-                        "\nFile:",
-                        "\nLaTeX",
-                        "\nDocument Class:",
-                    ]
-                    .map(|s| {
-                        self.chars[self.cursor..]
-                            .starts_with(s.chars().collect::<Vec<char>>().as_slice())
-                    })
-                    .iter()
-                    .filter(|e| **e)
-                    .count()
-                        > 0 =>
-                {
-                    break
-                }
-                Some(&'\n') if self.peak(1) != Some(&'\n') => {
-                    self.consume();
-                }
-
-                Some(c) if c.is_whitespace() => break,
-                Some(c) => {
-                    chars.push(*c);
-                    self.consume();
-                }
-                None => break,
-            }
+        let start = self.cursor;
+        while self.at_path_start() {
+            self.consume();
         }
-        String::from_iter(chars)
+        self.consume_while(|c| !c.is_whitespace() && !"()<>[]!\\".contains(c));
+        self.text(start, self.cursor)
+    }
+
+    /// Consume a path in quotes, which TeX uses for paths containing spaces. The quotes are not
+    /// part of the path.
+    fn consume_quoted_path(&mut self) -> Cow<'a, str> {
+        self.consume();
+        let path = self.consume_while(|c| c != '"');
+        self.consume();
+        path
     }
 }
 
-impl Iterator for Lexer {
-    type Item = Token;
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.next_token() {
             Some(t) => Some(t),
             None if !self.placed_eof => {
                 self.placed_eof = true;
-                Some(Token {
-                    kind: TokenKind::EOF,
-                    pos: self.cursor,
-                })
+                Some(self.token(TokenKind::EOF, self.source_pos()))
             }
             None => None,
         }
@@ -281,39 +404,57 @@ mod tests {
             vec![
                 Token {
                     kind: TokenKind::LeftParen,
-                    pos: 0,
+                    span: Span::new(0, 1),
+                    line: 1,
+                    col: 1,
                 },
                 Token {
                     kind: TokenKind::LeftParen,
-                    pos: 1,
+                    span: Span::new(1, 2),
+                    line: 1,
+                    col: 2,
                 },
                 Token {
                     kind: TokenKind::LeftParen,
-                    pos: 2,
+                    span: Span::new(2, 3),
+                    line: 1,
+                    col: 3,
                 },
                 Token {
                     kind: TokenKind::RightParen,
-                    pos: 3,
+                    span: Span::new(3, 4),
+                    line: 1,
+                    col: 4,
                 },
                 Token {
                     kind: TokenKind::RightParen,
-                    pos: 4,
+                    span: Span::new(4, 5),
+                    line: 1,
+                    col: 5,
                 },
                 Token {
                     kind: TokenKind::LeftParen,
-                    pos: 5,
+                    span: Span::new(5, 6),
+                    line: 1,
+                    col: 6,
                 },
                 Token {
                     kind: TokenKind::RightParen,
-                    pos: 6,
+                    span: Span::new(6, 7),
+                    line: 1,
+                    col: 7,
                 },
                 Token {
                     kind: TokenKind::RightParen,
-                    pos: 7,
+                    span: Span::new(7, 8),
+                    line: 1,
+                    col: 8,
                 },
                 Token {
                     kind: TokenKind::EOF,
-                    pos: 8,
+                    span: Span::new(8, 8),
+                    line: 1,
+                    col: 9,
                 },
             ]
         )
@@ -328,21 +469,96 @@ mod tests {
             vec![
                 Token {
                     kind: TokenKind::LeftParen,
-                    pos: 0,
+                    span: Span::new(0, 1),
+                    line: 1,
+                    col: 1,
                 },
                 Token {
-                    kind: TokenKind::Path("./path/to/interesting/place.awesome".to_string()),
-                    pos: 1,
+                    kind: TokenKind::Path("./path/to/interesting/place.awesome".into()),
+                    span: Span::new(1, 36),
+                    line: 1,
+                    col: 2,
                 },
                 Token {
                     kind: TokenKind::RightParen,
-                    pos: 36,
+                    span: Span::new(36, 37),
+                    line: 1,
+                    col: 37,
                 },
                 Token {
                     kind: TokenKind::EOF,
-                    pos: 37,
+                    span: Span::new(37, 37),
+                    line: 1,
+                    col: 38,
                 },
             ]
         )
     }
+
+    #[test]
+    fn lex_quoted_path() {
+        let s = "(\"./my thesis/chapter 1.tex\" text)";
+        let lexed_tokens = tokenize(s);
+        assert_eq!(
+            lexed_tokens[1].kind,
+            TokenKind::Path("./my thesis/chapter 1.tex".into())
+        );
+        assert_eq!(lexed_tokens[2].kind, TokenKind::Whitespace(" ".into()));
+    }
+
+    #[test]
+    fn unwrap_lines() {
+        let path = format!("./{}/chapter.tex", "a".repeat(MAX_PRINT_LINE));
+        let wrapped = format!(
+            "({}\n{})",
+            &path[..MAX_PRINT_LINE - 1],
+            &path[MAX_PRINT_LINE - 1..]
+        );
+        let lexed_tokens = tokenize(&wrapped);
+        assert_eq!(lexed_tokens[1].kind, TokenKind::Path(path.clone().into()));
+
+        // Positions refer to the wrapped source
+        let close = lexed_tokens
+            .iter()
+            .find(|t| t.kind == TokenKind::RightParen);
+        assert_eq!(close.unwrap().span.start, wrapped.chars().count() - 1);
+        assert_eq!(
+            lexed_tokens[1].span,
+            Span::new(1, wrapped.chars().count() - 1)
+        );
+        assert_eq!(
+            (close.unwrap().line, close.unwrap().col),
+            (2, path.len() - MAX_PRINT_LINE + 2)
+        );
+
+        // A line of the maximum length before a message is not wrapped
+        let line = format!(
+            "{}\n! Undefined control sequence.",
+            "a".repeat(MAX_PRINT_LINE)
+        );
+        assert_eq!(Lexer::find_wraps(&line, Engine::PdfTex), vec![]);
+
+        // LuaTeX wraps before a character that does not fit
+        let line = format!("{}\né.tex", "a".repeat(MAX_PRINT_LINE - 1));
+        assert_eq!(Lexer::find_wraps(&line, Engine::PdfTex), vec![]);
+        assert_eq!(
+            Lexer::find_wraps(&line, Engine::LuaTex),
+            vec![MAX_PRINT_LINE - 1]
+        );
+
+        // Text that does not span a wrapped line is borrowed from the log
+        let lexed_tokens = tokenize("(./main.tex)");
+        assert!(matches!(
+            lexed_tokens[1].kind,
+            TokenKind::Path(Cow::Borrowed(_))
+        ));
+    }
+
+    #[test]
+    fn line_col() {
+        let source = crate::text::SourceText::from_file("./test/main.log").unwrap();
+        for token in tokenize(source.as_str()) {
+            assert_eq!((token.line, token.col), source.row_col(token.span.start));
+        }
+    }
 }
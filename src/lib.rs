@@ -1,10 +1,62 @@
+//! Parse LaTeX log files into diagnostics.
+//!
+//! ```no_run
+//! let log = texlog::Log::from_path("main.log")?;
+//! for d in log.get_diagnostics() {
+//!     println!("{}", d.to_short_string());
+//! }
+//! # Ok::<(), texlog::Error>(())
+//! ```
+//!
+//! `parse_source` parses a log that is already in memory, and `LogStream` finds diagnostics in
+//! a log that is still being written. `tokenize` gives the tokens that the parser reads, for
+//! tools like syntax highlighters that only need those.
+
 #![allow(dead_code)]
 
+pub mod annotate;
+mod ansi;
+pub mod baseline;
+mod blg;
+pub mod clean;
+pub mod console;
+pub mod diff;
+pub mod error;
+pub mod explain;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod filter;
+pub mod fls;
+pub mod fonts;
+pub mod grep;
+mod ilg;
+mod inflate;
+mod json;
 pub mod lexer;
 pub mod log;
+pub mod magic;
+pub mod output;
+pub mod packages;
 pub mod parser;
-mod text;
+mod regex;
+pub mod span;
+mod stale;
+pub mod stats;
+pub mod stream;
+mod suggest;
+pub mod synctex;
+pub mod tectonic;
+pub mod text;
 
+pub use error::{Error, Result};
+pub use lexer::{tokenize, tokenize_unwrapped, Token, TokenKind, MAX_PRINT_LINE};
+pub use log::{Log, TracedTexDiagnostic};
+pub use parser::{
+    parse_source, DiagnosticLevel, Node, TexDiagnostic, TexDiagnosticKind, TryVisitor, Visitor,
+};
+pub use span::Span;
+pub use stream::LogStream;
+pub use text::SourceText;
 
 #[cfg(test)]
 mod tests {}
@@ -0,0 +1,116 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Build directories that are searched for the log of a document, besides its own directory
+const BUILD_DIRS: [&str; 2] = ["build", "out"];
+
+/// Names of the latexmk config files read from the directory of a document
+const LATEXMKRC_NAMES: [&str; 2] = [".latexmkrc", "latexmkrc"];
+
+/// Value of a latexmk variable assigned like `$out_dir = 'build';`
+fn latexmk_variable(config: &str, name: &str) -> Option<String> {
+    config.lines().rev().find_map(|line| {
+        let line = line.split('#').next()?.trim();
+        let (variable, value) = line.split_once('=')?;
+        if variable.trim().strip_prefix('$')? != name {
+            return None;
+        }
+        let value = value.trim().trim_end_matches(';').trim();
+        let value = value.trim_matches(|c| c == '\'' || c == '"');
+        Some(value.to_string()).filter(|v| !v.is_empty())
+    })
+}
+
+/// Directories that latexmk is configured to write the log to. The log goes to `$aux_dir` if it
+/// is set, and to `$out_dir` otherwise.
+fn latexmk_dirs(dir: &Path) -> Vec<PathBuf> {
+    LATEXMKRC_NAMES
+        .iter()
+        .filter_map(|name| fs::read_to_string(dir.join(name)).ok())
+        .filter_map(|config| {
+            latexmk_variable(&config, "aux_dir").or_else(|| latexmk_variable(&config, "out_dir"))
+        })
+        .map(|d| dir.join(d))
+        .collect()
+}
+
+/// The log of a `.tex` file. The directory of the file, the usual build directories and the
+/// output directory of latexmk are searched, and the most recently written log is used.
+pub(crate) fn log_for_tex(tex: &Path) -> Option<PathBuf> {
+    let dir = tex.parent().unwrap_or(Path::new(""));
+    let name = Path::new(tex.file_name()?).with_extension("log");
+    let mut dirs = vec![dir.to_path_buf()];
+    dirs.extend(BUILD_DIRS.iter().map(|d| dir.join(d)));
+    dirs.extend(latexmk_dirs(dir));
+    dirs.into_iter()
+        .map(|d| d.join(&name))
+        .filter(|p| p.is_file())
+        .max_by_key(|p| {
+            fs::metadata(p)
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH)
+        })
+}
+
+/// The log of the document in a project directory: the first `.tex` file with a
+/// `\documentclass` that has a log
+pub(crate) fn project_log(dir: &Path) -> Option<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "tex"))
+        .collect();
+    files.sort();
+    files
+        .iter()
+        .filter(|p| {
+            fs::read(p)
+                .is_ok_and(|bytes| String::from_utf8_lossy(&bytes).contains("\\documentclass"))
+        })
+        .find_map(|p| log_for_tex(p))
+}
+
+/// The log to read for a file given on the command line. A `.tex` file is replaced by its log if
+/// one is found.
+pub(crate) fn log_path(file: &str) -> String {
+    let path = Path::new(file);
+    match path.extension().is_some_and(|e| e == "tex") {
+        true => log_for_tex(path)
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| file.to_string()),
+        false => file.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_logs() {
+        let dir = std::env::temp_dir().join(format!("texlog-locate-{}", std::process::id()));
+        fs::create_dir_all(dir.join("latex-out")).unwrap();
+        fs::write(dir.join("main.tex"), "\\documentclass{article}").unwrap();
+        fs::write(dir.join("notes.tex"), "Some notes").unwrap();
+        fs::write(
+            dir.join(".latexmkrc"),
+            "$out_dir = 'latex-out'; # build here\n",
+        )
+        .unwrap();
+        assert_eq!(log_for_tex(&dir.join("main.tex")), None);
+
+        fs::write(dir.join("latex-out/main.log"), "").unwrap();
+        fs::write(dir.join("latex-out/notes.log"), "").unwrap();
+        assert_eq!(
+            log_for_tex(&dir.join("main.tex")),
+            Some(dir.join("latex-out/main.log"))
+        );
+        assert_eq!(project_log(&dir), Some(dir.join("latex-out/main.log")));
+        assert_eq!(log_path("main.log"), "main.log");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,15 +1,28 @@
-use termion::{
-    self,
-    color::{self, Fg},
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io,
+    path::{Component, Path, PathBuf},
 };
 
-use std::path::PathBuf;
-
 const TEX_LOG_WIDTH: usize = 78;
 
 use crate::{
-    parser::{Node, TexDiagnostic, Visitor},
-    text::SourceText,
+    ansi,
+    baseline::Baseline,
+    error::{Error, Result},
+    filter::DiagnosticFilter,
+    json::Json,
+    lexer::MAX_PRINT_LINE,
+    magic::MagicCommentCache,
+    output::{self, OutputSink, ShortSink, Summary, TerminalSink},
+    parser::{
+        is_system_file, stopwatch, DiagnosticLevel, Engine, Node, ParseTiming, TexDiagnostic,
+        Visitor,
+    },
+    span::Span,
+    synctex::{PdfPosition, SyncTex},
+    text::{decode_caret_escapes, SourceText},
 };
 
 /// A diagnostic with a call trace
@@ -17,20 +30,247 @@ use crate::{
 pub struct TracedTexDiagnostic {
     call_stack: Vec<PathBuf>,
     diagnostic: TexDiagnostic,
+
+    /// Number of identical diagnostics merged into this one
+    count: usize,
+
+    /// Where the source line ended up in the PDF, if a SyncTeX file was given
+    pdf_position: Option<PdfPosition>,
+
+    /// Lines of the log around the diagnostic, if context was asked for
+    context: Option<LogContext>,
+
+    /// The file of the diagnostic as written in the log, before paths are resolved
+    logged_file: Option<PathBuf>,
+}
+
+/// Raw lines of the log before and after a diagnostic
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LogContext {
+    /// The lines before the diagnostic, with their 1-based line numbers in the log
+    pub before: Vec<(usize, String)>,
+
+    /// The lines after the diagnostic, with their 1-based line numbers in the log
+    pub after: Vec<(usize, String)>,
+}
+
+impl LogContext {
+    /// Up to `lines` lines of the log on either side of a span
+    fn around(source: &SourceText, span: Span, lines: usize) -> Self {
+        // Messages that start at a line break start on the next line
+        let (mut first, _) = source.row_col(span.start);
+        if source.row_col(span.start + 1).0 > first && span.end > span.start + 1 {
+            first += 1;
+        }
+        let (last, _) = source.row_col(span.end.saturating_sub(1).max(span.start));
+        let line = |row: usize| source.line(row).map(|l| (row, l.to_string()));
+        Self {
+            before: (first.saturating_sub(lines).max(1)..first)
+                .filter_map(line)
+                .collect(),
+            after: (last + 1..=last + lines).filter_map(line).collect(),
+        }
+    }
+}
+
+impl TracedTexDiagnostic {
+    pub(crate) fn new(call_stack: Vec<PathBuf>, diagnostic: TexDiagnostic) -> Self {
+        let logged_file = diagnostic
+            .source_file()
+            .map(Path::to_path_buf)
+            .or_else(|| call_stack.last().cloned());
+        Self {
+            logged_file,
+            call_stack,
+            diagnostic,
+            count: 1,
+            pdf_position: None,
+            context: None,
+        }
+    }
+
+    /// The file that the diagnostic was emitted in
+    pub fn file(&self) -> Option<&PathBuf> {
+        self.call_stack.last()
+    }
+
+    pub fn diagnostic(&self) -> &TexDiagnostic {
+        &self.diagnostic
+    }
+
+    /// Number of times the diagnostic occurred in the log
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Where the source line of the diagnostic ended up in the PDF
+    pub fn pdf_position(&self) -> Option<PdfPosition> {
+        self.pdf_position
+    }
+
+    /// The lines of the log around the diagnostic, if `Log::with_context` was used
+    pub fn context(&self) -> Option<&LogContext> {
+        self.context.as_ref()
+    }
+
+    /// Title of the diagnostic, with the number of occurrences if there are more than one
+    pub fn title(&self) -> String {
+        match self.count {
+            1 => self.diagnostic.kind.to_string(),
+            n => format!("{} (x{})", self.diagnostic.kind, n),
+        }
+    }
+
+    /// The tex file the diagnostic points to, falling back to the innermost open file
+    pub fn source_file(&self) -> Option<&Path> {
+        self.diagnostic
+            .source_file()
+            .or_else(|| self.file().map(|f| f.as_path()))
+    }
+
+    /// The file of the diagnostic as written in the log, whatever the path style
+    pub(crate) fn logged_file(&self) -> Option<&Path> {
+        self.logged_file.as_deref()
+    }
+
+    /// The files that were open when the diagnostic was emitted, outermost first
+    pub fn call_stack(&self) -> &[PathBuf] {
+        &self.call_stack
+    }
+
+    /// Format the diagnostic as a single `file:line:col: level: title` line, as understood by
+    /// editor quickfix lists
+    pub fn to_short_string(&self) -> String {
+        self.short_string(false)
+    }
+
+    /// The single line form, with the file name as a terminal hyperlink if `links` is set
+    pub(crate) fn short_string(&self, links: bool) -> String {
+        let file = match self.source_file() {
+            Some(f) if links => hyperlink(f, self.diagnostic.source_line(), &f.display()),
+            Some(f) => f.display().to_string(),
+            None => "<unknown>".to_string(),
+        };
+        let columns = self.diagnostic.location().and_then(|l| l.columns);
+        let position = match (self.diagnostic.source_line(), columns) {
+            (Some(line), Some((start, end))) => format!(":{}:{}-{}", line, start, end),
+            (Some(line), None) => format!(":{}", line),
+            (None, _) => "".to_string(),
+        };
+        format!(
+            "{}{}: {}: {}",
+            file,
+            position,
+            self.diagnostic.level(),
+            self.title()
+        )
+    }
+}
+
+/// Options for rendering diagnostics as text blocks
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderOptions {
+    /// Use ANSI escape codes for color
+    pub color: bool,
+
+    /// Width of the title bar. The terminal width is used if `None`.
+    pub width: Option<usize>,
+
+    /// Make file names clickable with OSC 8 terminal hyperlinks
+    pub links: bool,
+
+    /// Collapse the files of the TeX distribution in the call trace
+    pub user_files_only: bool,
+
+    /// Number of lines of the call trace to print, counting from the innermost file. All of
+    /// them are printed if `None`.
+    pub trace_depth: Option<usize>,
+}
+
+/// A line of the printed call trace
+enum TraceLine<'a> {
+    File(&'a Path),
+
+    /// Files of the TeX distribution that are left out
+    System(usize),
+
+    /// Outer files that are left out because of the trace depth
+    Outer(usize),
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            color: true,
+            width: None,
+            links: false,
+            user_files_only: false,
+            trace_depth: None,
+        }
+    }
 }
 
-impl ToString for TracedTexDiagnostic {
-    fn to_string(&self) -> String {
-        let width = match termion::terminal_size() {
-            Ok((w, _h)) => w as usize,
-            Err(_) => TEX_LOG_WIDTH,
+/// A `file://` URI for a path, with the line as fragment
+fn file_uri(path: &Path, line: Option<usize>) -> String {
+    let path = match path.is_absolute() {
+        true => path.to_path_buf(),
+        false => std::env::current_dir().unwrap_or_default().join(path),
+    };
+    let mut uri = "file://".to_string();
+    for b in normalize(&path).to_string_lossy().bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(b as char)
+            }
+            _ => uri += &format!("%{:02X}", b),
+        }
+    }
+    if let Some(line) = line {
+        uri += &format!("#L{}", line);
+    }
+    uri
+}
+
+/// Text wrapped in an OSC 8 escape sequence, which makes it a link to the file in terminals that
+/// support it
+pub(crate) fn hyperlink(path: &Path, line: Option<usize>, text: &dyn Display) -> String {
+    format!(
+        "\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\",
+        file_uri(path, line),
+        text
+    )
+}
+
+/// Width of the terminal, if there is one
+#[cfg(feature = "terminal")]
+fn terminal_width() -> Option<usize> {
+    termion::terminal_size().ok().map(|(w, _h)| w as usize)
+}
+
+#[cfg(not(feature = "terminal"))]
+fn terminal_width() -> Option<usize> {
+    None
+}
+
+impl TracedTexDiagnostic {
+    /// Render the diagnostic as a block with a title bar, message and call trace
+    pub fn render(&self, options: &RenderOptions) -> String {
+        let paint = |code: &'static str| match options.color {
+            true => code,
+            false => "",
         };
-        let title = self.diagnostic.kind.to_string();
-        let side_padding = (width - title.len()) / 2 - 1;
+
+        let width = options
+            .width
+            .or_else(terminal_width)
+            .unwrap_or(TEX_LOG_WIDTH);
+        let title = format!("{} [{}]", self.title(), self.diagnostic.kind.code());
+        let side_padding = (width.saturating_sub(title.len()) / 2).saturating_sub(1);
 
         let title_color = match self.diagnostic.level() {
-            crate::parser::DiagnosticLevel::Warning => Fg(color::Yellow).to_string(),
-            crate::parser::DiagnosticLevel::Error => Fg(color::Red).to_string(),
+            crate::parser::DiagnosticLevel::Info => paint(ansi::CYAN),
+            crate::parser::DiagnosticLevel::Warning => paint(ansi::YELLOW),
+            crate::parser::DiagnosticLevel::Error => paint(ansi::RED),
         };
 
         let mut s = format!(
@@ -40,19 +280,152 @@ impl ToString for TracedTexDiagnostic {
             title,
             "=".repeat(side_padding),
             "=".repeat((width + title.len()) % 2), // Add one extra padding if uneven
-            Fg(color::Reset),
+            paint(ansi::RESET),
         );
+        let faint = |line: &(usize, String)| match options.color {
+            true => format!("{}{}{}\n", ansi::FAINT, line.1, ansi::NO_FAINT),
+            false => format!("{}\n", line.1),
+        };
+        if let Some(context) = &self.context {
+            s.extend(context.before.iter().map(faint));
+        }
         s += self.diagnostic.message.as_str();
-        s += "\n\n";
-        s += Fg(color::Blue).to_string().as_str();
-        for (i, call) in self.call_stack.iter().enumerate() {
-            s += &format!("{}{}\n", "  ".repeat(i), call.display());
+        s += "\n";
+        if let Some(context) = &self.context {
+            s.extend(context.after.iter().map(faint));
         }
-        s += Fg(color::Reset).to_string().as_str();
+        s += "\n";
+        if let Some(hint) = self.diagnostic.kind.hint() {
+            s += &format!(
+                "{}hint:{} {}\n\n",
+                paint(ansi::CYAN),
+                paint(ansi::RESET),
+                hint
+            );
+        }
+        if let Some(suggestion) = self.diagnostic.suggestion() {
+            s += &format!(
+                "{}help:{} {}\n\n",
+                paint(ansi::LIGHT_GREEN),
+                paint(ansi::RESET),
+                suggestion
+            );
+        }
+        if let (Some(file), Some(line)) = (self.source_file(), self.diagnostic.source_line()) {
+            let location = format!("{}:{}", file.display(), line);
+            match options.links {
+                true => s += &format!("--> {}", hyperlink(file, Some(line), &location)),
+                false => s += &format!("--> {}", location),
+            }
+            if let Some(column) = self.diagnostic.location().and_then(|l| l.column()) {
+                s += &format!(":{}", column);
+            }
+            match (self.diagnostic.page(), self.pdf_position) {
+                (Some(page), Some(position)) => s += &format!(" (page {}, {})", page, position),
+                (None, Some(position)) => s += &format!(" ({})", position),
+                (Some(page), None) => s += &format!(" (page {})", page),
+                (None, None) => {}
+            }
+            s += "\n";
+            if let Some(snippet) = self.snippet(title_color, paint(ansi::RESET)) {
+                s += &snippet;
+            }
+        }
+        s += paint(ansi::BLUE);
+        for (i, line) in self.trace(options).iter().enumerate() {
+            let indent = "  ".repeat(i);
+            match line {
+                TraceLine::File(call) if options.links => {
+                    s += &format!("{}{}\n", indent, hyperlink(call, None, &call.display()))
+                }
+                TraceLine::File(call) => s += &format!("{}{}\n", indent, call.display()),
+                TraceLine::System(1) => s += &format!("{}… (1 system file)\n", indent),
+                TraceLine::System(n) => s += &format!("{}… ({} system files)\n", indent, n),
+                TraceLine::Outer(1) => s += &format!("{}… (1 outer file)\n", indent),
+                TraceLine::Outer(n) => s += &format!("{}… ({} outer files)\n", indent, n),
+            }
+        }
+        s += paint(ansi::RESET);
         s
     }
 }
 
+impl TracedTexDiagnostic {
+    /// The lines of the call trace, outermost file first
+    fn trace(&self, options: &RenderOptions) -> Vec<TraceLine<'_>> {
+        let mut lines = vec![];
+        for call in &self.call_stack {
+            if !(options.user_files_only && is_system_file(call)) {
+                lines.push(TraceLine::File(call));
+                continue;
+            }
+            match lines.last_mut() {
+                Some(TraceLine::System(n)) => *n += 1,
+                _ => lines.push(TraceLine::System(1)),
+            }
+        }
+
+        let depth = options.trace_depth.unwrap_or(usize::MAX);
+        if lines.len() > depth {
+            let outer: usize = lines
+                .drain(..lines.len() - depth)
+                .map(|line| match line {
+                    TraceLine::System(n) | TraceLine::Outer(n) => n,
+                    TraceLine::File(_) => 1,
+                })
+                .sum();
+            lines.insert(0, TraceLine::Outer(outer));
+        }
+        lines
+    }
+
+    /// Rewrite the file paths of the diagnostic for a log file located in `base_dir`
+    pub(crate) fn resolve_paths(&mut self, base_dir: &Path, style: PathStyle) {
+        if style == PathStyle::Logged {
+            return;
+        }
+        for file in self.call_stack.iter_mut() {
+            *file = style.resolve(base_dir, file);
+        }
+        if let Some(file) = self.diagnostic.source_file.as_mut() {
+            *file = style.resolve(base_dir, file);
+        }
+    }
+
+    /// Decode the `^^` escaped characters in the message
+    pub(crate) fn decode_escapes(&mut self) {
+        self.diagnostic.message = decode_caret_escapes(&self.diagnostic.message);
+    }
+
+    /// The offending source line with a caret under the error location, like rustc does.
+    /// Returns `None` if the source file cannot be read.
+    fn snippet(&self, caret_color: &str, reset: &str) -> Option<String> {
+        let line_number = self.diagnostic.source_line()?;
+        let source = std::fs::read_to_string(self.source_file()?).ok()?;
+        let line = source.lines().nth(line_number.checked_sub(1)?)?;
+
+        let gutter = " ".repeat(line_number.to_string().len());
+        let mut s = format!("{} |\n{} | {}\n", gutter, line_number, line);
+        if let Some((start, end)) = self.diagnostic.location().and_then(|l| l.columns) {
+            s += &format!(
+                "{} | {}{}{}{}\n",
+                gutter,
+                " ".repeat(start - 1),
+                caret_color,
+                "^".repeat(end - start),
+                reset,
+            );
+        }
+        Some(s)
+    }
+}
+
+impl Display for TracedTexDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(&RenderOptions::default()))
+    }
+}
+
 struct DiagnosticGetter {
     call_stack: Vec<PathBuf>,
     diagsnostics: Vec<TracedTexDiagnostic>,
@@ -71,33 +444,377 @@ impl DiagnosticGetter {
     }
 }
 
-impl Visitor for DiagnosticGetter {
-    fn visit_node(&mut self, node: &Node) {
+impl Visitor<'_> for DiagnosticGetter {
+    fn enter_node(&mut self, node: &Node) {
         self.call_stack.push(PathBuf::from(node.file.clone()));
         for d in node.diagnostics() {
-            self.diagsnostics.push(TracedTexDiagnostic {
-                call_stack: self.call_stack.clone(),
-                diagnostic: d.clone(),
-            })
+            self.diagsnostics
+                .push(TracedTexDiagnostic::new(self.call_stack.clone(), d.clone()))
         }
-        self.do_visit_node(node);
+    }
+
+    fn leave_node(&mut self, _node: &Node) {
         self.call_stack.pop();
     }
 }
 
+/// Order in which diagnostics are emitted
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortOrder {
+    /// Errors before warnings
+    Severity,
+    /// Grouped by the file that emitted them
+    File,
+    /// The order they appear in the log
+    Position,
+}
+
+/// How file paths from the log are reported
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PathStyle {
+    /// As they appear in the log, relative to the directory TeX ran in
+    #[default]
+    Logged,
+    /// Joined onto the directory of the log file
+    Resolved,
+    /// Resolved and made absolute with symlinks followed
+    Canonical,
+}
+
+impl PathStyle {
+    /// Resolve a path from a log file located in `base_dir`
+    pub fn resolve(self, base_dir: &Path, path: &Path) -> PathBuf {
+        match self {
+            PathStyle::Logged => path.to_path_buf(),
+            PathStyle::Resolved => normalize(&base_dir.join(path)),
+            PathStyle::Canonical => {
+                let resolved = base_dir.join(path);
+                resolved
+                    .canonicalize()
+                    .unwrap_or_else(|_| normalize(&resolved))
+            }
+        }
+    }
+}
+
+/// Remove `.` and `..` components without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            c => normalized.push(c),
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        normalized.push(".");
+    }
+    normalized
+}
+
+/// The document TeX wrote, as reported at the end of the log
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutputInfo {
+    /// Written file, or `None` if there were no pages of output
+    pub file: Option<PathBuf>,
+    pub pages: usize,
+
+    /// Size of the file. XeTeX does not report it.
+    pub bytes: Option<u64>,
+}
+
+impl Display for OutputInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(file) = &self.file else {
+            return write!(f, "no pages of output");
+        };
+        let plural = if self.pages == 1 { "" } else { "s" };
+        write!(f, "{} ({} page{}", file.display(), self.pages, plural)?;
+        if let Some(bytes) = self.bytes {
+            match bytes {
+                0..1024 => write!(f, ", {} bytes", bytes)?,
+                1024..1048576 => write!(f, ", {:.1} KiB", bytes as f64 / 1024.0)?,
+                _ => write!(f, ", {:.1} MiB", bytes as f64 / 1048576.0)?,
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+/// The engine and format that wrote a log, from the banner at its start, like `This is pdfTeX,
+/// Version 3.141592653-2.6-1.40.25 (TeX Live 2023) (preloaded format=pdflatex)` and
+/// `LaTeX2e <2023-06-01>`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LogMeta {
+    pub engine: Engine,
+    pub engine_version: Option<String>,
+
+    /// Name of the format, like `LaTeX2e`
+    pub format: Option<String>,
+    pub format_date: Option<String>,
+
+    /// Words of node memory that LuaTeX had not freed at the end of the run, from the
+    /// `1049 words of node memory still in use:` line of its memory report
+    pub node_memory: Option<usize>,
+}
+
+impl LogMeta {
+    /// Number of lines at the start of a run that are searched for the format
+    const HEADER_LINES: usize = 20;
+
+    pub fn parse(run: &str, engine: Engine) -> Self {
+        let mut meta = LogMeta {
+            engine,
+            ..Default::default()
+        };
+        let mut lines = run.lines().take(Self::HEADER_LINES);
+        let Some(banner) = lines.next() else {
+            return meta;
+        };
+        if let Some((_, version)) = banner.split_once(", Version ") {
+            meta.engine_version = version.split_whitespace().next().map(str::to_string);
+        }
+        if let Some((_, format)) = banner.split_once("format=") {
+            let mut words = format.split_whitespace();
+            meta.format = words.next().map(|w| w.trim_end_matches(')').to_string());
+            meta.format_date = words.next().map(|w| w.trim_end_matches(')').to_string());
+        }
+        if let Some(latex) = lines.find_map(|l| l.strip_prefix("LaTeX2e <")) {
+            meta.format = Some("LaTeX2e".to_string());
+            meta.format_date = latex.split_once('>').map(|(date, _)| date.to_string());
+        }
+        if engine == Engine::LuaTex {
+            meta.node_memory = run.lines().rev().find_map(|l| {
+                let words = l
+                    .trim()
+                    .strip_suffix(" words of node memory still in use:")?;
+                words.parse().ok()
+            });
+        }
+        meta
+    }
+}
+
+impl Display for LogMeta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.engine)?;
+        if let Some(version) = &self.engine_version {
+            write!(f, " {}", version)?;
+        }
+        if let Some(format) = &self.format {
+            write!(f, ", {}", format)?;
+            if let Some(date) = &self.format_date {
+                write!(f, " {}", date)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 pub struct Log {
     pub(crate) info: String,
     pub(crate) source: SourceText,
+    /// Root node of the final run
     pub(crate) root_node: Node,
+
+    /// Root nodes of the runs before the final one, when several runs are concatenated
+    pub(crate) runs: Vec<Node>,
+
+    /// Byte offset of the final run in the source
+    pub(crate) run_start: usize,
+
+    /// Report diagnostics from every run instead of only the final one
+    pub(crate) all_runs: bool,
+
+    /// Path of the log file, if it was read from disk
+    pub(crate) path: Option<PathBuf>,
+
+    /// Decides which diagnostics are returned by `get_diagnostics`
+    pub(crate) filter: DiagnosticFilter,
+
+    /// Order of emitted diagnostics. Warnings are emitted before errors if `None`.
+    pub(crate) sort: Option<SortOrder>,
+
+    /// How file paths in diagnostics are reported
+    pub(crate) paths: PathStyle,
+
+    /// Keep `^^` escapes in messages instead of decoding them
+    pub(crate) raw_escapes: bool,
+
+    /// Engine that wrote the log
+    pub(crate) engine: Engine,
+
+    /// Places in the final run where the parentheses did not match up
+    pub(crate) parse_warnings: Vec<String>,
+
+    /// Diagnostics that are known and not reported again
+    pub(crate) baseline: Option<Baseline>,
+
+    /// Positions of the source lines in the PDF
+    pub(crate) synctex: Option<SyncTex>,
+
+    /// Time spent reading and parsing the log
+    pub(crate) timing: ParseTiming,
+
+    /// Hide diagnostics on lines marked with a `% texlog:` comment in the tex sources
+    pub(crate) magic_comments: bool,
+
+    /// Number of log lines to attach before and after every diagnostic
+    pub(crate) context: usize,
 }
 
 impl Log {
-    pub fn from_path<P>(path: P) -> Self
+    /// A log with the default settings
+    pub(crate) fn new(source: SourceText, root_node: Node) -> Self {
+        Self {
+            info: String::new(),
+            source,
+            root_node,
+            runs: vec![],
+            run_start: 0,
+            all_runs: false,
+            path: None,
+            filter: DiagnosticFilter::default(),
+            sort: None,
+            paths: PathStyle::default(),
+            raw_escapes: false,
+            engine: Engine::default(),
+            parse_warnings: vec![],
+            baseline: None,
+            synctex: None,
+            timing: ParseTiming::default(),
+            magic_comments: true,
+            context: 0,
+        }
+    }
+
+    /// Read a log file. Biber's `.blg` logs and the `.ilg` logs of makeindex and xindy are
+    /// recognized by their extension, and saved terminal output of Tectonic by its content.
+    pub fn from_path<P>(path: P) -> Result<Self>
     where
         P: AsRef<std::path::Path>,
     {
-        let source = SourceText::from_file(path).unwrap();
-        crate::parser::parse_source(source)
+        let path = path.as_ref();
+        let reading = stopwatch();
+        let source = SourceText::from_file(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let reading = reading();
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut log = match path.extension().and_then(|e| e.to_str()) {
+            Some("blg") => crate::blg::parse_blg(source, file_name),
+            Some("ilg") => crate::ilg::parse_ilg(source, file_name),
+            _ if crate::tectonic::is_tectonic_output(source.as_str()) => {
+                crate::tectonic::parse_tectonic(source)
+            }
+            _ => crate::parser::parse_source(source)?,
+        };
+        log.path = Some(path.to_path_buf());
+        log.timing.reading = reading;
+        Ok(log)
+    }
+
+    /// Do not report the diagnostics recorded in the baseline
+    pub fn with_baseline(mut self, baseline: Baseline) -> Self {
+        self.baseline = Some(baseline);
+        self
+    }
+
+    /// Attach up to `lines` raw lines of the log before and after every diagnostic, which are
+    /// printed around its message
+    pub fn with_context(mut self, lines: usize) -> Self {
+        self.context = lines;
+        self
+    }
+
+    /// Hide diagnostics on lines marked with a `% texlog: ignore ...` comment in the tex sources,
+    /// which is done by default
+    pub fn with_magic_comments(mut self, magic_comments: bool) -> Self {
+        self.magic_comments = magic_comments;
+        self
+    }
+
+    /// Only report diagnostics matching the filter
+    pub fn with_filter(mut self, filter: DiagnosticFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Emit diagnostics in the given order
+    pub fn with_sort(mut self, sort: SortOrder) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+
+    /// Report file paths in the given style
+    pub fn with_paths(mut self, paths: PathStyle) -> Self {
+        self.paths = paths;
+        self
+    }
+
+    /// The text of the log
+    pub fn source(&self) -> &SourceText {
+        &self.source
+    }
+
+    /// The root file of the final run, which the other files are opened from
+    pub fn root_node(&self) -> &Node {
+        &self.root_node
+    }
+
+    /// Places where the parentheses in the log did not match up, so that the files some
+    /// diagnostics are attributed to may be wrong
+    pub fn parse_warnings(&self) -> &[String] {
+        &self.parse_warnings
+    }
+
+    /// Time spent reading and parsing the log, and the number of tokens and files
+    pub fn timing(&self) -> &ParseTiming {
+        &self.timing
+    }
+
+    /// The TeX engine that wrote the log
+    pub fn engine(&self) -> Engine {
+        self.engine
+    }
+
+    /// The engine and format of the final run, from the banner at its start
+    pub fn meta(&self) -> LogMeta {
+        LogMeta::parse(self.final_run(), self.engine)
+    }
+
+    /// Report diagnostics from every run in the log, not just the final one
+    pub fn with_all_runs(mut self, all_runs: bool) -> Self {
+        self.all_runs = all_runs;
+        self
+    }
+
+    /// Number of runs in the log
+    pub fn run_count(&self) -> usize {
+        self.runs.len() + 1
+    }
+
+    /// Source text of the final run
+    pub(crate) fn final_run(&self) -> &str {
+        &self.source.as_str()[self.run_start..]
+    }
+
+    /// Keep `^^` escaped characters in messages as TeX wrote them
+    pub fn with_raw_escapes(mut self, raw_escapes: bool) -> Self {
+        self.raw_escapes = raw_escapes;
+        self
     }
 
     /// Returns the call stack at an index in the log file. Returns `None` if the index is outside
@@ -111,7 +828,7 @@ impl Log {
     fn trace_from_node(index: usize, root_node: &Node) -> Vec<PathBuf> {
         let file = PathBuf::from(&root_node.file);
         for sub_node in &root_node.calls {
-            if sub_node.start_pos <= index && index <= sub_node.end_pos {
+            if sub_node.span.start <= index && index <= sub_node.span.end {
                 let mut trace = Self::trace_from_node(index, sub_node);
                 trace.push(file);
                 return trace;
@@ -125,17 +842,181 @@ impl Log {
     }
 
     pub fn get_diagnostics(&self) -> Vec<TracedTexDiagnostic> {
+        let diagnostics = match &self.baseline {
+            Some(baseline) => baseline.unknown(self.get_undeduped_diagnostics()),
+            None => self.get_undeduped_diagnostics(),
+        };
+
+        if !self.filter.dedup {
+            return diagnostics;
+        }
+
+        // The first occurrences of each message, whose kinds are compared to find repeats
+        let mut firsts: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut deduped: Vec<TracedTexDiagnostic> = Vec::new();
+        for d in diagnostics {
+            let indices = firsts.entry(d.diagnostic.message.clone()).or_default();
+            match indices
+                .iter()
+                .find(|i| deduped[**i].diagnostic.kind == d.diagnostic.kind)
+            {
+                Some(i) => deduped[*i].count += 1,
+                None => {
+                    indices.push(deduped.len());
+                    deduped.push(d);
+                }
+            }
+        }
+        deduped
+    }
+
+    /// The warnings of `get_diagnostics`, with the files that were open when they were emitted
+    pub fn warnings(&self) -> Vec<TracedTexDiagnostic> {
+        self.diagnostics_at(DiagnosticLevel::Warning)
+    }
+
+    /// The errors of `get_diagnostics`, with the files that were open when they were emitted
+    pub fn errors(&self) -> Vec<TracedTexDiagnostic> {
+        self.diagnostics_at(DiagnosticLevel::Error)
+    }
+
+    fn diagnostics_at(&self, level: DiagnosticLevel) -> Vec<TracedTexDiagnostic> {
+        self.get_diagnostics()
+            .into_iter()
+            .filter(|d| d.diagnostic.level() == level)
+            .collect()
+    }
+
+    /// Every diagnostic of the reported runs, before the filter is applied
+    fn unfiltered_diagnostics(&self) -> Vec<TracedTexDiagnostic> {
         let mut getter = DiagnosticGetter::new();
+        if self.all_runs {
+            for run in &self.runs {
+                getter.populate(run);
+            }
+        }
         getter.populate(&self.root_node);
         getter.diagsnostics
     }
 
+    /// Magic comments that could not be parsed in the files that diagnostics point to, as
+    /// `file: line N: message`
+    pub fn magic_comment_errors(&self) -> Vec<String> {
+        if !self.magic_comments {
+            return vec![];
+        }
+        let mut magic_comments = MagicCommentCache::new(self);
+        for d in self.unfiltered_diagnostics() {
+            magic_comments.hides(&d);
+        }
+        magic_comments.errors()
+    }
+
+    /// Every diagnostic matching the filter, without merging repeated ones
+    pub(crate) fn get_undeduped_diagnostics(&self) -> Vec<TracedTexDiagnostic> {
+        let mut magic_comments = MagicCommentCache::new(self);
+        self.unfiltered_diagnostics()
+            .into_iter()
+            .map(|mut d| {
+                d.diagnostic.level = self.filter.level_of(&d.diagnostic.kind);
+                d
+            })
+            .filter(|d| self.filter.matches(d))
+            .filter(|d| !self.magic_comments || !magic_comments.hides(d))
+            .map(|mut d| {
+                d.resolve_paths(&self.base_dir(), self.paths);
+                if !self.raw_escapes {
+                    d.decode_escapes();
+                }
+                if let (Some(synctex), Some(line)) = (&self.synctex, d.diagnostic.source_line()) {
+                    d.pdf_position = d.source_file().and_then(|f| synctex.locate(f, line));
+                }
+                if self.context > 0 {
+                    d.context = Some(LogContext::around(
+                        &self.source,
+                        d.diagnostic.span,
+                        self.context,
+                    ));
+                }
+                d
+            })
+            .collect()
+    }
+
+    /// The file TeX wrote the document to, from `Output written on main.pdf (3 pages).`. XeTeX
+    /// writes an `.xdv` file that xdvipdfmx turns into the PDF.
+    pub fn output_file(&self) -> Option<PathBuf> {
+        self.output_info()?.file
+    }
+
+    /// What TeX wrote, from the last line of the log: `Output written on main.pdf (12 pages,
+    /// 431234 bytes).` or `No pages of output.`. Returns `None` if TeX stopped before that.
+    pub fn output_info(&self) -> Option<OutputInfo> {
+        let mut lines = self.final_run().lines();
+        let mut line = lines
+            .find(|l| l.starts_with("Output written on ") || l.starts_with("No pages of output"))?;
+        if line.starts_with("No pages of output") {
+            return Some(OutputInfo {
+                file: None,
+                pages: 0,
+                bytes: None,
+            });
+        }
+
+        // A long path makes TeX wrap the line
+        let mut text = String::new();
+        while line.chars().count() == MAX_PRINT_LINE {
+            text += line;
+            line = lines.next().unwrap_or("");
+        }
+        text += line;
+
+        let rest = text["Output written on ".len()..].trim_end_matches('.');
+        let (file, numbers) = rest.rsplit_once(" (").unwrap_or((rest, ""));
+        let mut numbers = numbers.trim_end_matches(')').split(", ");
+        let mut number = |unit: &str| {
+            numbers
+                .next()
+                .and_then(|n| n.trim_end_matches('s').strip_suffix(unit))
+                .and_then(|n| n.parse::<u64>().ok())
+        };
+        let pages = number(" page").unwrap_or(0) as usize;
+        let bytes = number(" byte");
+        Some(OutputInfo {
+            file: Some(self.paths.resolve(&self.base_dir(), Path::new(file))),
+            pages,
+            bytes,
+        })
+    }
+
+    /// Whether TeX finished the document. Returns `false` if the log reports an emergency stop
+    /// or a fatal error, in which case no PDF was produced.
+    pub fn compilation_succeeded(&self) -> bool {
+        !self.final_run().lines().any(|line| {
+            line.starts_with("! Emergency stop")
+                || line.contains("==> Fatal error occurred")
+                || line.starts_with("Fatal error occurred")
+        })
+    }
+
+    /// Whether the log asks for another run to get cross-references, citations or outlines
+    /// right. Filters do not apply.
+    pub fn needs_rerun(&self) -> bool {
+        let mut getter = DiagnosticGetter::new();
+        getter.populate(&self.root_node);
+        getter
+            .diagsnostics
+            .iter()
+            .any(|d| d.diagnostic.requests_rerun())
+    }
+
     pub fn get_warnings_and_errors(&self) -> (Vec<TracedTexDiagnostic>, Vec<TracedTexDiagnostic>) {
         let diags = self.get_diagnostics();
         let mut ws = Vec::with_capacity(30);
         let mut es = Vec::with_capacity(5);
         for d in diags {
             match d.diagnostic.level() {
+                crate::parser::DiagnosticLevel::Info => {}
                 crate::parser::DiagnosticLevel::Warning => ws.push(d),
                 crate::parser::DiagnosticLevel::Error => es.push(d),
             }
@@ -143,14 +1024,84 @@ impl Log {
         (ws, es)
     }
 
-    pub fn print_diagnostics(&self) {
-        let (ws, es) = self.get_warnings_and_errors();
-        for w in ws {
-            println!("\n{}", w.to_string());
+    /// Number of reported warnings and errors, and what TeX wrote with which engine
+    pub fn summary(&self) -> Summary {
+        Summary::from_diagnostics(&self.get_diagnostics())
+            .with_output(self.output_info())
+            .with_meta(self.meta())
+    }
+
+    /// The sorted diagnostics and the summary, as the document of the `json` output format. It is
+    /// built in memory, for embedding texlog where there is no terminal, like in a browser.
+    pub fn to_json(&self) -> String {
+        let ds = self.get_sorted_diagnostics();
+        let summary = Summary::from_diagnostics(&ds)
+            .with_output(self.output_info())
+            .with_meta(self.meta());
+        output::json_report(ds.iter().map(Json::from).collect(), &summary).to_string()
+    }
+
+    /// Diagnostics in the order they should be emitted
+    pub fn get_sorted_diagnostics(&self) -> Vec<TracedTexDiagnostic> {
+        let mut ds = self.get_diagnostics();
+        match self.sort {
+            None => {
+                let (ws, es) = ds
+                    .into_iter()
+                    .partition(|d| d.diagnostic.level() < DiagnosticLevel::Error);
+                ds = ws;
+                ds.extend::<Vec<_>>(es);
+            }
+            Some(SortOrder::Severity) => ds.sort_by(|a, b| {
+                b.diagnostic
+                    .level()
+                    .cmp(&a.diagnostic.level())
+                    .then(a.diagnostic.span.start.cmp(&b.diagnostic.span.start))
+            }),
+            Some(SortOrder::File) => ds.sort_by(|a, b| {
+                a.file()
+                    .cmp(&b.file())
+                    .then(a.diagnostic.span.start.cmp(&b.diagnostic.span.start))
+            }),
+            Some(SortOrder::Position) => ds.sort_by_key(|d| d.diagnostic.span.start),
+        }
+        ds
+    }
+
+    /// Send the sorted diagnostics to every sink, and then the summary
+    pub fn emit(&self, sinks: &mut [Box<dyn OutputSink>]) -> io::Result<()> {
+        let summary = self.emit_diagnostics(sinks)?;
+        for sink in sinks.iter_mut() {
+            sink.summary(&summary)?;
         }
-        for e in es {
-            println!("\n{}", e.to_string());
+        Ok(())
+    }
+
+    /// Write the diagnostics to the sinks without finishing them with the summary, so that the
+    /// diagnostics of several logs can be written to the same sinks
+    pub fn emit_diagnostics(&self, sinks: &mut [Box<dyn OutputSink>]) -> io::Result<Summary> {
+        let ds = self.get_sorted_diagnostics();
+        for d in &ds {
+            for sink in sinks.iter_mut() {
+                sink.diagnostic(d)?;
+            }
         }
+        Ok(Summary::from_diagnostics(&ds)
+            .with_output(self.output_info())
+            .with_meta(self.meta()))
+    }
+
+    pub fn print_diagnostics(&self) {
+        let sink = TerminalSink::new(Box::new(io::stdout()), RenderOptions::default());
+        self.emit(&mut [Box::new(sink)])
+            .expect("Could not write to stdout.")
+    }
+
+    /// Print one line per diagnostic in the `file:line:col: level: title` format
+    pub fn print_diagnostics_short(&self) {
+        let sink = ShortSink::new(Box::new(io::stdout()));
+        self.emit(&mut [Box::new(sink)])
+            .expect("Could not write to stdout.")
     }
 }
 
@@ -162,9 +1113,64 @@ mod tests {
 
     #[test]
     fn warnings() {
-        let log = Log::from_path("./test/main.log");
+        let log = Log::from_path("./test/main.log").unwrap();
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 35);
+    }
+
+    #[test]
+    fn dedup() {
+        let log = Log::from_path("./test/main.log")
+            .unwrap()
+            .with_filter(DiagnosticFilter {
+                dedup: true,
+                ..Default::default()
+            });
+        let ds = log.get_diagnostics();
+        assert!(ds.len() < 35);
+        assert_eq!(ds.iter().map(|d| d.count()).sum::<usize>(), 35);
+    }
+
+    #[test]
+    fn sort_by_position() {
+        let log = Log::from_path("./test/main.log")
+            .unwrap()
+            .with_sort(SortOrder::Position);
+        let ds = log.get_sorted_diagnostics();
+        assert_eq!(ds.len(), 35);
+        assert!(ds
+            .windows(2)
+            .all(|w| w[0].diagnostic.span.start <= w[1].diagnostic.span.start));
+    }
+
+    #[test]
+    fn resolve_paths() {
+        let log = Log::from_path("./test/main.log")
+            .unwrap()
+            .with_paths(PathStyle::Resolved);
         let ds = log.get_diagnostics();
-        assert_eq!(ds.len(), 34);
+        assert_eq!(ds[0].call_stack()[0], PathBuf::from("test/main.tex"));
+        assert_eq!(
+            ds[0].source_file().unwrap().parent(),
+            Some(Path::new("test"))
+        );
+
+        assert_eq!(
+            normalize(Path::new("build/../chapters/./intro.tex")),
+            PathBuf::from("chapters/intro.tex")
+        );
+        assert_eq!(normalize(Path::new("../a.tex")), PathBuf::from("../a.tex"));
+    }
+
+    #[test]
+    fn min_level() {
+        let log = Log::from_path("./test/main.log")
+            .unwrap()
+            .with_filter(DiagnosticFilter {
+                min_level: DiagnosticLevel::Error,
+                ..Default::default()
+            });
+        assert!(log.get_diagnostics().is_empty());
     }
 
     #[test]
@@ -198,9 +1204,150 @@ Enter file name:
 )
 ";
         let source = SourceText::new(text.to_string());
-        let log = parse_source(source);
+        let log = parse_source(source).unwrap();
         let ds = log.get_diagnostics();
         log.print_diagnostics();
         assert_eq!(ds.len(), 5);
+        assert!(log.warnings().is_empty());
+
+        let errors = log.errors();
+        assert_eq!(errors.len(), 5);
+        assert_eq!(
+            errors[4].call_stack(),
+            &[
+                PathBuf::from("./main.tex"),
+                PathBuf::from("./some/math/doc.tex")
+            ]
+        );
+    }
+
+    #[test]
+    fn snippet() {
+        let dir = std::env::temp_dir().join(format!("texlog-snippet-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let tex = dir.join("main.tex");
+        std::fs::write(&tex, "\\documentclass{article}\n\\dtae{December 2004}\n").unwrap();
+
+        let text = format!(
+            "({}\n! Undefined control sequence.\nl.2 \\dtae\n               {{December 2004}}\n)",
+            tex.display()
+        );
+        let log = parse_source(SourceText::new(text)).unwrap();
+        let ds = log.get_diagnostics();
+        let rendered = ds[0].render(&RenderOptions {
+            color: false,
+            width: Some(40),
+            ..Default::default()
+        });
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(rendered.contains("2 | \\dtae{December 2004}\n  | ^^^^^\n"));
+    }
+
+    #[test]
+    fn hyperlinks() {
+        assert_eq!(
+            hyperlink(Path::new("/tmp/./my file.tex"), Some(3), &"my file.tex"),
+            "\x1b]8;;file:///tmp/my%20file.tex#L3\x1b\\my file.tex\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn user_files_only() {
+        let text = "(./main.tex (/usr/share/texlive/texmf-dist/tex/latex/base/article.cls (/usr/share/texlive/texmf-dist/tex/latex/base/size10.clo (./local.sty\n\nLaTeX Warning: Reference `a' on page 1 undefined on input line 3.\n\n))))";
+        let log = parse_source(SourceText::new(text.to_string()))
+            .unwrap()
+            .with_paths(PathStyle::Logged);
+        let rendered = log.get_diagnostics()[0].render(&RenderOptions {
+            color: false,
+            user_files_only: true,
+            ..Default::default()
+        });
+        assert!(rendered.ends_with("./main.tex\n  … (2 system files)\n    ./local.sty\n"));
+    }
+
+    #[test]
+    fn trace_depth() {
+        let text = "(./main.tex (./a.tex (./b.tex (./c.tex\n\nLaTeX Warning: Reference `a' on page 1 undefined on input line 3.\n\n))))";
+        let log = parse_source(SourceText::new(text.to_string()))
+            .unwrap()
+            .with_paths(PathStyle::Logged);
+        let rendered = log.get_diagnostics()[0].render(&RenderOptions {
+            color: false,
+            trace_depth: Some(2),
+            ..Default::default()
+        });
+        assert!(rendered.ends_with("\n… (2 outer files)\n  ./b.tex\n    ./c.tex\n"));
+    }
+
+    #[test]
+    fn output_info() {
+        let dir = "/home/user/documents/a-rather-long-directory-name/thesis";
+        let line = format!(
+            "Output written on {}/main.pdf (12 pages, 431234 bytes).",
+            dir
+        );
+        let wrapped = format!("{}\n{}", &line[..79], &line[79..]);
+        let text = format!("(./main.tex\n\n)\n{}\n", wrapped);
+        let log = parse_source(SourceText::new(text)).unwrap();
+        let info = log.output_info().unwrap();
+        assert_eq!(info.file, Some(PathBuf::from(format!("{}/main.pdf", dir))));
+        assert_eq!((info.pages, info.bytes), (12, Some(431234)));
+        assert!(log
+            .summary()
+            .to_string()
+            .contains("main.pdf (12 pages, 421.1 KiB)"));
+
+        let log = parse_source(SourceText::new(
+            "(./main.tex)\nNo pages of output.\n".to_string(),
+        ));
+        let info = log.unwrap().output_info().unwrap();
+        assert_eq!((info.file, info.pages), (None, 0));
+    }
+
+    #[test]
+    fn meta() {
+        let log = parse_source(SourceText::from_file("./test/main.log").unwrap()).unwrap();
+        let meta = log.meta();
+        assert_eq!(
+            meta.engine_version.as_deref(),
+            Some("3.141592653-2.6-1.40.22")
+        );
+        assert_eq!(meta.format.as_deref(), Some("LaTeX2e"));
+        assert_eq!(meta.format_date.as_deref(), Some("2020-10-01"));
+
+        let meta = LogMeta::parse(
+            "This is TeX, Version 3.141592653 (TeX Live 2023) (preloaded format=tex 2023.5.1)\n",
+            Engine::PdfTex,
+        );
+        assert_eq!(meta.format.as_deref(), Some("tex"));
+        assert_eq!(meta.format_date.as_deref(), Some("2023.5.1"));
+    }
+
+    #[test]
+    fn context() {
+        let text = "(./main.tex\nsome text\n! Undefined control sequence.\n<recently read> \\foo\nl.3 \\foo\n\nmore text\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        assert_eq!(log.get_diagnostics()[0].context(), None);
+
+        let ds = log.with_context(2).get_diagnostics();
+        let context = ds[0].context().unwrap();
+        assert_eq!(context.before, vec![(1, "(./main.tex".to_string())]);
+        assert_eq!(context.after[1], (7, "more text".to_string()));
+        let rendered = ds[0].render(&RenderOptions {
+            color: false,
+            width: Some(40),
+            ..Default::default()
+        });
+        assert!(rendered.contains("(./main.tex\nsome text\n! Undefined control sequence."));
+    }
+
+    #[test]
+    fn to_json() {
+        let text = "(./main.tex\n! Undefined control sequence.\nl.3 \\foo\n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let json = log.to_json();
+        assert!(json.contains("\"code\":\"E0021\""));
+        assert!(json.contains("\"errors\":1"));
     }
 }
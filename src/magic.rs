@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    filter::KindPattern,
+    log::{Log, PathStyle, TracedTexDiagnostic},
+    parser::{is_system_file, DiagnosticLevel},
+    text,
+};
+
+/// Text after the `%` that starts a magic comment
+const MARKER: &str = "texlog:";
+
+/// What a magic comment hides
+#[derive(Clone, Debug, PartialEq)]
+pub enum Directive {
+    /// `% texlog: ignore-next-warning` hides the warnings of the next line
+    IgnoreWarnings,
+    /// `% texlog: ignore overfull, package:hyperref` hides diagnostics of these kinds
+    Ignore(Vec<KindPattern>),
+}
+
+impl Directive {
+    /// Parse the text of a comment after the `%`
+    fn parse(comment: &str) -> Option<Result<Self, String>> {
+        let rest = comment.trim().strip_prefix(MARKER)?.trim();
+        let directive = match rest.split_once(char::is_whitespace) {
+            _ if rest == "ignore-next-warning" => Ok(Directive::IgnoreWarnings),
+            Some(("ignore", kinds)) => kinds
+                .split(',')
+                .map(|k| k.parse())
+                .collect::<Result<Vec<KindPattern>, String>>()
+                .map(Directive::Ignore),
+            _ => Err(format!("unknown magic comment '{}'", rest)),
+        };
+        Some(directive)
+    }
+
+    pub fn hides(&self, d: &TracedTexDiagnostic) -> bool {
+        match self {
+            Directive::IgnoreWarnings => d.diagnostic().level() <= DiagnosticLevel::Warning,
+            Directive::Ignore(patterns) => {
+                patterns.iter().any(|p| p.matches(d.diagnostic().kind()))
+            }
+        }
+    }
+}
+
+/// The `% texlog:` comments of a tex file. A comment on a line of its own applies to the next
+/// line with code, and a comment after code applies to its own line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MagicComments {
+    /// Directives by the 1-based line they apply to
+    directives: HashMap<usize, Vec<Directive>>,
+
+    /// Magic comments that could not be parsed
+    errors: Vec<String>,
+}
+
+/// The comment of a line, which starts at the first `%` that is not escaped as `\%`
+fn comment(line: &str) -> Option<(&str, &str)> {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '%' if !escaped => return Some((&line[..i], &line[i + 1..])),
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+impl MagicComments {
+    pub fn parse(source: &str) -> Self {
+        let mut comments = Self::default();
+        let mut pending = vec![];
+        for (i, line) in source.lines().enumerate() {
+            let (code, directive) = match comment(line) {
+                Some((code, text)) => (code, Directive::parse(text)),
+                None => (line, None),
+            };
+            match directive {
+                Some(Ok(directive)) => pending.push(directive),
+                Some(Err(e)) => comments.errors.push(format!("line {}: {}", i + 1, e)),
+                None => {}
+            }
+            if !code.trim().is_empty() && !pending.is_empty() {
+                comments
+                    .directives
+                    .insert(i + 1, std::mem::take(&mut pending));
+            }
+        }
+        comments
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::parse(&text::decode(&fs::read(path)?)))
+    }
+
+    /// Whether a comment hides the diagnostic. Only the line the diagnostic points to is
+    /// looked at, so the diagnostic must come from this file.
+    pub fn hides(&self, d: &TracedTexDiagnostic) -> bool {
+        d.diagnostic()
+            .source_line()
+            .and_then(|line| self.directives.get(&line))
+            .is_some_and(|directives| directives.iter().any(|directive| directive.hides(d)))
+    }
+
+    /// Magic comments that could not be parsed, with their line
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+}
+
+/// Reads the magic comments of the files that diagnostics point to, each file once
+pub(crate) struct MagicCommentCache {
+    base_dir: PathBuf,
+    files: HashMap<PathBuf, MagicComments>,
+}
+
+impl MagicCommentCache {
+    pub(crate) fn new(log: &Log) -> Self {
+        Self {
+            base_dir: log.base_dir(),
+            files: HashMap::new(),
+        }
+    }
+
+    /// Whether a magic comment in the file of the diagnostic hides it. Paths must not have been
+    /// resolved yet, and files of the TeX distribution are not read.
+    pub(crate) fn hides(&mut self, d: &TracedTexDiagnostic) -> bool {
+        let Some(file) = d.source_file() else {
+            return false;
+        };
+        if d.diagnostic().source_line().is_none() || is_system_file(file) {
+            return false;
+        }
+        let path = PathStyle::Resolved.resolve(&self.base_dir, file);
+        self.files
+            .entry(path)
+            .or_insert_with_key(|path| MagicComments::from_path(path).unwrap_or_default())
+            .hides(d)
+    }
+
+    /// Magic comments that could not be parsed in the files read so far, as
+    /// `file: line N: message`
+    pub(crate) fn errors(&self) -> Vec<String> {
+        let mut files: Vec<_> = self.files.iter().collect();
+        files.sort_by_key(|(path, _)| *path);
+        files
+            .into_iter()
+            .flat_map(|(path, comments)| {
+                comments
+                    .errors()
+                    .iter()
+                    .map(move |e| format!("{}: {}", path.display(), e))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::parse_source, text::SourceText};
+
+    #[test]
+    fn parse() {
+        let source = "\\documentclass{article}\n% texlog: ignore-next-warning\n\n\\ref{a}\n\\ref{b} % texlog: ignore overfull, package:hyperref\n50\\% % texlog: bogus\n";
+        let comments = MagicComments::parse(source);
+        assert_eq!(comments.directives[&4], vec![Directive::IgnoreWarnings]);
+        assert!(matches!(&comments.directives[&5][0], Directive::Ignore(p) if p.len() == 2));
+        assert_eq!(comments.directives.len(), 2);
+        assert_eq!(
+            comments.errors(),
+            &["line 6: unknown magic comment 'bogus'"]
+        );
+    }
+
+    #[test]
+    fn hides() {
+        let dir = std::env::temp_dir().join(format!("texlog-magic-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("main.tex"),
+            "% texlog: ignore-next-warning\n\\ref{a}\n\\ref{b}\n% texlog: bogus\n",
+        )
+        .unwrap();
+        let text = "(./main.tex\n\nLaTeX Warning: Reference `a' on page 1 undefined on input line 2.\n\n\nLaTeX Warning: Reference `b' on page 1 undefined on input line 3.\n\n)";
+        let mut log = parse_source(SourceText::new(text.to_string())).unwrap();
+        log.path = Some(dir.join("main.log"));
+        let ds = log.get_diagnostics();
+        let errors = log.magic_comment_errors();
+        let unfiltered = log.with_magic_comments(false).get_diagnostics();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(ds.len(), 1);
+        assert_eq!(ds[0].diagnostic().source_line(), Some(3));
+        assert_eq!(unfiltered.len(), 2);
+        assert_eq!(
+            errors,
+            vec![format!(
+                "{}: line 4: unknown magic comment 'bogus'",
+                dir.join("main.tex").display()
+            )]
+        );
+    }
+}
@@ -1,16 +1,742 @@
 #![allow(dead_code)]
 
-use clap::Parser;
-use log::Log;
+use std::{
+    ffi::OsString,
+    fmt::Display,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Instant,
+};
+
+use baseline::Baseline;
+use clap::{Parser, ValueEnum};
+use config::Config;
+use diff::LogDiff;
+use filter::{DiagnosticFilter, IgnoreRule, KindPattern};
+use fls::Recorder;
+use log::{Log, RenderOptions};
+use output::{JsonLinesSink, OutputSink, ShortSink, Summary, SummarySink, TerminalSink};
+use parser::Printer;
+use stats::Stats;
+use synctex::SyncTex;
+use termion::{
+    color::{self, Fg},
+    style,
+};
+use text::SourceText;
+
+use texlog::{
+    baseline, console, diff, error, explain, filter, fls, log, output, parser, stats, synctex,
+    tectonic, text,
+};
 
 mod cli;
-mod lexer;
-mod log;
-mod parser;
-mod text;
+mod config;
+mod follow;
+mod locate;
+mod run;
+mod tui;
+mod watch;
+
+fn build_filter(args: &cli::Args) -> Result<DiagnosticFilter, String> {
+    let parse_patterns = |patterns: &Vec<String>| {
+        patterns
+            .iter()
+            .map(|p| p.parse())
+            .collect::<Result<Vec<KindPattern>, String>>()
+    };
+    let level_overrides = args
+        .severity
+        .iter()
+        .map(|s| {
+            let (pattern, level) = s
+                .rsplit_once('=')
+                .ok_or_else(|| format!("expected KIND=LEVEL, got '{}'", s))?;
+            let level = cli::Level::from_str(level, true)?;
+            Ok((pattern.parse()?, level.into()))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(DiagnosticFilter {
+        min_level: match args.verbose {
+            true => cli::Level::Info.into(),
+            false => args.min_level.clone().into(),
+        },
+        only: parse_patterns(&args.only)?,
+        ignore: parse_patterns(&args.ignore)?,
+        ignored_packages: args.ignore_package.clone(),
+        path_patterns: args.path_filter.clone(),
+        dedup: !args.no_dedup,
+        level_overrides,
+        ignore_rules: ignore_rules(args)?,
+        min_overfull_pt: args.min_overfull_pt,
+        min_badness: args.min_badness,
+    })
+}
+
+/// Read the log from a file, or from standard input if the file is `-` or missing. With
+/// `--console` the input is taken to be the terminal output of the engine.
+fn read_log(args: &cli::Args, file: Option<&str>) -> Result<Log, String> {
+    match file {
+        Some("-") => {}
+        Some(path) if args.console => {
+            let source = SourceText::from_file(path)
+                .map_err(|e| format!("could not read {}: {}", path, e))?;
+            return console::parse_console(source).map_err(|e| e.to_string());
+        }
+        Some(path) => return Log::from_path(path).map_err(|e| e.to_string()),
+        None if io::stdin().is_terminal() => {
+            return match locate::project_log(Path::new(".")) {
+                Some(path) => Log::from_path(path).map_err(|e| e.to_string()),
+                None => Err("no log file given. Use `-` to read from standard input.".to_string()),
+            }
+        }
+        None => {}
+    }
+    let source = SourceText::from_reader(io::stdin())
+        .map_err(|e| format!("could not read standard input: {}", e))?;
+    match args.console || tectonic::is_tectonic_output(source.as_str()) {
+        true => console::parse_console(source),
+        false => parser::parse_source(source),
+    }
+    .map_err(|e| e.to_string())
+}
+
+fn build_sinks(args: &cli::Args) -> Result<Vec<Box<dyn OutputSink>>, String> {
+    let collapse = match args.collapse {
+        _ if args.verbose => None,
+        0 => None,
+        n => Some(n),
+    };
+    let mut sinks: Vec<Box<dyn OutputSink>> = vec![];
+    if !args.quiet {
+        sinks.push(match args.format {
+            cli::Format::Pretty => Box::new(
+                TerminalSink::new(
+                    Box::new(io::stdout()),
+                    RenderOptions {
+                        color: args.color.enabled(),
+                        width: args.width,
+                        links: args.hyperlinks.allowed(),
+                        user_files_only: args.user_files_only,
+                        trace_depth: args.trace_depth,
+                    },
+                )
+                .with_collapse(collapse),
+            ),
+            cli::Format::Short => Box::new(
+                ShortSink::new(Box::new(io::stdout()))
+                    .with_links(args.hyperlinks.allowed())
+                    .with_collapse(collapse),
+            ),
+            cli::Format::Jsonl => Box::new(JsonLinesSink::new(Box::new(io::stdout()))),
+        });
+    }
+    if args.summary {
+        sinks.push(Box::new(SummarySink::new(Box::new(io::stdout()))));
+    }
+    for spec in &args.outputs {
+        sinks.push(output::sink_from_spec(spec)?);
+    }
+    Ok(sinks)
+}
+
+/// Exit code for the reported diagnostics
+fn exit_code(args: &cli::Args, summary: &Summary) -> ExitCode {
+    let failed = match args.fail_on {
+        cli::FailOn::Error => summary.errors > 0,
+        cli::FailOn::Warning => summary.errors > 0 || summary.warnings > 0,
+        cli::FailOn::Never => false,
+    };
+    let too_many_warnings = args.max_warnings.is_some_and(|max| summary.warnings > max);
+    if too_many_warnings && !args.quiet {
+        eprintln!(
+            "Error: {} warnings exceed the maximum of {}",
+            summary.warnings,
+            args.max_warnings.unwrap_or_default()
+        );
+    }
+    if failed || too_many_warnings {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Print a message about the log as a whole to stderr, unless `--quiet` is given
+fn notice(args: &cli::Args, c: &dyn color::Color, label: &str, message: &str) {
+    if args.quiet {
+        return;
+    }
+    match args.color.enabled() && io::stderr().is_terminal() {
+        true => eprintln!("{}{}: {}{}", Fg(c), label, message, Fg(color::Reset)),
+        false => eprintln!("{}: {}", label, message),
+    }
+}
+
+/// Print how long reading the log took. Diagnostics are collected once more to time it.
+fn print_timing(log: &Log, name: Option<&str>) {
+    let start = Instant::now();
+    let diagnostics = log.get_diagnostics().len();
+    let collecting = start.elapsed();
+    if let Some(name) = name {
+        eprintln!("{}:", name);
+    }
+    eprintln!("{}", log.timing());
+    eprintln!(
+        "diagnostics {:>6.2} ms  {} diagnostics",
+        collecting.as_secs_f64() * 1000.0,
+        diagnostics
+    );
+}
+
+/// Every `.log` file in a directory and its subdirectories, in order
+fn find_logs(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("could not read {}: {}", dir.display(), e))?;
+    let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    paths.sort();
+    let mut logs = vec![];
+    for path in paths {
+        let hidden = path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        if path.is_dir() && !hidden {
+            logs.extend(find_logs(&path)?);
+        } else if path.extension().is_some_and(|e| e == "log") {
+            logs.push(path);
+        }
+    }
+    Ok(logs)
+}
+
+/// Parse the logs and report their diagnostics. Directories are searched for logs, and standard
+/// input is read if no files are given.
+fn check(args: &cli::Args, files: &[String]) -> ExitCode {
+    let mut paths = vec![];
+    for file in files {
+        let path = Path::new(file);
+        match path.is_dir() {
+            true => match find_logs(path) {
+                Ok(logs) => paths.extend(logs.iter().map(|p| p.display().to_string())),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            },
+            false => paths.push(file.clone()),
+        }
+    }
+    if paths.is_empty() && !files.is_empty() {
+        eprintln!("Error: no log files found in {}", files.join(", "));
+        return ExitCode::FAILURE;
+    }
+
+    let logs = match paths.is_empty() {
+        true => read_log(args, None).map(|log| vec![("-".to_string(), log)]),
+        false => paths
+            .iter()
+            .map(|path| read_log(args, Some(path)).map(|log| (path.clone(), log)))
+            .collect::<Result<Vec<_>, String>>()
+            .and_then(|logs| with_companion_logs(args, logs, &paths)),
+    };
+    match logs {
+        Ok(logs) => report(args, logs),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Add the Biber and index logs found next to every log after it, so that their problems are
+/// reported alongside those of TeX. Logs in `given` are not added again.
+fn with_companion_logs(
+    args: &cli::Args,
+    logs: Vec<(String, Log)>,
+    given: &[String],
+) -> Result<Vec<(String, Log)>, String> {
+    if args.console {
+        return Ok(logs);
+    }
+    let mut all = vec![];
+    for (name, log) in logs {
+        let companions: Vec<String> = [log.blg_path(), log.ilg_path()]
+            .into_iter()
+            .flatten()
+            .map(|path| path.display().to_string())
+            .filter(|path| !given.contains(path))
+            .collect();
+        all.push((name, log));
+        for path in companions {
+            let log = read_log(args, Some(&path))?;
+            all.push((path, log));
+        }
+    }
+    Ok(all)
+}
+
+/// Apply the options of the command line to a parsed log. Files found next to the log that
+/// cannot be read are skipped with a warning, while files given on the command line must be
+/// readable.
+fn prepare(args: &cli::Args, log: Log, filter: DiagnosticFilter) -> Result<Log, String> {
+    let skip = |e: error::Error| {
+        notice(
+            args,
+            &color::Yellow,
+            "Warning",
+            &format!("{}, skipping it", e),
+        );
+    };
+    let mut log = match &args.fls {
+        Some(path) => {
+            log.with_recorder(&Recorder::from_path(Path::new(path)).map_err(|e| e.to_string())?)
+        }
+        None => match log.recorder_path().map(|path| Recorder::from_path(&path)) {
+            Some(Ok(recorder)) => log.with_recorder(&recorder),
+            Some(Err(e)) => {
+                skip(e);
+                log
+            }
+            None => log,
+        },
+    };
+    match &args.synctex {
+        Some(path) => {
+            log = log.with_synctex(SyncTex::from_path(Path::new(path)).map_err(|e| e.to_string())?);
+        }
+        None => match log.synctex_path().map(|path| SyncTex::from_path(&path)) {
+            Some(Ok(synctex)) => log = log.with_synctex(synctex),
+            Some(Err(e)) => skip(e),
+            None => {}
+        },
+    }
+    log = log
+        .with_filter(filter)
+        .with_paths(args.paths.into())
+        .with_raw_escapes(args.raw_escapes)
+        .with_all_runs(args.all_runs)
+        .with_magic_comments(!args.no_magic_comments)
+        .with_context(args.context);
+    if let Some(sort) = args.sort.clone() {
+        log = log.with_sort(sort.into());
+    }
+    if let (Some(path), false) = (&args.baseline, args.write_baseline) {
+        log = log.with_baseline(Baseline::from_path(Path::new(path)).map_err(|e| e.to_string())?);
+    }
+    Ok(log)
+}
+
+/// Read a log and apply the options of the command line to it
+fn load_log(args: &cli::Args, file: Option<&str>) -> Result<Log, String> {
+    prepare(args, read_log(args, file)?, build_filter(args)?)
+}
+
+/// Report the diagnostics of parsed logs, named by their path. With several logs the
+/// diagnostics of each are headed by its path, and the summary covers all of them.
+fn report(args: &cli::Args, logs: Vec<(String, Log)>) -> ExitCode {
+    let filter = match build_filter(args) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let several = logs.len() > 1;
+    if several && args.write_baseline {
+        eprintln!("Error: --write-baseline takes a single log");
+        return ExitCode::FAILURE;
+    }
+    let mut sinks = match build_sinks(args) {
+        Ok(sinks) => sinks,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut total = Summary::default();
+    for (name, log) in logs {
+        let log = match prepare(args, log, filter.clone()) {
+            Ok(log) => log,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return ExitCode::FAILURE;
+            }
+        };
+        if let (Some(path), true) = (args.baseline.as_ref().map(Path::new), args.write_baseline) {
+            let baseline = log.to_baseline();
+            if let Err(e) = baseline.write(path) {
+                eprintln!("Error: {}", e);
+                return ExitCode::FAILURE;
+            }
+            notice(
+                args,
+                &color::Cyan,
+                "Baseline",
+                &format!(
+                    "recorded {} diagnostics in {}",
+                    baseline.len(),
+                    path.display()
+                ),
+            );
+            return ExitCode::SUCCESS;
+        }
 
-fn main() {
+        // Messages about a log name it when there are several
+        let about = |message: &str| match several {
+            true => format!("{}: {}", name, message),
+            false => message.to_string(),
+        };
+        if several && !args.quiet && args.format == cli::Format::Pretty {
+            let heading = match args.color.enabled() {
+                true => format!("{}==> {} <=={}", style::Bold, name, style::Reset),
+                false => format!("==> {} <==", name),
+            };
+            if let Err(e) = print_lines([heading]) {
+                eprintln!("Error: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+        if let Some(warning) = log.staleness_warning() {
+            notice(args, &color::Yellow, "Warning", &about(&warning));
+        }
+        if let Some(first) = log.parse_warnings().first() {
+            let others = match log.parse_warnings().len() - 1 {
+                0 => "".to_string(),
+                n => format!(" (and {} more)", n),
+            };
+            notice(
+                args,
+                &color::Yellow,
+                "Warning",
+                &about(&format!(
+                    "{}{} — diagnostics may be attributed to the wrong file",
+                    first, others
+                )),
+            );
+        }
+        let magic_comment_errors = log.magic_comment_errors();
+        if let Some(first) = magic_comment_errors.first() {
+            let others = match magic_comment_errors.len() - 1 {
+                0 => "".to_string(),
+                n => format!(" (and {} more)", n),
+            };
+            notice(
+                args,
+                &color::Yellow,
+                "Warning",
+                &about(&format!("magic comment ignored in {}{}", first, others)),
+            );
+        }
+
+        if args.timing {
+            print_timing(&log, several.then_some(name.as_str()));
+        }
+        if args.annotate {
+            if let Err(e) = print_out(log.annotate(args.color.enabled())) {
+                eprintln!("Error: {}", e);
+                return ExitCode::FAILURE;
+            }
+            continue;
+        }
+        match log.emit_diagnostics(&mut sinks) {
+            Ok(summary) => total += summary,
+            Err(e) => {
+                eprintln!("Error: could not write output: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+        if !log.compilation_succeeded() {
+            notice(
+                args,
+                &color::Red,
+                "Compilation failed",
+                &about("TeX stopped with a fatal error, no PDF was produced"),
+            );
+        }
+        if log.needs_rerun() {
+            notice(
+                args,
+                &color::Cyan,
+                "Rerun",
+                &about("the log asks for another run to get cross-references right"),
+            );
+        }
+    }
+
+    if several {
+        total.output = None;
+        total.meta = None;
+    }
+    for sink in sinks.iter_mut() {
+        if let Err(e) = sink.summary(&total) {
+            eprintln!("Error: could not write output: {}", e);
+            return ExitCode::FAILURE;
+        }
+    }
+    exit_code(args, &total)
+}
+
+/// Print the diagnostics that differ between two logs
+fn diff(args: &cli::Args, old: &str, new: &str) -> ExitCode {
+    let filter = match build_filter(args) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let load = |file: &str| {
+        read_log(args, Some(file)).map(|log| {
+            log.with_filter(filter.clone())
+                .with_paths(args.paths.into())
+                .with_raw_escapes(args.raw_escapes)
+        })
+    };
+    let (old, new) = match (load(old), load(new)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let diff = LogDiff::new(&old, &new);
+    let color = args.color.enabled();
+    let mut lines = vec![];
+    for (sign, c, diagnostics) in [
+        ("-", &color::Green as &dyn color::Color, diff.fixed()),
+        ("+", &color::Red, diff.introduced()),
+    ] {
+        for d in diagnostics {
+            lines.push(match color {
+                true => format!(
+                    "{}{} {}{}",
+                    Fg(c),
+                    sign,
+                    d.to_short_string(),
+                    Fg(color::Reset)
+                ),
+                false => format!("{} {}", sign, d.to_short_string()),
+            });
+        }
+    }
+    lines.push(format!(
+        "{} introduced, {} fixed",
+        diff.introduced().len(),
+        diff.fixed().len()
+    ));
+    if let Err(e) = print_lines(lines) {
+        eprintln!("Error: {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    match diff.introduced().is_empty() {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+/// Print the explanation of a diagnostic kind, or list the kinds
+fn explain(kind: Option<&str>) -> Result<(), String> {
+    let Some(kind) = kind else {
+        return print_lines(
+            explain::EXPLANATIONS
+                .iter()
+                .map(|e| format!("{}  {}", e.code, e.name)),
+        );
+    };
+    match explain::explain(kind) {
+        Some(explanation) => print_lines([explanation]),
+        None => Err(format!(
+            "unknown diagnostic kind '{}', see `texlog explain` for the list",
+            kind
+        )),
+    }
+}
+
+/// Print the files that were open at a `LINE[:COLUMN]` position of the log
+fn trace(args: &cli::Args, file: &str, position: &str) -> Result<(), String> {
+    let (line, column) = position.split_once(':').unwrap_or((position, "1"));
+    let parse = |n: &str| {
+        n.parse::<usize>()
+            .map_err(|_| format!("expected LINE[:COLUMN], got '{}'", position))
+    };
+    let (line, column) = (parse(line)?, parse(column)?);
+    let log = read_log(args, Some(file))?;
+    let index = log.source().index(line, column);
+    print_lines(
+        log.trace_at(index)
+            .iter()
+            .enumerate()
+            .map(|(i, file)| format!("{}{}", "  ".repeat(i), file.display())),
+    )
+}
+
+/// Print the lines of the log that match a pattern, with the match highlighted
+fn grep(args: &cli::Args, pattern: &str, file: Option<&str>) -> Result<(), String> {
+    let log = read_log(args, file)?.with_paths(args.paths.into());
+    let color = args.color.enabled();
+    print_lines(log.grep(pattern)?.iter().map(|m| {
+        if !color {
+            return m.to_string();
+        }
+        let (start, end) = m.range;
+        let level = m
+            .level
+            .as_ref()
+            .map(|l| format!("{}: ", l))
+            .unwrap_or_default();
+        format!(
+            "{}{}:{} {}{}{}{}{}{}",
+            Fg(color::Blue),
+            m.trace(),
+            Fg(color::Reset),
+            level,
+            &m.line[..start],
+            Fg(color::Red),
+            &m.line[start..end],
+            Fg(color::Reset),
+            &m.line[end..]
+        )
+    }))
+}
+
+/// Directory that config and ignore files are searched from
+fn project_dir(args: &cli::Args) -> PathBuf {
+    match args.log_file().map(Path::new).and_then(|f| f.parent()) {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Ignore rules from the command line and the project's ignore file
+fn ignore_rules(args: &cli::Args) -> Result<Vec<IgnoreRule>, String> {
+    let mut rules = args
+        .ignore_rule
+        .iter()
+        .map(|r| r.parse())
+        .collect::<Result<Vec<IgnoreRule>, String>>()?;
+    if !args.no_config {
+        if let Some(path) = Config::discover(&project_dir(args), config::IGNORE_FILE_NAME) {
+            let source = std::fs::read_to_string(&path)
+                .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+            rules.extend(
+                IgnoreRule::parse_file(&source)
+                    .map_err(|e| format!("{}: {}", path.display(), e))?,
+            );
+        }
+    }
+    Ok(rules)
+}
+
+/// Parse the command line, with the arguments from the config file placed before the user's own
+/// so that they can be overridden
+fn parse_args() -> Result<cli::Args, String> {
     let args = cli::Args::parse();
-    let log = Log::from_path(args.file.as_str());
-    log.print_diagnostics()
+    if args.no_config {
+        return Ok(args);
+    }
+
+    let config_path = match &args.config {
+        Some(path) => Some(PathBuf::from(path)),
+        None => Config::discover(&project_dir(&args), config::CONFIG_FILE_NAME),
+    };
+    let Some(config_path) = config_path else {
+        return Ok(args);
+    };
+
+    let config_args = Config::load(&config_path)?.to_args()?;
+    let mut argv: Vec<OsString> = std::env::args_os().collect();
+    argv.splice(1..1, config_args.into_iter().map(OsString::from));
+    cli::Args::try_parse_from(argv).map_err(|e| format!("in {}: {}", config_path.display(), e))
+}
+
+/// Turn the result of writing to stdout into a message. A reader that went away, like `head`,
+/// is not an error: printing just stops.
+fn written(result: io::Result<()>) -> Result<(), String> {
+    match result {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+            Err(format!("could not write output: {}", e))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Write to stdout without panicking when the reader went away, see `written`
+fn print_out(text: impl Display) -> Result<(), String> {
+    written(write!(io::stdout().lock(), "{}", text))
+}
+
+/// Write one line per item to stdout, see `print_out`
+fn print_lines<T: Display>(items: impl IntoIterator<Item = T>) -> Result<(), String> {
+    let mut out = io::stdout().lock();
+    written(
+        items
+            .into_iter()
+            .try_for_each(|item| writeln!(out, "{}", item)),
+    )
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args.with_located_logs(),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let result = match &args.command {
+        Some(cli::Command::Tui { file }) => read_log(&args, file.as_deref())
+            .and_then(|log| tui::run(&log).map_err(|e| format!("terminal error: {}", e))),
+        Some(cli::Command::Tree { file, depth, dot }) => {
+            load_log(&args, file.as_deref()).and_then(|log| {
+                let tree = Printer::new()
+                    .with_diagnostics(&log.get_diagnostics())
+                    .with_max_depth(*depth)
+                    .with_user_files_only(args.user_files_only)
+                    .with_dot(*dot)
+                    .print(log.root_node());
+                print_out(tree)
+            })
+        }
+        Some(cli::Command::Stats { file }) => {
+            load_log(&args, file.as_deref()).and_then(|log| print_out(Stats::new(&log)))
+        }
+        Some(cli::Command::Packages { file }) => {
+            load_log(&args, file.as_deref()).and_then(|log| print_lines(log.packages()))
+        }
+        Some(cli::Command::Fonts { file }) => {
+            load_log(&args, file.as_deref()).and_then(|log| print_lines(log.fonts()))
+        }
+        Some(cli::Command::Explain { kind }) => explain(kind.as_deref()),
+        Some(cli::Command::Trace { file, position }) => trace(&args, file, position),
+        Some(cli::Command::Grep { pattern, file }) => grep(&args, pattern, file.as_deref()),
+        Some(cli::Command::Clean { file }) => {
+            read_log(&args, file.as_deref()).and_then(|log| print_out(log.clean()))
+        }
+        Some(cli::Command::Diff { old, new }) => return diff(&args, old, new),
+        Some(cli::Command::Run { command }) => return run::run(&args, command),
+        Some(cli::Command::Watch { .. }) => return watch::watch(&args),
+        Some(cli::Command::Check { .. }) | None if args.follow => {
+            return match build_sinks(&args) {
+                Ok(mut sinks) => follow::follow(&args, &mut sinks),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Some(cli::Command::Check { files }) => return check(&args, files),
+        None => return check(&args, &args.files),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
 }
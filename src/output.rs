@@ -0,0 +1,721 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::File,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+};
+
+use crate::{
+    json::Json,
+    log::{LogMeta, OutputInfo, RenderOptions, TracedTexDiagnostic},
+    parser::{DiagnosticLevel, TexDiagnosticKind},
+};
+
+/// Counts reported to sinks after all diagnostics have been emitted
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Summary {
+    pub warnings: usize,
+    pub errors: usize,
+
+    /// What TeX wrote, if the summary is of a single log
+    pub output: Option<OutputInfo>,
+
+    /// The engine and format that wrote the log, if the summary is of a single log
+    pub meta: Option<LogMeta>,
+}
+
+impl Summary {
+    /// Count diagnostics, including merged duplicates
+    pub fn from_diagnostics(diagnostics: &[TracedTexDiagnostic]) -> Self {
+        let count = |level: DiagnosticLevel| {
+            diagnostics
+                .iter()
+                .filter(|d| d.diagnostic().level() == level)
+                .map(|d| d.count())
+                .sum()
+        };
+        Self {
+            warnings: count(DiagnosticLevel::Warning),
+            errors: count(DiagnosticLevel::Error),
+            output: None,
+            meta: None,
+        }
+    }
+
+    pub fn with_output(mut self, output: Option<OutputInfo>) -> Self {
+        self.output = output;
+        self
+    }
+
+    pub fn with_meta(mut self, meta: LogMeta) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+}
+
+impl std::ops::AddAssign for Summary {
+    fn add_assign(&mut self, other: Self) {
+        self.warnings += other.warnings;
+        self.errors += other.errors;
+        self.output = other.output.or(self.output.take());
+        self.meta = other.meta.or(self.meta.take());
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        write!(
+            f,
+            "{} error{}, {} warning{}",
+            self.errors,
+            plural(self.errors),
+            self.warnings,
+            plural(self.warnings)
+        )?;
+        match &self.output {
+            Some(output) if output.file.is_some() => write!(f, "; wrote {}", output),
+            Some(output) => write!(f, "; {}", output),
+            None => Ok(()),
+        }?;
+        match &self.meta {
+            Some(meta) => write!(f, "; {}", meta),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A destination for diagnostics, like the terminal or a report file
+pub trait OutputSink {
+    /// Called once for every diagnostic
+    fn diagnostic(&mut self, diagnostic: &TracedTexDiagnostic) -> io::Result<()>;
+
+    /// Called once after the last diagnostic
+    fn summary(&mut self, _summary: &Summary) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Open the writer for a path, where `-` means standard output
+fn open_writer(path: &str) -> io::Result<Box<dyn Write>> {
+    if path == "-" {
+        Ok(Box::new(io::stdout()))
+    } else {
+        Ok(Box::new(File::create(path)?))
+    }
+}
+
+/// Formats accepted by `sink_from_spec`
+const FORMATS: [&str; 7] = ["pretty", "short", "json", "jsonl", "sarif", "lsp", "html"];
+
+/// Create a sink from a `<format>:<path>` specification, like `json:report.json`
+pub fn sink_from_spec(spec: &str) -> Result<Box<dyn OutputSink>, String> {
+    let (format, path) = spec.split_once(':').unwrap_or((spec, "-"));
+    // Check the format before the file is created, so that a typo does not truncate it
+    if !FORMATS.contains(&format) {
+        return Err(format!("unknown output format '{}'", format));
+    }
+    let writer = open_writer(path).map_err(|e| format!("could not open '{}': {}", path, e))?;
+    let sink: Box<dyn OutputSink> = match format {
+        "pretty" => Box::new(TerminalSink::new(
+            writer,
+            RenderOptions {
+                color: path == "-" && io::stdout().is_terminal(),
+                ..Default::default()
+            },
+        )),
+        "short" => Box::new(ShortSink::new(writer)),
+        "json" => Box::new(JsonSink::new(writer)),
+        "jsonl" => Box::new(JsonLinesSink::new(writer)),
+        "sarif" => Box::new(SarifSink::new(writer)),
+        "lsp" => Box::new(LspSink::new(writer)),
+        "html" => Box::new(HtmlSink::new(writer)),
+        _ => unreachable!(),
+    };
+    Ok(sink)
+}
+
+/// Hides warnings once more than a limit of them share a kind and a file, and counts the ones
+/// that were hidden
+#[derive(Default)]
+struct Collapser {
+    limit: Option<usize>,
+
+    /// Shown and hidden warnings of every kind and file
+    groups: HashMap<(&'static str, Option<PathBuf>), (usize, usize)>,
+
+    /// Kinds and files with hidden warnings, in the order they were first hidden
+    collapsed: Vec<(&'static str, Option<PathBuf>)>,
+}
+
+impl Collapser {
+    fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit,
+            ..Default::default()
+        }
+    }
+
+    /// Whether to show the diagnostic, counting it if not. Duplicates merged into it are
+    /// counted too, so that the numbers add up to the summary.
+    fn show(&mut self, d: &TracedTexDiagnostic) -> bool {
+        let Some(limit) = self.limit else {
+            return true;
+        };
+        if d.diagnostic().level() != DiagnosticLevel::Warning {
+            return true;
+        }
+        let key = (d.diagnostic().kind().name(), d.file().cloned());
+        let (shown, hidden) = self.groups.entry(key.clone()).or_default();
+        if *shown < limit {
+            *shown += 1;
+            return true;
+        }
+        if *hidden == 0 {
+            self.collapsed.push(key);
+        }
+        *hidden += d.count();
+        false
+    }
+
+    /// Lines like `… 23 more font warnings in preamble.tex (use --verbose to expand)`
+    fn hidden(&self) -> Vec<String> {
+        self.collapsed
+            .iter()
+            .map(|key| {
+                let hidden = self.groups[key].1;
+                let plural = if hidden == 1 { "" } else { "s" };
+                let file = match &key.1 {
+                    Some(file) => format!(" in {}", file.display()),
+                    None => "".to_string(),
+                };
+                format!(
+                    "… {} more {} warning{}{} (use --verbose to expand)",
+                    hidden, key.0, plural, file
+                )
+            })
+            .collect()
+    }
+}
+
+/// Colored diagnostic blocks with message and call trace
+pub struct TerminalSink {
+    writer: Box<dyn Write>,
+    options: RenderOptions,
+    collapser: Collapser,
+}
+
+impl TerminalSink {
+    pub fn new(writer: Box<dyn Write>, options: RenderOptions) -> Self {
+        Self {
+            writer,
+            options,
+            collapser: Collapser::default(),
+        }
+    }
+
+    /// Only print the first `limit` warnings of a kind in a file, and count the others at the end
+    pub fn with_collapse(mut self, limit: Option<usize>) -> Self {
+        self.collapser = Collapser::new(limit);
+        self
+    }
+}
+
+impl OutputSink for TerminalSink {
+    fn diagnostic(&mut self, diagnostic: &TracedTexDiagnostic) -> io::Result<()> {
+        if !self.collapser.show(diagnostic) {
+            return Ok(());
+        }
+        writeln!(self.writer, "\n{}", diagnostic.render(&self.options))
+    }
+
+    fn summary(&mut self, _summary: &Summary) -> io::Result<()> {
+        let hidden = self.collapser.hidden();
+        if !hidden.is_empty() {
+            writeln!(self.writer)?;
+        }
+        for line in hidden {
+            writeln!(self.writer, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// One `file:line:col: level: title` line per diagnostic
+pub struct ShortSink {
+    writer: Box<dyn Write>,
+
+    /// Make file names clickable with OSC 8 terminal hyperlinks
+    links: bool,
+
+    collapser: Collapser,
+}
+
+impl ShortSink {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        Self {
+            writer,
+            links: false,
+            collapser: Collapser::default(),
+        }
+    }
+
+    pub fn with_links(mut self, links: bool) -> Self {
+        self.links = links;
+        self
+    }
+
+    /// Only print the first `limit` warnings of a kind in a file, and count the others at the end
+    pub fn with_collapse(mut self, limit: Option<usize>) -> Self {
+        self.collapser = Collapser::new(limit);
+        self
+    }
+}
+
+impl OutputSink for ShortSink {
+    fn diagnostic(&mut self, diagnostic: &TracedTexDiagnostic) -> io::Result<()> {
+        if !self.collapser.show(diagnostic) {
+            return Ok(());
+        }
+        writeln!(self.writer, "{}", diagnostic.short_string(self.links))
+    }
+
+    fn summary(&mut self, _summary: &Summary) -> io::Result<()> {
+        for line in self.collapser.hidden() {
+            writeln!(self.writer, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Only a single line with the number of errors and warnings
+pub struct SummarySink {
+    writer: Box<dyn Write>,
+}
+
+impl SummarySink {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        Self { writer }
+    }
+}
+
+impl OutputSink for SummarySink {
+    fn diagnostic(&mut self, _diagnostic: &TracedTexDiagnostic) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn summary(&mut self, summary: &Summary) -> io::Result<()> {
+        writeln!(self.writer, "{}", summary)
+    }
+}
+
+/// The fields of a diagnostic's kind, like the size of a box, or `null` for kinds without any
+fn kind_details(kind: &TexDiagnosticKind) -> Json {
+    let lines = |lines: &Option<(u32, u32)>| -> Json {
+        lines
+            .map(|(first, last)| Json::Array(vec![first.into(), last.into()]))
+            .into()
+    };
+    let points = |pt: f32| Json::Number((pt as f64 * 1000.0).round() / 1000.0);
+    match kind {
+        TexDiagnosticKind::OverfullHbox {
+            overflow_pt,
+            lines: l,
+        }
+        | TexDiagnosticKind::OverfullVbox {
+            overflow_pt,
+            lines: l,
+        } => Json::object([("overflow_pt", points(*overflow_pt)), ("lines", lines(l))]),
+        TexDiagnosticKind::UnderfullHbox { badness, lines: l }
+        | TexDiagnosticKind::UnderfullVbox { badness, lines: l } => {
+            Json::object([("badness", (*badness).into()), ("lines", lines(l))])
+        }
+        TexDiagnosticKind::Font {
+            requested,
+            substituted,
+        } => Json::object([
+            ("requested", Json::string(requested)),
+            ("substituted", Json::string(substituted)),
+        ]),
+        kind => kind
+            .argument()
+            .map(|argument| Json::object([("argument", Json::string(argument))]))
+            .into(),
+    }
+}
+
+/// The JSON form of a diagnostic, as written by the `json` output format
+impl From<&TracedTexDiagnostic> for Json {
+    fn from(d: &TracedTexDiagnostic) -> Self {
+        let location = d.diagnostic().location();
+        Json::object([
+            ("level", Json::string(d.diagnostic().level())),
+            ("kind", Json::string(&d.diagnostic().kind)),
+            ("code", Json::string(d.diagnostic().kind.code())),
+            ("details", kind_details(&d.diagnostic().kind)),
+            ("message", Json::string(&d.diagnostic().message)),
+            ("hint", d.diagnostic().kind.hint().map(Json::string).into()),
+            (
+                "suggestion",
+                d.diagnostic().suggestion().map(Json::string).into(),
+            ),
+            ("count", d.count().into()),
+            (
+                "file",
+                d.source_file().map(|f| Json::string(f.display())).into(),
+            ),
+            ("line", d.diagnostic().source_line().into()),
+            ("page", d.diagnostic().page().into()),
+            (
+                "pdf",
+                d.pdf_position()
+                    .map(|p| {
+                        Json::object([
+                            ("page", p.page.into()),
+                            ("x", Json::Number((p.x as f64 * 10.0).round() / 10.0)),
+                            ("y", Json::Number((p.y as f64 * 10.0).round() / 10.0)),
+                        ])
+                    })
+                    .into(),
+            ),
+            (
+                "span",
+                Json::object([
+                    ("start", d.diagnostic().span().start.into()),
+                    ("end", d.diagnostic().span().end.into()),
+                ]),
+            ),
+            (
+                "columns",
+                location
+                    .and_then(|l| l.columns)
+                    .map(|(start, end)| Json::Array(vec![start.into(), end.into()]))
+                    .into(),
+            ),
+            (
+                "trace",
+                Json::Array(
+                    d.call_stack()
+                        .iter()
+                        .map(|f| Json::string(f.display()))
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+}
+
+/// The document of the `json` format
+pub(crate) fn json_report(diagnostics: Vec<Json>, summary: &Summary) -> Json {
+    Json::object([
+        ("diagnostics", Json::Array(diagnostics)),
+        (
+            "summary",
+            Json::object([
+                ("warnings", summary.warnings.into()),
+                ("errors", summary.errors.into()),
+            ]),
+        ),
+    ])
+}
+
+/// A single JSON document with all diagnostics and the summary
+pub struct JsonSink {
+    writer: Box<dyn Write>,
+    diagnostics: Vec<Json>,
+}
+
+impl JsonSink {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        Self {
+            writer,
+            diagnostics: vec![],
+        }
+    }
+}
+
+impl OutputSink for JsonSink {
+    fn diagnostic(&mut self, diagnostic: &TracedTexDiagnostic) -> io::Result<()> {
+        self.diagnostics.push(diagnostic.into());
+        Ok(())
+    }
+
+    fn summary(&mut self, summary: &Summary) -> io::Result<()> {
+        let diagnostics = std::mem::take(&mut self.diagnostics);
+        writeln!(self.writer, "{}", json_report(diagnostics, summary))
+    }
+}
+
+/// One JSON object per line for every diagnostic, written as soon as it is reported, and a
+/// final line with the summary
+pub struct JsonLinesSink {
+    writer: Box<dyn Write>,
+}
+
+impl JsonLinesSink {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        Self { writer }
+    }
+}
+
+impl OutputSink for JsonLinesSink {
+    fn diagnostic(&mut self, diagnostic: &TracedTexDiagnostic) -> io::Result<()> {
+        writeln!(self.writer, "{}", Json::from(diagnostic))?;
+        self.writer.flush()
+    }
+
+    fn summary(&mut self, summary: &Summary) -> io::Result<()> {
+        let json = Json::object([(
+            "summary",
+            Json::object([
+                ("warnings", summary.warnings.into()),
+                ("errors", summary.errors.into()),
+            ]),
+        )]);
+        writeln!(self.writer, "{}", json)?;
+        self.writer.flush()
+    }
+}
+
+/// A SARIF 2.1.0 report, as consumed by code scanning tools
+pub struct SarifSink {
+    writer: Box<dyn Write>,
+    results: Vec<Json>,
+}
+
+impl SarifSink {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        Self {
+            writer,
+            results: vec![],
+        }
+    }
+}
+
+impl OutputSink for SarifSink {
+    fn diagnostic(&mut self, d: &TracedTexDiagnostic) -> io::Result<()> {
+        let level = match d.diagnostic().level() {
+            DiagnosticLevel::Info => "note",
+            DiagnosticLevel::Warning => "warning",
+            DiagnosticLevel::Error => "error",
+        };
+
+        let mut region = vec![];
+        if let Some(line) = d.diagnostic().source_line() {
+            region.push(("startLine".to_string(), line.into()));
+            if let Some((start, end)) = d.diagnostic().location().and_then(|l| l.columns) {
+                region.push(("startColumn".to_string(), start.into()));
+                region.push(("endColumn".to_string(), end.into()));
+            }
+        }
+
+        let mut physical_location = vec![(
+            "artifactLocation".to_string(),
+            Json::object([(
+                "uri",
+                d.source_file().map(|f| Json::string(f.display())).into(),
+            )]),
+        )];
+        if !region.is_empty() {
+            physical_location.push(("region".to_string(), Json::Object(region)));
+        }
+
+        self.results.push(Json::object([
+            ("ruleId", Json::string(&d.diagnostic().kind)),
+            ("level", Json::string(level)),
+            (
+                "message",
+                Json::object([("text", Json::string(&d.diagnostic().message))]),
+            ),
+            (
+                "locations",
+                Json::Array(vec![Json::object([(
+                    "physicalLocation",
+                    Json::Object(physical_location),
+                )])]),
+            ),
+        ]));
+        Ok(())
+    }
+
+    fn summary(&mut self, _summary: &Summary) -> io::Result<()> {
+        let json = Json::object([
+            ("version", Json::string("2.1.0")),
+            (
+                "$schema",
+                Json::string("https://json.schemastore.org/sarif-2.1.0.json"),
+            ),
+            (
+                "runs",
+                Json::Array(vec![Json::object([
+                    (
+                        "tool",
+                        Json::object([(
+                            "driver",
+                            Json::object([
+                                ("name", Json::string(env!("CARGO_PKG_NAME"))),
+                                ("version", Json::string(env!("CARGO_PKG_VERSION"))),
+                            ]),
+                        )]),
+                    ),
+                    ("results", Json::Array(std::mem::take(&mut self.results))),
+                ])]),
+            ),
+        ]);
+        writeln!(self.writer, "{}", json)
+    }
+}
+
+/// A `file://` URI for a path, with the bytes that may not appear in a URI percent-encoded
+fn file_uri(path: &std::path::Path) -> String {
+    let path = std::fs::canonicalize(path)
+        .or_else(|_| std::env::current_dir().map(|dir| dir.join(path)))
+        .unwrap_or_else(|_| path.to_path_buf());
+    let mut uri = "file://".to_string();
+    for b in path.to_string_lossy().bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'.' | b'_' | b'~' => {
+                uri.push(b as char)
+            }
+            b => uri += &format!("%{:02X}", b),
+        }
+    }
+    uri
+}
+
+/// The parameters of the `textDocument/publishDiagnostics` notifications of the Language Server
+/// Protocol for every file, as a JSON array, for editor plugins to pass on to the editor.
+/// Diagnostics underline the columns of the offending token if they are known, and their whole
+/// line otherwise. Diagnostics that do not point to a file are left out.
+pub struct LspSink {
+    writer: Box<dyn Write>,
+
+    /// Diagnostics of every file, in the order the files were first reported
+    files: Vec<(PathBuf, Vec<Json>)>,
+}
+
+impl LspSink {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        Self {
+            writer,
+            files: vec![],
+        }
+    }
+}
+
+impl OutputSink for LspSink {
+    fn diagnostic(&mut self, d: &TracedTexDiagnostic) -> io::Result<()> {
+        let Some(file) = d.source_file() else {
+            return Ok(());
+        };
+        let severity: usize = match d.diagnostic().level() {
+            DiagnosticLevel::Error => 1,
+            DiagnosticLevel::Warning => 2,
+            DiagnosticLevel::Info => 3,
+        };
+        // LSP positions count from 0, and a range that ends at the start of the next line covers
+        // a whole line
+        let line = d.diagnostic().source_line().unwrap_or(1).saturating_sub(1);
+        let position = |line: usize, character: usize| {
+            Json::object([("line", line.into()), ("character", character.into())])
+        };
+        let (start, end) = match d.diagnostic().location().and_then(|l| l.columns) {
+            Some((start, end)) => (position(line, start - 1), position(line, end - 1)),
+            None => (position(line, 0), position(line + 1, 0)),
+        };
+        let json = Json::object([
+            ("range", Json::object([("start", start), ("end", end)])),
+            ("severity", severity.into()),
+            ("code", Json::string(d.diagnostic().kind.code())),
+            ("source", Json::string(env!("CARGO_PKG_NAME"))),
+            ("message", Json::string(&d.diagnostic().message)),
+        ]);
+        match self.files.iter_mut().find(|(f, _)| f == file) {
+            Some((_, diagnostics)) => diagnostics.push(json),
+            None => self.files.push((file.to_path_buf(), vec![json])),
+        }
+        Ok(())
+    }
+
+    fn summary(&mut self, _summary: &Summary) -> io::Result<()> {
+        let files = std::mem::take(&mut self.files)
+            .into_iter()
+            .map(|(file, diagnostics)| {
+                Json::object([
+                    ("uri", Json::string(file_uri(&file))),
+                    ("diagnostics", Json::Array(diagnostics)),
+                ])
+            })
+            .collect();
+        writeln!(self.writer, "{}", Json::Array(files))
+    }
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A standalone HTML page with a table of diagnostics
+pub struct HtmlSink {
+    writer: Box<dyn Write>,
+    rows: String,
+}
+
+impl HtmlSink {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        Self {
+            writer,
+            rows: String::new(),
+        }
+    }
+}
+
+impl OutputSink for HtmlSink {
+    fn diagnostic(&mut self, d: &TracedTexDiagnostic) -> io::Result<()> {
+        let trace: Vec<String> = d
+            .call_stack()
+            .iter()
+            .map(|f| html_escape(&f.display().to_string()))
+            .collect();
+        self.rows += &format!(
+            "<tr class=\"{}\"><td>{}</td><td><pre>{}</pre></td><td>{}</td></tr>\n",
+            d.diagnostic().level(),
+            html_escape(&d.title()),
+            html_escape(&d.diagnostic().message),
+            trace.join("<br>"),
+        );
+        Ok(())
+    }
+
+    fn summary(&mut self, summary: &Summary) -> io::Result<()> {
+        write!(
+            self.writer,
+            "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>texlog report</title>
+<style>
+body {{ font-family: sans-serif; }}
+td {{ vertical-align: top; padding: 0.3em; }}
+tr.warning td:first-child {{ color: #b58900; }}
+tr.error td:first-child {{ color: #dc322f; }}
+</style>
+</head>
+<body>
+<h1>texlog report</h1>
+<p>{} errors, {} warnings</p>
+<table>
+<tr><th>Kind</th><th>Message</th><th>Trace</th></tr>
+{}</table>
+</body>
+</html>
+",
+            summary.errors, summary.warnings, self.rows
+        )
+    }
+}
@@ -0,0 +1,121 @@
+use std::{fmt::Display, path::PathBuf};
+
+use crate::{log::Log, parser::Node};
+
+/// A package that was loaded, from the `Package: hyperref 2023-02-07 v7.00v Hypertext links
+/// for LaTeX` line it writes to the log
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadedPackage {
+    pub name: String,
+
+    /// Release date, as the package writes it
+    pub date: Option<String>,
+    pub version: Option<String>,
+    pub description: String,
+
+    /// The file of the package
+    pub file: PathBuf,
+
+    /// The file that loaded the package
+    pub loaded_by: Option<PathBuf>,
+}
+
+impl LoadedPackage {
+    /// Parse the part of a line after `Package: `
+    fn parse(line: &str, file: PathBuf, loaded_by: Option<PathBuf>) -> Option<Self> {
+        let mut words = line.split_whitespace().peekable();
+        let name = words.next()?.to_string();
+        let date = words
+            .next_if(|w| w.starts_with(|c: char| c.is_ascii_digit()))
+            .map(str::to_string);
+        let version = words
+            .next_if(|w| w.starts_with('v') && w[1..].starts_with(|c: char| c.is_ascii_digit()))
+            .map(str::to_string);
+        Some(Self {
+            name,
+            date,
+            version,
+            description: words.collect::<Vec<_>>().join(" "),
+            file,
+            loaded_by,
+        })
+    }
+}
+
+impl Display for LoadedPackage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:<20} {:<10} {:<10}",
+            self.name,
+            self.date.as_deref().unwrap_or("-"),
+            self.version.as_deref().unwrap_or("-"),
+        )?;
+        if let Some(loaded_by) = &self.loaded_by {
+            write!(f, " loaded by {}", loaded_by.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// Add the packages of a node and the files it calls
+fn collect(node: &Node, parent: Option<&Node>, packages: &mut Vec<LoadedPackage>) {
+    for line in node.messages().lines() {
+        if let Some(line) = line.trim_start().strip_prefix("Package: ") {
+            let loaded_by = parent.map(|p| PathBuf::from(p.file()));
+            packages.extend(LoadedPackage::parse(
+                line,
+                PathBuf::from(node.file()),
+                loaded_by,
+            ));
+        }
+    }
+    for call in &node.calls {
+        collect(call, Some(node), packages);
+    }
+}
+
+impl Log {
+    /// The packages loaded in the final run, in the order they were loaded
+    pub fn packages(&self) -> Vec<LoadedPackage> {
+        let mut packages = vec![];
+        collect(self.root_node(), None, &mut packages);
+        let base_dir = self.base_dir();
+        for package in &mut packages {
+            package.file = self.paths.resolve(&base_dir, &package.file);
+            if let Some(loaded_by) = &mut package.loaded_by {
+                *loaded_by = self.paths.resolve(&base_dir, loaded_by);
+            }
+        }
+        packages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::parse_source, text::SourceText};
+
+    #[test]
+    fn packages() {
+        let text = "(./main.tex\n(/usr/share/texmf/tex/latex/hyperref/hyperref.sty\nPackage: hyperref 2021-02-27 v7.00k Hypertext links for LaTeX\n(/usr/share/texmf/tex/generic/iftex/iftex.sty\nPackage: iftex 2020/03/06 v1.0d TeX engine tests\n))\n(./local.sty\nPackage: local\n))";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let packages = log.packages();
+        assert_eq!(packages.len(), 3);
+        assert_eq!(packages[0].name, "hyperref");
+        assert_eq!(packages[0].version.as_deref(), Some("v7.00k"));
+        assert_eq!(packages[0].description, "Hypertext links for LaTeX");
+        assert_eq!(packages[0].loaded_by, Some(PathBuf::from("./main.tex")));
+        assert_eq!(packages[1].date.as_deref(), Some("2020/03/06"));
+        assert_eq!(
+            packages[1].loaded_by,
+            Some(PathBuf::from(
+                "/usr/share/texmf/tex/latex/hyperref/hyperref.sty"
+            ))
+        );
+        assert_eq!(
+            (packages[2].date.clone(), packages[2].version.clone()),
+            (None, None)
+        );
+    }
+}
@@ -1,54 +1,588 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
 use crate::{
+    error::{Error, Result},
     lexer::{self, Token, TokenKind},
-    log::Log,
+    log::{Log, TracedTexDiagnostic},
+    span::Span,
+    suggest::suggestion,
     text::SourceText,
 };
 
-pub fn parse_source(source: SourceText) -> Log {
-    let tokens = lexer::tokenize(source.as_str());
-    let mut parser = Parser::new(tokens);
-    parser.parse(source)
+/// Parse a log. Fails if the log does not open a file.
+pub fn parse_source(source: SourceText) -> Result<Log> {
+    parse_text(source, true)
 }
 
-#[derive(Clone, Debug, PartialEq)]
+/// Parse a log, or other TeX output. `wrapped` tells if long lines were wrapped by TeX.
+pub(crate) fn parse_text(source: SourceText, wrapped: bool) -> Result<Log> {
+    let mut timing = ParseTiming::default();
+    let starts = run_starts(source.as_str());
+    let mut runs = vec![];
+    let mut last: Option<(usize, String, Node, Vec<String>, Engine)> = None;
+    for (i, start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(source.as_str().len());
+        let text = &source.as_str()[*start..end];
+
+        // Tokens point into the whole log, not just this run
+        let offset = source.as_str()[..*start].chars().count();
+        let line_offset = source.as_str()[..*start].matches('\n').count();
+        let lexing = stopwatch();
+        let mut tokens = match wrapped {
+            true => lexer::tokenize(text),
+            false => lexer::tokenize_unwrapped(text),
+        };
+        for token in &mut tokens {
+            token.span.start += offset;
+            token.span.end += offset;
+            token.line += line_offset;
+        }
+        timing.lexing += lexing();
+        timing.tokens += tokens.len();
+
+        let parsing = stopwatch();
+        let mut parser = Parser::new(tokens);
+        let (info, root_node) = parser.parse_run(text)?;
+        timing.parsing += parsing();
+        timing.nodes += root_node.walk().count();
+        let run = (
+            *start,
+            info,
+            root_node,
+            parser.parse_warnings,
+            parser.engine,
+        );
+        if let Some((_, _, previous, _, _)) = last.replace(run) {
+            runs.push(previous);
+        }
+    }
+
+    let (run_start, info, root_node, parse_warnings, engine) =
+        last.expect("A log has at least one run");
+    let mut log = Log::new(source, root_node);
+    log.info = info;
+    log.engine = engine;
+    log.parse_warnings = parse_warnings;
+    log.runs = runs;
+    log.run_start = run_start;
+    log.timing = timing;
+    Ok(log)
+}
+
+/// Start a clock, and return the time since when called. The standard clock panics on
+/// `wasm32-unknown-unknown`, where no time is measured.
+pub(crate) fn stopwatch() -> impl Fn() -> Duration {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    let start = Some(std::time::Instant::now());
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    let start: Option<std::time::Instant> = None;
+    move || start.map(|s| s.elapsed()).unwrap_or_default()
+}
+
+/// Time spent on the stages of reading a log, for finding out why a large log is slow
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParseTiming {
+    /// Reading the file from disk, which is zero for logs that were not read from a file
+    pub reading: Duration,
+    pub lexing: Duration,
+    pub parsing: Duration,
+
+    /// Number of tokens in all runs
+    pub tokens: usize,
+
+    /// Number of files opened in all runs
+    pub nodes: usize,
+}
+
+impl Display for ParseTiming {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        writeln!(f, "reading  {:>9.2} ms", ms(self.reading))?;
+        writeln!(
+            f,
+            "lexing   {:>9.2} ms  {} tokens",
+            ms(self.lexing),
+            self.tokens
+        )?;
+        write!(
+            f,
+            "parsing  {:>9.2} ms  {} files",
+            ms(self.parsing),
+            self.nodes
+        )
+    }
+}
+
+/// Byte offsets of the runs in a log. A new run starts at every banner like `This is pdfTeX,
+/// Version 3.14...`, which happens when the logs of several passes are concatenated, as in the
+/// output of latexmk. Anything before the first banner belongs to the first run.
+pub fn run_starts(log: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    let mut seen_banner = false;
+    let mut pos = 0;
+    for line in log.split_inclusive('\n') {
+        if line.starts_with("This is ") && line.contains("TeX, Version ") {
+            if seen_banner {
+                starts.push(pos);
+            }
+            seen_banner = true;
+        }
+        pos += line.len();
+    }
+    starts
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum DiagnosticLevel {
+    /// Messages about what TeX and the packages did, which are hidden by default
+    Info,
     Warning,
     Error,
 }
 
+impl Display for DiagnosticLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiagnosticLevel::Info => write!(f, "info"),
+            DiagnosticLevel::Warning => write!(f, "warning"),
+            DiagnosticLevel::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// The TeX engine that wrote a log
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Engine {
+    #[default]
+    PdfTex,
+    LuaTex,
+    XeTex,
+}
+
+impl Engine {
+    /// Detect the engine from the banner on the first line of a log, like
+    /// `This is LuaHBTeX, Version 1.15.0 (TeX Live 2022)`. Assumes pdfTeX if it is not recognized.
+    /// Tectonic runs XeTeX, and names itself in the banner of its logs.
+    pub fn detect(log: &str) -> Self {
+        let banner = log.lines().next().unwrap_or("");
+        match banner.strip_prefix("This is ") {
+            Some(b) if b.starts_with("LuaTeX") || b.starts_with("LuaHBTeX") => Engine::LuaTex,
+            Some(b) if b.starts_with("XeTeX") || b.contains("Tectonic") => Engine::XeTex,
+            _ => Engine::PdfTex,
+        }
+    }
+}
+
+impl Display for Engine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Engine::PdfTex => write!(f, "pdfTeX"),
+            Engine::LuaTex => write!(f, "LuaTeX"),
+            Engine::XeTex => write!(f, "XeTeX"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum TexDiagnosticKind {
-    Font,
+    /// Font warning from LaTeX. A font shape that is not available is replaced by another one;
+    /// both are empty for other font warnings.
+    Font {
+        requested: String,
+        substituted: String,
+    },
     Package(String),
-    UnderfullHbox,
-    OverfullHbox,
+    /// Line that is too loose, with the lines of the source paragraph if known
+    UnderfullHbox {
+        badness: u32,
+        lines: Option<(u32, u32)>,
+    },
+    /// Line that sticks out into the margin by `overflow_pt` points
+    OverfullHbox {
+        overflow_pt: f32,
+        lines: Option<(u32, u32)>,
+    },
+    /// Page that is too loose
+    UnderfullVbox {
+        badness: u32,
+        lines: Option<(u32, u32)>,
+    },
+    /// Page that is too tall by `overflow_pt` points
+    OverfullVbox {
+        overflow_pt: f32,
+        lines: Option<(u32, u32)>,
+    },
     PdfLatex,
     GenericError(String),
+    /// Reference to an undefined label, carrying the label key
+    Reference(String),
+    /// Label that is defined more than once, carrying the label key
+    MultiplyDefinedLabel(String),
+    /// Labels may have changed, so another run is needed
+    Rerun,
+    /// Warning from a document class, carrying the class name
+    Class(String),
+    /// Error from a document class, carrying the class name
+    ClassError(String),
+    /// Error from a package, carrying the package name
+    PackageError(String),
+    /// Error from pdfTeX itself, like a missing image, carrying the description
+    PdfTexError(String),
+    /// Font that could not be loaded, carrying the font name
+    MissingFont(String),
+    /// Token that hyperref removed from a PDF string like a bookmark, carrying the token
+    PdfStringToken(String),
+    /// Two PDF destinations with the same name, carrying the name
+    DuplicateDestination(String),
+    /// Argument that ran past the end of a paragraph or file, carrying the macro name
+    Runaway(String),
+    /// Error that stopped the compilation, like an emergency stop
+    Fatal,
+    /// Use of a macro that is not defined, carrying the macro name
+    UndefinedControlSequence(String),
+    /// Error in Lua code run by LuaTeX, carrying the description
+    LuaError(String),
+    /// Character that is not in the current font, carrying the character
+    MissingCharacter(String),
+    /// Warning from xdvipdfmx, the PDF backend of XeTeX
+    Xdvipdfmx,
+    /// Error from xdvipdfmx
+    XdvipdfmxError,
+    /// Warning from Biber, carrying the key of the entry it is about if any
+    Biber(String),
+    /// Error from Biber, carrying the key of the entry it is about if any
+    BiberError(String),
+    /// Warning from makeindex or xindy
+    Index,
+    /// Error from makeindex or xindy, like a rejected entry
+    IndexError,
+    /// File that TeX could not find, like a misspelled or uninstalled package, carrying the
+    /// file name
+    MissingFile(String),
+    /// TeX ran out of one of its fixed-size memory pools, carrying the pool name, like
+    /// `main memory size`
+    CapacityExceeded(String),
+    /// Warning from LaTeX that has no kind of its own, carrying the first line of the warning
+    LatexWarning(String),
+    /// Info message from LaTeX, like `LaTeX Info: Redefining \setlength`
+    LatexInfo,
+    /// Info message from a package, carrying the package name
+    PackageInfo(String),
+    /// Info message from a document class, carrying the class name
+    ClassInfo(String),
 }
 
 impl TexDiagnosticKind {
     pub fn level(&self) -> DiagnosticLevel {
         match self {
-            TexDiagnosticKind::Font => DiagnosticLevel::Warning,
+            TexDiagnosticKind::Font { .. } => DiagnosticLevel::Warning,
             TexDiagnosticKind::Package(_) => DiagnosticLevel::Warning,
-            TexDiagnosticKind::UnderfullHbox => DiagnosticLevel::Warning,
-            TexDiagnosticKind::OverfullHbox => DiagnosticLevel::Warning,
+            TexDiagnosticKind::UnderfullHbox { .. } => DiagnosticLevel::Warning,
+            TexDiagnosticKind::OverfullHbox { .. } => DiagnosticLevel::Warning,
+            TexDiagnosticKind::UnderfullVbox { .. } => DiagnosticLevel::Warning,
+            TexDiagnosticKind::OverfullVbox { .. } => DiagnosticLevel::Warning,
             TexDiagnosticKind::PdfLatex => DiagnosticLevel::Warning,
             TexDiagnosticKind::GenericError(_) => DiagnosticLevel::Error,
+            TexDiagnosticKind::Reference(_) => DiagnosticLevel::Warning,
+            TexDiagnosticKind::MultiplyDefinedLabel(_) => DiagnosticLevel::Warning,
+            TexDiagnosticKind::Rerun => DiagnosticLevel::Warning,
+            TexDiagnosticKind::Class(_) => DiagnosticLevel::Warning,
+            TexDiagnosticKind::ClassError(_) => DiagnosticLevel::Error,
+            TexDiagnosticKind::PackageError(_) => DiagnosticLevel::Error,
+            TexDiagnosticKind::PdfTexError(_) => DiagnosticLevel::Error,
+            TexDiagnosticKind::MissingFont(_) => DiagnosticLevel::Error,
+            TexDiagnosticKind::PdfStringToken(_) => DiagnosticLevel::Warning,
+            TexDiagnosticKind::DuplicateDestination(_) => DiagnosticLevel::Warning,
+            TexDiagnosticKind::Runaway(_) => DiagnosticLevel::Error,
+            TexDiagnosticKind::Fatal => DiagnosticLevel::Error,
+            TexDiagnosticKind::UndefinedControlSequence(_) => DiagnosticLevel::Error,
+            TexDiagnosticKind::LuaError(_) => DiagnosticLevel::Error,
+            TexDiagnosticKind::MissingCharacter(_) => DiagnosticLevel::Warning,
+            TexDiagnosticKind::Xdvipdfmx => DiagnosticLevel::Warning,
+            TexDiagnosticKind::XdvipdfmxError => DiagnosticLevel::Error,
+            TexDiagnosticKind::Biber(_) => DiagnosticLevel::Warning,
+            TexDiagnosticKind::BiberError(_) => DiagnosticLevel::Error,
+            TexDiagnosticKind::Index => DiagnosticLevel::Warning,
+            TexDiagnosticKind::IndexError => DiagnosticLevel::Error,
+            TexDiagnosticKind::MissingFile(_) => DiagnosticLevel::Error,
+            TexDiagnosticKind::CapacityExceeded(_) => DiagnosticLevel::Error,
+            TexDiagnosticKind::LatexWarning(_) => DiagnosticLevel::Warning,
+            TexDiagnosticKind::LatexInfo => DiagnosticLevel::Info,
+            TexDiagnosticKind::PackageInfo(_) => DiagnosticLevel::Info,
+            TexDiagnosticKind::ClassInfo(_) => DiagnosticLevel::Info,
+        }
+    }
+
+    /// Short name of the kind used on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            TexDiagnosticKind::Font { .. } => "font",
+            TexDiagnosticKind::Package(_) => "package",
+            TexDiagnosticKind::UnderfullHbox { .. } => "underfull-hbox",
+            TexDiagnosticKind::OverfullHbox { .. } => "overfull-hbox",
+            TexDiagnosticKind::UnderfullVbox { .. } => "underfull-vbox",
+            TexDiagnosticKind::OverfullVbox { .. } => "overfull-vbox",
+            TexDiagnosticKind::PdfLatex => "pdftex",
+            TexDiagnosticKind::GenericError(_) => "error",
+            TexDiagnosticKind::Reference(_) => "reference",
+            TexDiagnosticKind::MultiplyDefinedLabel(_) => "label",
+            TexDiagnosticKind::Rerun => "rerun",
+            TexDiagnosticKind::Class(_) => "class",
+            TexDiagnosticKind::ClassError(_) => "class-error",
+            TexDiagnosticKind::PackageError(_) => "package-error",
+            TexDiagnosticKind::PdfTexError(_) => "pdftex-error",
+            TexDiagnosticKind::MissingFont(_) => "missing-font",
+            TexDiagnosticKind::PdfStringToken(_) => "hyperref-token",
+            TexDiagnosticKind::DuplicateDestination(_) => "duplicate-destination",
+            TexDiagnosticKind::Runaway(_) => "runaway",
+            TexDiagnosticKind::Fatal => "fatal",
+            TexDiagnosticKind::UndefinedControlSequence(_) => "undefined-control-sequence",
+            TexDiagnosticKind::LuaError(_) => "lua-error",
+            TexDiagnosticKind::MissingCharacter(_) => "missing-character",
+            TexDiagnosticKind::Xdvipdfmx => "xdvipdfmx",
+            TexDiagnosticKind::XdvipdfmxError => "xdvipdfmx-error",
+            TexDiagnosticKind::Biber(_) => "biber",
+            TexDiagnosticKind::BiberError(_) => "biber-error",
+            TexDiagnosticKind::Index => "index",
+            TexDiagnosticKind::IndexError => "index-error",
+            TexDiagnosticKind::MissingFile(_) => "missing-file",
+            TexDiagnosticKind::CapacityExceeded(_) => "capacity",
+            TexDiagnosticKind::LatexWarning(_) => "latex",
+            TexDiagnosticKind::LatexInfo => "latex-info",
+            TexDiagnosticKind::PackageInfo(_) => "package-info",
+            TexDiagnosticKind::ClassInfo(_) => "class-info",
+        }
+    }
+
+    /// The value carried by the kind, like the package name
+    pub fn argument(&self) -> Option<&str> {
+        match self {
+            TexDiagnosticKind::Package(p_name) => Some(p_name),
+            TexDiagnosticKind::Font { requested, .. } if !requested.is_empty() => Some(requested),
+            TexDiagnosticKind::GenericError(e) => Some(e.trim()),
+            TexDiagnosticKind::Reference(key) => Some(key),
+            TexDiagnosticKind::MultiplyDefinedLabel(key) => Some(key),
+            TexDiagnosticKind::Class(c_name) => Some(c_name),
+            TexDiagnosticKind::ClassError(c_name) => Some(c_name),
+            TexDiagnosticKind::PackageError(p_name) => Some(p_name),
+            TexDiagnosticKind::PdfTexError(e) => Some(e),
+            TexDiagnosticKind::MissingFont(font) => Some(font),
+            TexDiagnosticKind::PdfStringToken(token) => Some(token),
+            TexDiagnosticKind::DuplicateDestination(name) => Some(name),
+            TexDiagnosticKind::Runaway(macro_name) => Some(macro_name),
+            TexDiagnosticKind::UndefinedControlSequence(macro_name) => Some(macro_name),
+            TexDiagnosticKind::LuaError(e) => Some(e),
+            TexDiagnosticKind::MissingCharacter(c) => Some(c),
+            TexDiagnosticKind::MissingFile(file) => Some(file),
+            TexDiagnosticKind::CapacityExceeded(pool) => Some(pool),
+            TexDiagnosticKind::LatexWarning(w) => Some(w),
+            TexDiagnosticKind::PackageInfo(p_name) => Some(p_name),
+            TexDiagnosticKind::ClassInfo(c_name) => Some(c_name),
+            TexDiagnosticKind::Biber(key) | TexDiagnosticKind::BiberError(key)
+                if !key.is_empty() =>
+            {
+                Some(key)
+            }
+            _ => None,
+        }
+    }
+
+    /// Advice on how to fix a diagnostic of this kind, if there is any beyond the message
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            TexDiagnosticKind::MissingFile(file) => {
+                let path = Path::new(file);
+                match path.extension().and_then(|e| e.to_str()) {
+                    Some("sty" | "cls") => {
+                        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                        Some(format!(
+                            "Check the spelling of `{}`. If it is right, install the package with \
+                             `tlmgr install {}` on TeX Live, or from the MiKTeX Console on MiKTeX.",
+                            file, name
+                        ))
+                    }
+                    _ => Some(format!(
+                        "Check the spelling of `{}` and that the file exists relative to the \
+                         directory TeX was run from.",
+                        file
+                    )),
+                }
+            }
+            TexDiagnosticKind::CapacityExceeded(pool) => {
+                let variable = match Self::pool_variable(pool) {
+                    // Nesting too deep is almost always a macro that expands to itself
+                    None | Some("stack_size" | "max_in_open" | "nest_size" | "save_size") => {
+                        return Some(format!(
+                            "The {} ran out, which is usually caused by an infinite loop, like a \
+                             macro that calls itself or a command redefined in terms of itself. \
+                             Check the macros used at the line of the error.",
+                            pool
+                        ))
+                    }
+                    Some(variable) => variable,
+                };
+                Some(format!(
+                    "TeX ran out of {}. An infinite loop can cause this, but a large document \
+                     may simply need more: raise `{}` in texmf.cnf, or set it as an environment \
+                     variable for a single run.",
+                    pool, variable
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// The texmf.cnf variable that sets the size of a pool of TeX. Grouping levels are fixed.
+    fn pool_variable(pool: &str) -> Option<&'static str> {
+        match pool {
+            "main memory size" | "extra memory size" => Some("main_memory"),
+            "pool size" | "number of strings" => Some("pool_size"),
+            "buffer size" => Some("buf_size"),
+            "hash size" => Some("hash_extra"),
+            "font memory" => Some("font_mem_size"),
+            "save size" => Some("save_size"),
+            "input stack size" => Some("stack_size"),
+            "text input levels" => Some("max_in_open"),
+            "semantic nest size" => Some("nest_size"),
+            "parameter stack size" => Some("param_size"),
+            "exception dictionary" => Some("hyph_size"),
+            _ => None,
+        }
+    }
+
+    /// The package that emitted the warning, if known
+    pub fn package(&self) -> Option<&str> {
+        match self {
+            TexDiagnosticKind::Package(p_name) | TexDiagnosticKind::PackageInfo(p_name) => {
+                Some(p_name)
+            }
+            TexDiagnosticKind::PdfStringToken(_) => Some("hyperref"),
+            _ => None,
         }
     }
 }
 
-impl ToString for TexDiagnosticKind {
-    fn to_string(&self) -> String {
+impl Display for TexDiagnosticKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            TexDiagnosticKind::Font => "Font Warning".to_string(),
-            TexDiagnosticKind::Package(p_name) => format!("Package ({}) Warning", p_name),
-            TexDiagnosticKind::UnderfullHbox => "Underfull Hbox".to_string(),
-            TexDiagnosticKind::OverfullHbox => "Overfull Hbox".to_string(),
-            TexDiagnosticKind::PdfLatex => "PdfLaTeX Warning".to_string(),
-            TexDiagnosticKind::GenericError(e) => format!("Error: {}", e),
+            TexDiagnosticKind::Font { .. } => write!(f, "Font Warning"),
+            TexDiagnosticKind::Package(p_name) => write!(f, "Package ({}) Warning", p_name),
+            TexDiagnosticKind::UnderfullHbox { .. } => write!(f, "Underfull Hbox"),
+            TexDiagnosticKind::OverfullHbox { .. } => write!(f, "Overfull Hbox"),
+            TexDiagnosticKind::UnderfullVbox { .. } => write!(f, "Underfull Vbox"),
+            TexDiagnosticKind::OverfullVbox { .. } => write!(f, "Overfull Vbox"),
+            TexDiagnosticKind::PdfLatex => write!(f, "PdfLaTeX Warning"),
+            TexDiagnosticKind::GenericError(e) => write!(f, "Error: {}", e),
+            TexDiagnosticKind::Reference(key) => write!(f, "Undefined Reference ({})", key),
+            TexDiagnosticKind::MultiplyDefinedLabel(key) => {
+                write!(f, "Label ({}) Multiply Defined", key)
+            }
+            TexDiagnosticKind::Rerun => write!(f, "Rerun Needed"),
+            TexDiagnosticKind::Class(c_name) => write!(f, "Class ({}) Warning", c_name),
+            TexDiagnosticKind::ClassError(c_name) => write!(f, "Class ({}) Error", c_name),
+            TexDiagnosticKind::PackageError(p_name) => write!(f, "Package ({}) Error", p_name),
+            TexDiagnosticKind::PdfTexError(e) => write!(f, "PdfTeX Error: {}", e),
+            TexDiagnosticKind::MissingFont(font) => write!(f, "Missing Font ({})", font),
+            TexDiagnosticKind::PdfStringToken(token) => {
+                write!(f, "Token Not Allowed in PDF String ({})", token)
+            }
+            TexDiagnosticKind::DuplicateDestination(name) => {
+                write!(f, "Duplicate Destination ({})", name)
+            }
+            TexDiagnosticKind::Runaway(macro_name) => {
+                write!(f, "Runaway Argument ({})", macro_name)
+            }
+            TexDiagnosticKind::Fatal => write!(f, "Fatal Error"),
+            TexDiagnosticKind::UndefinedControlSequence(macro_name) => {
+                write!(f, "Undefined Control Sequence ({})", macro_name)
+            }
+            TexDiagnosticKind::LuaError(e) => write!(f, "Lua Error: {}", e),
+            TexDiagnosticKind::MissingCharacter(c) => write!(f, "Missing Character ({})", c),
+            TexDiagnosticKind::Xdvipdfmx => write!(f, "Xdvipdfmx Warning"),
+            TexDiagnosticKind::XdvipdfmxError => write!(f, "Xdvipdfmx Error"),
+            TexDiagnosticKind::Biber(key) if key.is_empty() => write!(f, "Biber Warning"),
+            TexDiagnosticKind::Biber(key) => write!(f, "Biber Warning ({})", key),
+            TexDiagnosticKind::BiberError(key) if key.is_empty() => write!(f, "Biber Error"),
+            TexDiagnosticKind::BiberError(key) => write!(f, "Biber Error ({})", key),
+            TexDiagnosticKind::Index => write!(f, "Index Warning"),
+            TexDiagnosticKind::IndexError => write!(f, "Index Error"),
+            TexDiagnosticKind::MissingFile(file) => write!(f, "Missing File ({})", file),
+            TexDiagnosticKind::CapacityExceeded(pool) => {
+                write!(f, "TeX Capacity Exceeded ({})", pool)
+            }
+            TexDiagnosticKind::LatexWarning(w) => write!(f, "LaTeX Warning: {}", w),
+            TexDiagnosticKind::LatexInfo => write!(f, "LaTeX Info"),
+            TexDiagnosticKind::PackageInfo(p_name) => write!(f, "Package ({}) Info", p_name),
+            TexDiagnosticKind::ClassInfo(c_name) => write!(f, "Class ({}) Info", c_name),
+        }
+    }
+}
+
+/// Location in a tex source file that a diagnostic points to
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceLocation {
+    /// Line in the source file (1-indexed)
+    pub line: usize,
+
+    /// Character range of the offending text on the line (1-indexed, end exclusive)
+    pub columns: Option<(usize, usize)>,
+}
+
+impl SourceLocation {
+    /// Find the `l.N` context line of an error message. TeX splits the echoed source line at
+    /// the point where it stopped reading, so the text after `l.N ` ends with the offending token.
+    fn from_message(message: &str) -> Option<Self> {
+        for line in message.lines() {
+            let Some(rest) = line.strip_prefix("l.") else {
+                continue;
+            };
+            let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            let Ok(line_number) = digits.parse() else {
+                continue;
+            };
+            let read = rest[digits.len()..].strip_prefix(' ').unwrap_or("");
+            return Some(Self {
+                line: line_number,
+                columns: Self::offending_columns(read),
+            });
+        }
+        None
+    }
+
+    /// Columns of the last token in the part of the line that TeX had read
+    fn offending_columns(read: &str) -> Option<(usize, usize)> {
+        // Long lines are cut short with "...", so the column is not known
+        if read.is_empty() || read.starts_with("...") {
+            return None;
+        }
+
+        let chars: Vec<char> = read.chars().collect();
+        let end = chars.len();
+        let mut start = end - 1;
+
+        // Underline the whole control sequence if the line ends in one
+        if chars[start].is_alphabetic() {
+            let mut word_start = start;
+            while word_start > 0 && chars[word_start - 1].is_alphabetic() {
+                word_start -= 1;
+            }
+            if word_start > 0 && chars[word_start - 1] == '\\' {
+                start = word_start - 1;
+            }
         }
+
+        Some((start + 1, end + 1))
+    }
+
+    /// The first column of the location, if known
+    pub fn column(&self) -> Option<usize> {
+        self.columns.map(|(start, _)| start)
     }
 }
 
@@ -56,34 +590,330 @@ impl ToString for TexDiagnosticKind {
 pub struct TexDiagnostic {
     pub(crate) kind: TexDiagnosticKind,
     pub(crate) message: String,
+    pub(crate) location: Option<SourceLocation>,
+
+    /// The region of the log file that the diagnostic was read from
+    pub(crate) span: Span,
+
+    /// Severity, which is the kind's level unless it has been overridden
+    pub(crate) level: DiagnosticLevel,
+
+    /// The tex file that was being read when the diagnostic was emitted
+    pub(crate) source_file: Option<PathBuf>,
+
+    /// Line in `source_file` that the diagnostic refers to
+    pub(crate) source_line: Option<usize>,
+
+    /// Page of the output document that was being built when the diagnostic was emitted
+    pub(crate) page: Option<u32>,
+
+    /// A fix for well-known errors, like a package that defines an undefined command
+    pub(crate) suggestion: Option<String>,
 }
 
 impl TexDiagnostic {
+    pub(crate) fn new(kind: TexDiagnosticKind, message: String, pos: usize) -> Self {
+        let location = SourceLocation::from_message(&message);
+        Self {
+            level: kind.level(),
+            source_file: None,
+            page: None,
+            source_line: location
+                .as_ref()
+                .map(|l| l.line)
+                .or_else(|| Self::parse_source_line(&message)),
+            span: Span::new(pos, pos + message.chars().count()),
+            suggestion: suggestion(&kind, &message),
+            kind: Self::with_details(kind, &message),
+            message,
+            location,
+        }
+    }
+
+    /// Fill in the fields of a kind that are read from the message, like the size of a box
+    fn with_details(kind: TexDiagnosticKind, message: &str) -> TexDiagnosticKind {
+        let lines = Self::parse_box_lines(message);
+        match kind {
+            TexDiagnosticKind::OverfullHbox { .. } => TexDiagnosticKind::OverfullHbox {
+                overflow_pt: Self::parse_overflow(message).unwrap_or_default(),
+                lines,
+            },
+            TexDiagnosticKind::OverfullVbox { .. } => TexDiagnosticKind::OverfullVbox {
+                overflow_pt: Self::parse_overflow(message).unwrap_or_default(),
+                lines,
+            },
+            TexDiagnosticKind::UnderfullHbox { .. } => TexDiagnosticKind::UnderfullHbox {
+                badness: Self::parse_badness(message).unwrap_or_default(),
+                lines,
+            },
+            TexDiagnosticKind::UnderfullVbox { .. } => TexDiagnosticKind::UnderfullVbox {
+                badness: Self::parse_badness(message).unwrap_or_default(),
+                lines,
+            },
+            TexDiagnosticKind::Font { .. } => {
+                let quoted = |prefix: &str| {
+                    let start = message.find(prefix)? + prefix.len();
+                    let end = message[start..].find('\'')?;
+                    Some(message[start..start + end].to_string())
+                };
+                TexDiagnosticKind::Font {
+                    requested: quoted("Font shape `").unwrap_or_default(),
+                    substituted: quoted("using `").unwrap_or_default(),
+                }
+            }
+            kind => kind,
+        }
+    }
+
+    /// Parse the `66` and `68` from `at lines 66--68`, or the line of `detected at line 20`
+    fn parse_box_lines(message: &str) -> Option<(u32, u32)> {
+        let number = |s: &str| -> Option<u32> {
+            let digits: String = s.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse().ok()
+        };
+        if let Some(start) = message.find("at lines ") {
+            let (first, last) = message[start + "at lines ".len()..].split_once("--")?;
+            return Some((number(first)?, number(last)?));
+        }
+        let start = message.find("detected at line ")? + "detected at line ".len();
+        let line = number(&message[start..])?;
+        Some((line, line))
+    }
+
+    /// Parse the `12.3` from `(12.3pt too wide)` or `(12.3pt too high)`
+    fn parse_overflow(message: &str) -> Option<f32> {
+        let end = message.find("pt too ")?;
+        let start = message[..end].rfind('(')? + 1;
+        message[start..end].parse().ok()
+    }
+
+    /// Parse the `10000` from `(badness 10000)`
+    fn parse_badness(message: &str) -> Option<u32> {
+        let start = message.find("(badness ")? + "(badness ".len();
+        let digits: String = message[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        digits.parse().ok()
+    }
+
+    /// Parse the line from `on input line 42` or the first line of `at lines 66--67`
+    fn parse_source_line(message: &str) -> Option<usize> {
+        let start = ["on input line ", "at lines ", "at line "]
+            .iter()
+            .find_map(|p| message.find(p).map(|i| i + p.len()))?;
+        let digits: String = message[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        digits.parse().ok()
+    }
+
+    /// The tex file that the diagnostic points to
+    pub fn source_file(&self) -> Option<&Path> {
+        self.source_file.as_deref()
+    }
+
+    /// The line in the source file that the diagnostic points to
+    pub fn source_line(&self) -> Option<usize> {
+        self.source_line
+    }
+
+    /// Whether the diagnostic asks for another run, like the `Rerun to get cross-references
+    /// right` of LaTeX or the `Please rerun LaTeX` of biblatex
+    pub fn requests_rerun(&self) -> bool {
+        if self.kind == TexDiagnosticKind::Rerun {
+            return true;
+        }
+        let message = self.message.to_lowercase();
+        ["rerun to get", "rerun latex", "please (re)run"]
+            .iter()
+            .any(|p| message.contains(p))
+    }
+
+    /// Replace the kind with a more specific one, along with its level
+    fn with_kind(mut self, kind: TexDiagnosticKind) -> Self {
+        self.level = kind.level();
+        self.suggestion = suggestion(&kind, &self.message);
+        self.kind = kind;
+        self
+    }
+
+    /// Page of the output document the diagnostic belongs to
+    pub fn page(&self) -> Option<u32> {
+        self.page
+    }
+
+    /// How far an overfull box sticks out in points
+    pub fn overflow_pt(&self) -> Option<f32> {
+        match self.kind {
+            TexDiagnosticKind::OverfullHbox { overflow_pt, .. }
+            | TexDiagnosticKind::OverfullVbox { overflow_pt, .. } => Some(overflow_pt),
+            _ => None,
+        }
+    }
+
+    /// Badness of an underfull box
+    pub fn badness(&self) -> Option<u32> {
+        match self.kind {
+            TexDiagnosticKind::UnderfullHbox { badness, .. }
+            | TexDiagnosticKind::UnderfullVbox { badness, .. } => Some(badness),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> &TexDiagnosticKind {
+        &self.kind
+    }
+
+    /// The message as it appears in the log
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// How to fix the diagnostic, for well-known errors
+    pub fn suggestion(&self) -> Option<&str> {
+        self.suggestion.as_deref()
+    }
+
+    /// Position of the start of the diagnostic in the log file
+    pub fn pos(&self) -> usize {
+        self.span.start
+    }
+
+    /// The region of the log file that the diagnostic was read from, including any help text
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
     pub fn level(&self) -> DiagnosticLevel {
-        self.kind.level()
+        self.level.clone()
+    }
+
+    pub fn location(&self) -> Option<&SourceLocation> {
+        self.location.as_ref()
+    }
+}
+
+/// Environment variables with the root directories of the TeX distribution
+const TEXMF_VARIABLES: [&str; 8] = [
+    "TEXMFROOT",
+    "TEXMFDIST",
+    "TEXMFMAIN",
+    "TEXMFLOCAL",
+    "TEXMFSYSVAR",
+    "TEXMFSYSCONFIG",
+    "TEXMFVAR",
+    "TEXMFHOME",
+];
+
+/// Directories that TeX distributions are installed in when the variables are not set
+const SYSTEM_PREFIXES: [&str; 6] = [
+    "/usr/share/tex",
+    "/usr/local/share/tex",
+    "/usr/local/texlive",
+    "/opt/texlive",
+    "/Library/TeX",
+    "/nix/store",
+];
+
+/// Whether a file belongs to the TeX distribution rather than the document, like classes,
+/// packages and fonts. Files of the document are usually logged relative to the directory TeX
+/// ran in, while the distribution's files are found through kpathsea and logged with absolute
+/// paths into a texmf tree.
+pub fn is_system_file(path: &Path) -> bool {
+    if !path.is_absolute() {
+        return false;
     }
+    let in_texmf_tree = path.components().any(|c| {
+        let c = c.as_os_str().to_string_lossy().to_lowercase();
+        c.starts_with("texmf") || c == "texlive" || c == "miktex"
+    });
+    in_texmf_tree
+        || SYSTEM_PREFIXES.iter().any(|p| path.starts_with(p))
+        || TEXMF_VARIABLES.iter().any(|v| {
+            std::env::var_os(v)
+                .filter(|root| !root.is_empty())
+                .is_some_and(|root| path.starts_with(root))
+        })
 }
 
-#[derive(Debug)]
-pub(crate) struct Node {
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
     /// Path to file that this node represents
     pub(crate) file: String,
 
     /// Log messages for this node
     pub(crate) messages: String,
 
-    /// Position of the node in log file
-    pub(crate) start_pos: usize,
-    pub(crate) end_pos: usize,
+    /// The region of the log from the `(` opening the file up to the `)` closing it
+    pub(crate) span: Span,
 
     /// The other files that this one calls
     pub(crate) calls: Vec<Node>,
 
     /// List of diagnostics in node
-    diagnostics: Vec<TexDiagnostic>,
+    pub(crate) diagnostics: Vec<TexDiagnostic>,
 }
 
 impl Node {
+    /// A node for a file without any calls to other files
+    pub(crate) fn new(file: String, diagnostics: Vec<TexDiagnostic>) -> Self {
+        Self {
+            file,
+            messages: String::new(),
+            span: Span::default(),
+            calls: vec![],
+            diagnostics,
+        }
+    }
+
+    /// Change the file of the node, and of the diagnostics that point to it
+    pub(crate) fn rename(&mut self, file: String) {
+        for d in &mut self.diagnostics {
+            if d.source_file.as_deref() == Some(Path::new(&self.file)) {
+                d.source_file = Some(PathBuf::from(&file));
+            }
+        }
+        self.file = file;
+    }
+
+    /// Path of the file as it appears in the log
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The region of the log from the `(` opening the file up to the `)` closing it
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The files opened while this one was open
+    pub fn children(&self) -> &[Node] {
+        &self.calls
+    }
+
+    /// The text of the log written while this file was open, without the diagnostics and the
+    /// text of the files it opened
+    pub fn messages(&self) -> &str {
+        &self.messages
+    }
+
+    /// Whether the file is part of the document rather than the TeX distribution
+    pub fn is_user_file(&self) -> bool {
+        !is_system_file(Path::new(&self.file))
+    }
+
+    /// This node and all the nodes below it with their depth, in the order the files were opened
+    pub fn walk(&self) -> impl Iterator<Item = (usize, &Node)> {
+        let mut walker = Walker {
+            nodes: vec![],
+            depth: 0,
+        };
+        walker.visit_node(self);
+        walker.nodes.into_iter()
+    }
+
     pub fn diagnostics(&self) -> &Vec<TexDiagnostic> {
         &self.diagnostics
     }
@@ -103,18 +933,73 @@ impl Node {
     }
 }
 
-pub struct Parser {
+/// A file that has been opened in the log, but not closed yet
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct OpenFile {
+    /// The file's node, with the calls and diagnostics found so far
+    pub(crate) node: Node,
+    unclosed_text_parens: usize,
+}
+
+pub struct Parser<'a> {
     cursor: usize,
-    tokens: Vec<Token>,
+    tokens: Vec<Token<'a>>,
+
+    /// Engine that wrote the log, which decides the engine specific rules
+    pub(crate) engine: Engine,
+
+    /// Number of the last page shipped out, from the `[12]` markers
+    pub(crate) page: u32,
+
+    /// Places where the parentheses did not match up and the parser had to guess
+    pub(crate) parse_warnings: Vec<String>,
+
+    /// Number of files that are open at the cursor
+    depth: usize,
 }
 
-impl Parser {
+/// Deepest nesting of files that is parsed. TeX itself allows far fewer open files, so a log
+/// nested deeper than this is garbage, and parsing it would overflow the stack.
+const MAX_FILE_DEPTH: usize = 1000;
+
+impl<'a> Parser<'a> {
     /// Create a new parser from a vec of tokens
-    pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, cursor: 0 }
+    pub fn new(tokens: Vec<Token<'a>>) -> Self {
+        Self {
+            tokens,
+            cursor: 0,
+            page: 0,
+            engine: Engine::default(),
+            parse_warnings: vec![],
+            depth: 0,
+        }
+    }
+
+    /// Update the page number if the cursor is at a `[12]` page marker. TeX writes the page
+    /// number followed by the files it loaded for the page, like `[3 <./figure.png>]`.
+    fn track_page(&mut self) {
+        if self.current().kind != TokenKind::Punctuation('[') {
+            return;
+        }
+        let mut digits = String::new();
+        let mut offset = 1;
+        while let TokenKind::Punctuation(c @ '0'..='9') = self.peak(offset).kind {
+            digits.push(c);
+            offset += 1;
+        }
+        let terminated = matches!(
+            self.peak(offset).kind,
+            TokenKind::Punctuation(']' | '{' | '<')
+                | TokenKind::Whitespace(_)
+                | TokenKind::Newline
+                | TokenKind::EOF
+        );
+        if let (true, Ok(page)) = (terminated, digits.parse()) {
+            self.page = page;
+        }
     }
 
-    fn peak(&self, offset: isize) -> &Token {
+    fn peak(&self, offset: isize) -> &Token<'a> {
         let index = self.cursor as isize + offset;
         self.tokens
             .get(index.clamp(0, self.tokens.len() as isize - 1) as usize)
@@ -122,22 +1007,20 @@ impl Parser {
     }
 
     /// Get token under cursor
-    fn current(&self) -> &Token {
+    fn current(&self) -> &Token<'a> {
         self.tokens
             .get(self.cursor.clamp(0, self.tokens.len() - 1))
             .expect("Index should be clamped to a valid index.")
     }
 
     /// Get token under cursor and increment cursor
-    fn consume(&mut self) -> &Token {
+    fn consume(&mut self) -> &Token<'a> {
         if self.tokens.is_empty() {
-            if cfg!(Debug) {
+            if cfg!(debug_assertions) {
                 eprintln!("Warning: Called `consume` but token stream is empty.");
             }
-            self.tokens.push(Token {
-                kind: TokenKind::EOF,
-                pos: 0,
-            });
+            self.tokens
+                .push(Token::new(TokenKind::EOF, Span::default(), 1, 1));
             return self.tokens.last().unwrap();
         }
 
@@ -172,12 +1055,14 @@ impl Parser {
                     } else {
                         break;
                     }
-                },
+                }
                 TokenKind::Newline if next == &TokenKind::Newline => {
                     self.consume();
                     break;
-                },
-                _ => {},
+                }
+                // A log that is cut off ends in the middle of a message
+                TokenKind::EOF => break,
+                _ => {}
             }
             self.consume();
         }
@@ -192,90 +1077,475 @@ impl Parser {
         message.trim().to_string()
     }
 
-    fn consume_diag_if_diag(&mut self) -> Option<TexDiagnostic> {
-        // Must be at newline
-        if self.peak(-1).kind != TokenKind::Newline {
-            return None;
-        }
-
-        match &self.current().kind {
-            // pdfTeX warning:
-            TokenKind::Word(w) if w.as_str() == "pdfTeX" => {
-                if self.peak(2).kind != TokenKind::Word("warning".to_string()) {
-                    return None;
-                }
-                if self.peak(3).kind != TokenKind::Punctuation(':') {
-                    return None;
+    /// Consume the message of an info diagnostic. Unlike warnings, infos are not followed by an
+    /// empty line. They continue on lines that start with the package name in parentheses, like
+    /// `(hyperref)`.
+    fn consume_info_message(&mut self) -> String {
+        let start_index = self.cursor;
+        loop {
+            match self.current().kind {
+                TokenKind::EOF => break,
+                TokenKind::Newline => {
+                    let continued = self.peak(1).kind == TokenKind::LeftParen
+                        && matches!(self.peak(2).kind, TokenKind::Word(_))
+                        && self.peak(3).kind == TokenKind::RightParen;
+                    if !continued {
+                        break;
+                    }
                 }
-                Some(TexDiagnostic {
-                    kind: TexDiagnosticKind::PdfLatex,
-                    message: self.consume_diagnostic_message(),
-                })
+                _ => {}
             }
+            self.consume();
+        }
+        let message: String = self.tokens[start_index..self.cursor]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        message.trim().to_string()
+    }
 
-            // LaTeX Font Warning:
-            TokenKind::Word(w) if w.as_str() == "LaTeX" => {
-                if self.peak(2).kind != TokenKind::Word("Font".to_string()) {
-                    return None;
-                }
-                if self.peak(4).kind != TokenKind::Word("Warning".to_string()) {
-                    return None;
-                }
-                if self.peak(5).kind != TokenKind::Punctuation(':') {
-                    return None;
-                }
-                Some(TexDiagnostic {
-                    kind: TexDiagnosticKind::Font,
-                    message: self.consume_diagnostic_message(),
-                })
-            }
+    /// Consume the message of a diagnostic starting at the cursor
+    fn consume_diagnostic(&mut self, kind: TexDiagnosticKind) -> TexDiagnostic {
+        let pos = self.current().span.start;
+        let mut message = match kind.level() {
+            DiagnosticLevel::Info => self.consume_info_message(),
+            _ => self.consume_diagnostic_message(),
+        };
+        if matches!(
+            kind,
+            TexDiagnosticKind::PackageError(_) | TexDiagnosticKind::ClassError(_)
+        ) {
+            message += &self.consume_error_help();
+        }
+        let mut diagnostic = TexDiagnostic::new(kind, message, pos);
+        diagnostic.span.end = self.current().span.start;
+        // Diagnostics are emitted while the next page is being built
+        diagnostic.page = Some(self.page + 1);
+        diagnostic
+    }
 
-            // Overfull \hbox
-            TokenKind::Word(w) if w.as_str() == "Overfull" => {
-                if self.peak(2).kind != TokenKind::Punctuation('\\') {
-                    return None;
-                }
-                if self.peak(3).kind != TokenKind::Word("hbox".to_string()) {
-                    return None;
-                }
-                Some(TexDiagnostic {
-                    kind: TexDiagnosticKind::OverfullHbox,
-                    message: self.consume_diagnostic_message(),
-                })
+    /// Read a quoted argument like `` `fig:intro' `` starting `offset` tokens from the cursor
+    fn quoted_argument(&self, offset: isize) -> Option<String> {
+        if !matches!(self.peak(offset).kind, TokenKind::Punctuation('`' | '\'')) {
+            return None;
+        }
+        let mut argument = String::new();
+        let mut offset = offset + 1;
+        loop {
+            match &self.peak(offset).kind {
+                TokenKind::Punctuation('\'') => return Some(argument),
+                TokenKind::Newline | TokenKind::EOF => return None,
+                kind => argument += &kind.to_string(),
             }
+            offset += 1;
+        }
+    }
 
-            // Underfull \hbox
-            TokenKind::Word(w) if w.as_str() == "Underfull" => {
-                if self.peak(2).kind != TokenKind::Punctuation('\\') {
-                    return None;
-                }
-                if self.peak(3).kind != TokenKind::Word("hbox".to_string()) {
-                    return None;
-                }
-                Some(TexDiagnostic {
-                    kind: TexDiagnosticKind::UnderfullHbox,
-                    message: self.consume_diagnostic_message(),
-                })
+    /// Consume the paragraphs that LaTeX prints after a package or class error, like `See the
+    /// hyperref package documentation for explanation.` and the `l.N` context line
+    fn consume_error_help(&mut self) -> String {
+        let mut help = String::new();
+        while self.current().kind == TokenKind::Newline && self.at_error_help(1) {
+            self.consume();
+            help += "\n\n";
+            help += &self.consume_diagnostic_message();
+        }
+        help
+    }
+
+    /// Whether the line starting `offset` tokens from the cursor is part of an error's help
+    fn at_error_help(&self, offset: isize) -> bool {
+        match &self.peak(offset).kind {
+            TokenKind::Word(w) => match w.as_ref() {
+                "See" | "Type" => true,
+                "l" => self.peak(offset + 1).kind == TokenKind::Punctuation('.'),
+                _ => false,
+            },
+            // The ` ...` placeholder for the lines TeX left out
+            TokenKind::Whitespace(_) => self.peak(offset + 1).kind == TokenKind::Punctuation('.'),
+            _ => false,
+        }
+    }
+
+    /// Kind of a `!` error from its title, like `Class memoir Error: ...`
+    fn error_kind(&self, title: String) -> TexDiagnosticKind {
+        if self.engine == Engine::LuaTex {
+            if let Some(description) = title.strip_prefix("LuaTeX error") {
+                return TexDiagnosticKind::LuaError(description.trim().to_string());
+            }
+        }
+        if let Some(macro_name) = Self::runaway_macro(&title) {
+            return TexDiagnosticKind::Runaway(macro_name);
+        }
+        if let Some(file) = Self::missing_file(&title) {
+            return TexDiagnosticKind::MissingFile(file);
+        }
+        // TeX capacity exceeded, sorry [main memory size=5000000].
+        if let Some(rest) = title.strip_prefix("TeX capacity exceeded, sorry [") {
+            let pool = rest.split(['=', ']']).next().unwrap_or_default();
+            return TexDiagnosticKind::CapacityExceeded(pool.to_string());
+        }
+        let words: Vec<&str> = title.splitn(4, ' ').collect();
+        match words[..] {
+            ["Class", name, "Error:", _] => TexDiagnosticKind::ClassError(name.to_string()),
+            ["Package", name, "Error:", _] => TexDiagnosticKind::PackageError(name.to_string()),
+            // Packages written in Lua report errors as modules
+            ["Module", name, "Error:", _] if self.engine == Engine::LuaTex => {
+                TexDiagnosticKind::PackageError(name.to_string())
             }
+            _ if title.starts_with("Emergency stop") || title.starts_with("==> Fatal error") => {
+                TexDiagnosticKind::Fatal
+            }
+            _ if title.starts_with("fontspec error: \"font-not-found\"") => {
+                TexDiagnosticKind::MissingFont(String::new())
+            }
+            _ if title.starts_with("Font \\") && title.contains(" not loadable") => {
+                TexDiagnosticKind::MissingFont(Self::unloadable_font(&title))
+            }
+            _ => match title.strip_prefix("pdfTeX error") {
+                Some(description) => TexDiagnosticKind::PdfTexError(
+                    description.trim_start_matches([':', ' ']).to_string(),
+                ),
+                None => TexDiagnosticKind::GenericError(title),
+            },
+        }
+    }
+
+    /// Fill in the parts of an error kind that are found on the lines after the title
+    fn refine_error(mut diagnostic: TexDiagnostic) -> TexDiagnostic {
+        match &mut diagnostic.kind {
+            // fontspec names the font on the lines after the title
+            TexDiagnosticKind::MissingFont(font) if font.is_empty() => {
+                *font = Self::fontspec_font(&diagnostic.message).unwrap_or_default();
+                diagnostic
+            }
+            TexDiagnosticKind::GenericError(title)
+                if title.starts_with("Undefined control sequence") =>
+            {
+                match Self::undefined_macro(&diagnostic.message) {
+                    Some(macro_name) => diagnostic
+                        .with_kind(TexDiagnosticKind::UndefinedControlSequence(macro_name)),
+                    None => diagnostic,
+                }
+            }
+            _ => diagnostic,
+        }
+    }
+
+    /// The file of ``LaTeX Error: File `foo.sty' not found.`` or ``I can't find file `foo'.``
+    fn missing_file(title: &str) -> Option<String> {
+        let rest = title
+            .strip_prefix("LaTeX Error: File `")
+            .or_else(|| title.strip_prefix("I can't find file `"))?;
+        let (file, _) = rest.split_once('\'')?;
+        Some(file.to_string())
+    }
+
+    /// The undefined macro of an `Undefined control sequence` error. TeX breaks the first
+    /// context line right after the offending control sequence, like `l.6 \dtae`.
+    fn undefined_macro(message: &str) -> Option<String> {
+        let mut lines = message.lines();
+        lines.find(|l| l.starts_with("! Undefined control sequence"))?;
+        let context = lines.next()?.trim_end();
+        let start = context.rfind('\\')?;
+        let macro_name = &context[start..];
+        match macro_name.len() > 1 && !macro_name.contains(char::is_whitespace) {
+            true => Some(macro_name.to_string()),
+            false => None,
+        }
+    }
+
+    /// The `⌘` of `Missing character: There is no ⌘ (U+2318) in font ...`
+    fn missing_character(message: &str) -> Option<String> {
+        let start = message.find("There is no ")? + "There is no ".len();
+        let end = message[start..].find(" in font")?;
+        let character = &message[start..start + end];
+        // XeTeX adds the code point, like `⌘ (U+2318)`
+        let character = character.split(" (U+").next().unwrap_or(character);
+        Some(character.to_string())
+    }
+
+    /// The macro whose argument ran away, from `Paragraph ended before \date was complete.` or
+    /// `File ended while scanning use of \textbf.`
+    fn runaway_macro(title: &str) -> Option<String> {
+        let rest = [
+            "Paragraph ended before ",
+            "File ended while scanning use of ",
+            "File ended while scanning definition of ",
+        ]
+        .iter()
+        .find_map(|p| title.strip_prefix(p))?;
+        let end = rest.find([' ', '.']).unwrap_or(rest.len());
+        Some(rest[..end].to_string())
+    }
+
+    /// The removed token of hyperref's `Token not allowed in a PDF string` warning, like the
+    /// `\textbf` of `removing `\textbf'`
+    fn pdf_string_token(message: &str) -> Option<String> {
+        if !message.contains("Token not allowed in a PDF string") {
+            return None;
+        }
+        let start = message.find("removing `")? + "removing `".len();
+        let end = message[start..].find('\'')?;
+        Some(message[start..start + end].to_string())
+    }
 
-            // Package wrapfig Warning:
-            TokenKind::Word(w) if w.as_str() == "Package" => {
+    /// The `page.1` of `destination with the same identifier (name{page.1}) has been already used`
+    fn duplicate_destination(message: &str) -> Option<String> {
+        let start = message.find("destination with the same identifier (")?
+            + "destination with the same identifier (".len();
+        let end = message[start..].find(") has been already used")?;
+        let name = &message[start..start + end];
+        let name = name
+            .strip_prefix("name{")
+            .and_then(|n| n.strip_suffix('}'))
+            .unwrap_or(name);
+        Some(name.to_string())
+    }
+
+    /// The `Foo Sans` of `Font \TU/FooSans(0)/m/n/10=Foo Sans at 10.0pt not loadable: ...`
+    fn unloadable_font(title: &str) -> String {
+        let spec = title.split_once('=').map_or(title, |(_, spec)| spec);
+        let end = [" at ", " scaled ", " not loadable"]
+            .iter()
+            .filter_map(|s| spec.find(s))
+            .min()
+            .unwrap_or(spec.len());
+        spec[..end].trim().to_string()
+    }
+
+    /// The `Foo Sans` of the `! The font "Foo Sans" cannot be found.` line of a fontspec error
+    fn fontspec_font(message: &str) -> Option<String> {
+        let start = message.find("The font \"")? + "The font \"".len();
+        let end = message[start..].find('"')?;
+        Some(message[start..start + end].to_string())
+    }
+
+    /// Consume a `LaTeX Warning:` at the cursor if it is one that is recognized
+    fn consume_latex_warning(&mut self) -> Option<TexDiagnostic> {
+        match &self.peak(5).kind {
+            // LaTeX Warning: Reference `fig:intro' on page 3 undefined on input line 42.
+            TokenKind::Word(w) if w == "Reference" => {
+                let key = self.quoted_argument(7)?;
+                Some(self.consume_diagnostic(TexDiagnosticKind::Reference(key)))
+            }
+            // LaTeX Warning: Label(s) may have changed. Rerun to get cross-references right.
+            TokenKind::Word(w) if w == "Label" && self.peak(6).kind == TokenKind::LeftParen => {
+                Some(self.consume_diagnostic(TexDiagnosticKind::Rerun))
+            }
+            // LaTeX Warning: Label `sec:intro' multiply defined.
+            TokenKind::Word(w) if w == "Label" => {
+                let key = self.quoted_argument(7)?;
+                Some(self.consume_diagnostic(TexDiagnosticKind::MultiplyDefinedLabel(key)))
+            }
+            // Any other warning of the LaTeX kernel, like `Float too large for page`
+            _ => {
+                let mut title = String::new();
+                let mut offset = 5;
+                loop {
+                    match &self.peak(offset).kind {
+                        TokenKind::Newline | TokenKind::EOF => break,
+                        kind => title += &kind.to_string(),
+                    }
+                    offset += 1;
+                }
+                let title = title.trim().to_string();
+                Some(self.consume_diagnostic(TexDiagnosticKind::LatexWarning(title)))
+            }
+        }
+    }
+
+    fn consume_diag_if_diag(&mut self) -> Option<TexDiagnostic> {
+        // Must be at newline, except for pdfTeX errors which are often written right after a
+        // page marker
+        let at_pdftex_error = self.current().kind == TokenKind::ExclamationMark
+            && self.peak(1).kind == TokenKind::Word("pdfTeX".into());
+        if self.peak(-1).kind != TokenKind::Newline && !at_pdftex_error {
+            return None;
+        }
+
+        match &self.current().kind {
+            // pdfTeX warning:
+            TokenKind::Word(w) if w == "pdfTeX" => {
+                if self.peak(2).kind != TokenKind::Word("warning".into()) {
+                    return None;
+                }
+                // pdfTeX warning (ext4): destination with the same identifier (name{page.1}) ...
+                if self.peak(4).kind == TokenKind::LeftParen {
+                    let diagnostic = self.consume_diagnostic(TexDiagnosticKind::PdfLatex);
+                    return match Self::duplicate_destination(&diagnostic.message) {
+                        Some(name) => Some(
+                            diagnostic.with_kind(TexDiagnosticKind::DuplicateDestination(name)),
+                        ),
+                        None => Some(diagnostic),
+                    };
+                }
+                if self.peak(3).kind != TokenKind::Punctuation(':') {
+                    return None;
+                }
+                Some(self.consume_diagnostic(TexDiagnosticKind::PdfLatex))
+            }
+
+            // LaTeX Font Warning:
+            TokenKind::Word(w) if w == "LaTeX" => {
+                if self.peak(2).kind == TokenKind::Word("Warning".into())
+                    && self.peak(3).kind == TokenKind::Punctuation(':')
+                {
+                    return self.consume_latex_warning();
+                }
+                if self.peak(2).kind == TokenKind::Word("Info".into())
+                    && self.peak(3).kind == TokenKind::Punctuation(':')
+                {
+                    return Some(self.consume_diagnostic(TexDiagnosticKind::LatexInfo));
+                }
+                if self.peak(2).kind != TokenKind::Word("Font".into()) {
+                    return None;
+                }
+                if self.peak(4).kind != TokenKind::Word("Warning".into()) {
+                    return None;
+                }
+                if self.peak(5).kind != TokenKind::Punctuation(':') {
+                    return None;
+                }
+                Some(self.consume_diagnostic(TexDiagnosticKind::Font {
+                    requested: String::new(),
+                    substituted: String::new(),
+                }))
+            }
+
+            // Overfull \hbox or \vbox
+            TokenKind::Word(w) if w == "Overfull" => {
+                if self.peak(2).kind != TokenKind::Punctuation('\\') {
+                    return None;
+                }
+                let kind = match &self.peak(3).kind {
+                    TokenKind::Word(b) if b == "hbox" => TexDiagnosticKind::OverfullHbox {
+                        overflow_pt: 0.0,
+                        lines: None,
+                    },
+                    TokenKind::Word(b) if b == "vbox" => TexDiagnosticKind::OverfullVbox {
+                        overflow_pt: 0.0,
+                        lines: None,
+                    },
+                    _ => return None,
+                };
+                Some(self.consume_diagnostic(kind))
+            }
+
+            // Underfull \hbox or \vbox
+            TokenKind::Word(w) if w == "Underfull" => {
+                if self.peak(2).kind != TokenKind::Punctuation('\\') {
+                    return None;
+                }
+                let kind = match &self.peak(3).kind {
+                    TokenKind::Word(b) if b == "hbox" => TexDiagnosticKind::UnderfullHbox {
+                        badness: 0,
+                        lines: None,
+                    },
+                    TokenKind::Word(b) if b == "vbox" => TexDiagnosticKind::UnderfullVbox {
+                        badness: 0,
+                        lines: None,
+                    },
+                    _ => return None,
+                };
+                Some(self.consume_diagnostic(kind))
+            }
+
+            // Package wrapfig Warning:, Package babel Error: or Package hyperref Info:, and the Module luaotfload Warning:
+            // of packages written in Lua
+            TokenKind::Word(w)
+                if w == "Package" || (w == "Module" && self.engine == Engine::LuaTex) =>
+            {
                 let package_name;
                 if let TokenKind::Word(name) = &self.peak(2).kind {
                     package_name = name.clone();
                 } else {
                     return None;
                 }
-                if self.peak(4).kind != TokenKind::Word("Warning".to_string()) {
+                if self.peak(5).kind != TokenKind::Punctuation(':') {
                     return None;
                 }
+                let kind = match &self.peak(4).kind {
+                    TokenKind::Word(l) if l == "Warning" => {
+                        TexDiagnosticKind::Package(package_name.to_string())
+                    }
+                    TokenKind::Word(l) if l == "Error" => {
+                        TexDiagnosticKind::PackageError(package_name.to_string())
+                    }
+                    TokenKind::Word(l) if l == "Info" => {
+                        TexDiagnosticKind::PackageInfo(package_name.to_string())
+                    }
+                    _ => return None,
+                };
+                let is_hyperref = kind == TexDiagnosticKind::Package("hyperref".to_string());
+                let diagnostic = self.consume_diagnostic(kind);
+                match Self::pdf_string_token(&diagnostic.message) {
+                    Some(token) if is_hyperref => {
+                        Some(diagnostic.with_kind(TexDiagnosticKind::PdfStringToken(token)))
+                    }
+                    _ => Some(diagnostic),
+                }
+            }
+
+            // Missing character: There is no ⌘ (U+2318) in font [lmroman10-regular]:mapping=tex-text;!
+            TokenKind::Word(w) if w == "Missing" => {
+                if self.peak(2).kind != TokenKind::Word("character".into())
+                    || self.peak(3).kind != TokenKind::Punctuation(':')
+                {
+                    return None;
+                }
+                let diagnostic =
+                    self.consume_diagnostic(TexDiagnosticKind::MissingCharacter(String::new()));
+                let character = Self::missing_character(&diagnostic.message).unwrap_or_default();
+                Some(diagnostic.with_kind(TexDiagnosticKind::MissingCharacter(character)))
+            }
+
+            // ** WARNING ** and ** ERROR ** from xdvipdfmx
+            TokenKind::Punctuation('*') if self.engine == Engine::XeTex => {
+                if self.peak(1).kind != TokenKind::Punctuation('*') {
+                    return None;
+                }
+                let kind = match &self.peak(3).kind {
+                    TokenKind::Word(l) if l == "WARNING" => TexDiagnosticKind::Xdvipdfmx,
+                    TokenKind::Word(l) if l == "ERROR" => TexDiagnosticKind::XdvipdfmxError,
+                    _ => return None,
+                };
+                Some(self.consume_diagnostic(kind))
+            }
+
+            // xdvipdfmx:warning: and xdvipdfmx:fatal: from newer versions of xdvipdfmx
+            TokenKind::Word(w) if w == "xdvipdfmx" && self.engine == Engine::XeTex => {
+                if self.peak(1).kind != TokenKind::Punctuation(':')
+                    || self.peak(3).kind != TokenKind::Punctuation(':')
+                {
+                    return None;
+                }
+                let kind = match &self.peak(2).kind {
+                    TokenKind::Word(l) if l == "warning" => TexDiagnosticKind::Xdvipdfmx,
+                    TokenKind::Word(l) if l == "error" || l == "fatal" => {
+                        TexDiagnosticKind::XdvipdfmxError
+                    }
+                    _ => return None,
+                };
+                Some(self.consume_diagnostic(kind))
+            }
+
+            // Class book Warning:, Class memoir Error: or Class scrartcl Info:
+            TokenKind::Word(w) if w == "Class" => {
+                let class_name = match &self.peak(2).kind {
+                    TokenKind::Word(name) => name.to_string(),
+                    _ => return None,
+                };
                 if self.peak(5).kind != TokenKind::Punctuation(':') {
                     return None;
                 }
-                Some(TexDiagnostic {
-                    kind: TexDiagnosticKind::Package(package_name),
-                    message: self.consume_diagnostic_message(),
-                })
+                let kind = match &self.peak(4).kind {
+                    TokenKind::Word(l) if l == "Warning" => TexDiagnosticKind::Class(class_name),
+                    TokenKind::Word(l) if l == "Error" => TexDiagnosticKind::ClassError(class_name),
+                    TokenKind::Word(l) if l == "Info" => TexDiagnosticKind::ClassInfo(class_name),
+                    _ => return None,
+                };
+                Some(self.consume_diagnostic(kind))
             }
 
             // GenericError
@@ -293,41 +1563,118 @@ impl Parser {
                     }
                     self.consume();
                 }
-                let title: String = self.tokens[err_start + 2..self.cursor]
+                let title: String = self.tokens[err_start + 1..self.cursor]
                     .iter()
                     .map(|t| t.to_string())
                     .collect();
+                let kind = self.error_kind(title.trim().to_string());
 
                 // Reset cursor to get full diagnostic
                 self.cursor = err_start;
 
-                // Look back for start of error message
-                loop {
-                    match &self.peak(-1).kind {
-                        TokenKind::Newline if self.peak(-2).kind == TokenKind::Newline => break,
-                        TokenKind::EOF => break,
-                        TokenKind::Path(_) => break,
-                        _ => self.cursor -= 1,
+                // Look back for start of error message. pdfTeX errors stand on their own.
+                if !matches!(kind, TexDiagnosticKind::PdfTexError(_)) {
+                    while self.cursor > 0 {
+                        match &self.peak(-1).kind {
+                            TokenKind::Newline if self.peak(-2).kind == TokenKind::Newline => break,
+                            TokenKind::EOF => break,
+                            TokenKind::Path(_) => break,
+                            _ => self.cursor -= 1,
+                        }
                     }
                 }
 
-                Some(TexDiagnostic {
-                    kind: TexDiagnosticKind::GenericError(title),
-                    message: self.consume_diagnostic_message(),
-                })
+                let mut diagnostic = self.consume_diagnostic(kind.clone());
+                // A `)` before the `!` ends the message early. The error must still be consumed,
+                // or the parser would find it again and never get past it.
+                if self.cursor <= err_start {
+                    self.cursor = err_start;
+                    diagnostic = self.consume_diagnostic(kind);
+                }
+                Some(Self::refine_error(diagnostic))
             }
 
             _ => None,
         }
     }
 
+    /// Find diagnostics in tokens that are part of a longer log. `stack` holds the files that
+    /// are open at the start of the tokens, and is left with the files that are still open at the
+    /// end. Root files that are closed in the tokens are added to `completed`.
+    pub(crate) fn scan_diagnostics(
+        &mut self,
+        stack: &mut Vec<OpenFile>,
+        completed: &mut Vec<Node>,
+    ) -> Vec<TracedTexDiagnostic> {
+        let mut diagnostics = vec![];
+        loop {
+            if let Some(mut diag) = self.consume_diag_if_diag() {
+                diag.source_file = stack.last().map(|f| PathBuf::from(&f.node.file));
+                let call_stack = stack.iter().map(|f| PathBuf::from(&f.node.file)).collect();
+                if let Some(f) = stack.last_mut() {
+                    f.node.diagnostics.push(diag.clone());
+                }
+                diagnostics.push(TracedTexDiagnostic::new(call_stack, diag));
+            }
+
+            self.track_page();
+            let token = self.current();
+            match &token.kind {
+                TokenKind::LeftParen => {
+                    let path = match &self.peak(1).kind {
+                        TokenKind::Path(p) if stack.len() < MAX_FILE_DEPTH => Some(p),
+                        _ => None,
+                    };
+                    if let Some(p) = path {
+                        let mut node = Node::new(p.to_string(), vec![]);
+                        node.span.start = token.span.start;
+                        stack.push(OpenFile {
+                            node,
+                            unclosed_text_parens: 0,
+                        });
+                    } else if let Some(f) = stack.last_mut() {
+                        f.node.messages += "(";
+                        f.unclosed_text_parens += 1;
+                    }
+                }
+                TokenKind::RightParen => match stack.last_mut() {
+                    Some(f) if f.unclosed_text_parens > 0 => {
+                        f.node.messages += ")";
+                        f.unclosed_text_parens -= 1;
+                    }
+                    Some(_) => {
+                        let mut node = stack.pop().expect("The stack is not empty").node;
+                        node.span.end = token.span.start;
+                        match stack.last_mut() {
+                            Some(parent) => parent.node.calls.push(node),
+                            None => completed.push(node),
+                        }
+                    }
+                    None => {}
+                },
+                TokenKind::EOF => break,
+                kind => {
+                    if let Some(f) = stack.last_mut() {
+                        // Parentheses in messages do not span paragraphs
+                        if *kind == TokenKind::Newline && self.peak(1).kind == TokenKind::Newline {
+                            f.unclosed_text_parens = 0;
+                        }
+                        f.node.messages += &kind.to_string();
+                    }
+                }
+            }
+            self.consume();
+        }
+        diagnostics
+    }
+
     fn parse_node(&mut self) -> Node {
-        let pos = self.current().pos;
+        let start = self.current().span.start;
 
         assert!(self.consume().has_kind(&TokenKind::LeftParen));
 
         let file = match &self.current().kind {
-            TokenKind::Path(p) => p.clone(),
+            TokenKind::Path(p) => p.to_string(),
             _ => "no path...".to_string(),
         };
 
@@ -338,15 +1685,27 @@ impl Parser {
         let mut unclosed_text_parens: usize = 0;
 
         loop {
-            if let Some(diag) = self.consume_diag_if_diag() {
+            if let Some(mut diag) = self.consume_diag_if_diag() {
+                diag.source_file = Some(PathBuf::from(&file));
                 diagnostics.push(diag);
             }
 
+            self.track_page();
             match &self.current().kind {
                 TokenKind::LeftParen => {
-                    if let TokenKind::Path(_) = self.peak(1).kind {
-                        calls.push(self.parse_node())
+                    let opens_file = matches!(self.peak(1).kind, TokenKind::Path(_));
+                    if opens_file && self.depth < MAX_FILE_DEPTH {
+                        self.depth += 1;
+                        calls.push(self.parse_node());
+                        self.depth -= 1;
                     } else {
+                        if opens_file {
+                            self.parse_warnings.push(format!(
+                                "files nested more than {} deep near line {}",
+                                MAX_FILE_DEPTH,
+                                self.current().line
+                            ));
+                        }
                         messages += "(";
                         unclosed_text_parens += 1;
                         self.consume();
@@ -362,23 +1721,22 @@ impl Parser {
                         return Node {
                             file,
                             messages,
-                            start_pos: pos,
-                            end_pos: end_token.pos,
+                            span: Span::new(start, end_token.span.start),
                             calls,
                             diagnostics,
                         };
                     }
                 }
                 TokenKind::Path(p) => {
-                    messages += p.as_str();
+                    messages += p;
                     self.consume();
                 }
                 TokenKind::Word(w) => {
-                    messages += w.as_str();
+                    messages += w;
                     self.consume();
                 }
                 TokenKind::Whitespace(w) => {
-                    messages += w.as_str();
+                    messages += w;
                     self.consume();
                 }
                 TokenKind::ExclamationMark => {
@@ -390,16 +1748,47 @@ impl Parser {
                     self.consume();
                 }
                 TokenKind::Newline => {
+                    // Parentheses in messages do not span paragraphs, so a `(` that is still
+                    // open at a blank line was never closed
+                    if unclosed_text_parens > 0 && self.peak(1).kind == TokenKind::Newline {
+                        self.parse_warnings.push(format!(
+                            "unbalanced '(' in {} near line {}",
+                            file,
+                            self.current().line
+                        ));
+                        unclosed_text_parens = 0;
+                    }
                     messages += "\n";
                     self.consume();
                 }
-                TokenKind::EOF => panic!("EOF in the middle of {}", file),
+                TokenKind::EOF => {
+                    self.parse_warnings
+                        .push(format!("{} is not closed before the end of the log", file));
+                    return Node {
+                        file,
+                        messages,
+                        span: Span::new(start, self.current().span.start),
+                        calls,
+                        diagnostics,
+                    };
+                }
             }
         }
     }
 
-    /// Parse source text to `Log`
-    pub fn parse(&mut self, source: SourceText) -> Log {
+    /// Parse source text of a single run to `Log`
+    pub fn parse(&mut self, source: SourceText) -> Result<Log> {
+        let (info, root_node) = self.parse_run(source.as_str())?;
+        let mut log = Log::new(source, root_node);
+        log.info = info;
+        log.engine = self.engine;
+        log.parse_warnings = std::mem::take(&mut self.parse_warnings);
+        Ok(log)
+    }
+
+    /// Parse a single run into the text before the first file and the root node
+    fn parse_run(&mut self, text: &str) -> Result<(String, Node)> {
+        self.engine = Engine::detect(text);
         let mut info = "".to_string();
         loop {
             match &self.current().kind {
@@ -411,58 +1800,248 @@ impl Parser {
                     }
                 }
                 TokenKind::Path(p) => info += p.to_string().as_str(),
-                TokenKind::EOF => todo!(),
+                TokenKind::EOF => return Err(Error::NoRootFile),
                 kind => info += kind.to_string().as_str(),
             }
             self.consume();
         }
-        let root_node = self.parse_node();
-        Log {
-            info,
-            root_node,
-            source,
+        let mut root_node = self.parse_node();
+
+        // A stray `)` closes the root file early, so the files opened after it still belong
+        // to the document
+        while self.current().kind != TokenKind::EOF {
+            if self.current().kind == TokenKind::LeftParen
+                && matches!(self.peak(1).kind, TokenKind::Path(_))
+            {
+                let node = self.parse_node();
+                self.parse_warnings.push(format!(
+                    "{} is opened after {} is closed",
+                    node.file, root_node.file
+                ));
+                root_node.calls.push(node);
+            } else {
+                self.consume();
+            }
         }
+        Ok((info, root_node))
     }
 }
 
-pub(crate) trait Visitor {
-    fn visit_node(&mut self, node: &Node) {
-        self.do_visit_node(node)
+/// Walks the tree of files in a log, from the root file down through the files each one opens.
+/// Most analyses only implement `enter_node` and `leave_node`, which are called before and after
+/// the files opened by a node are visited. Override `visit_node` to decide whether to go into
+/// the files of a node, calling `do_visit_node` to do so.
+pub trait Visitor<'a> {
+    fn visit_node(&mut self, node: &'a Node) {
+        self.enter_node(node);
+        self.do_visit_node(node);
+        self.leave_node(node);
     }
 
-    fn do_visit_node(&mut self, node: &Node) {
+    /// Called for a node before the files it opens are visited
+    fn enter_node(&mut self, _node: &'a Node) {}
+
+    /// Called for a node after the files it opens are visited
+    fn leave_node(&mut self, _node: &'a Node) {}
+
+    /// Visit the files opened by a node
+    fn do_visit_node(&mut self, node: &'a Node) {
         for other_node in &node.calls {
             self.visit_node(other_node)
         }
     }
 }
 
+/// A `Visitor` whose steps can fail. The walk stops at the first error, which is returned.
+pub trait TryVisitor<'a> {
+    type Error;
+
+    fn try_visit_node(&mut self, node: &'a Node) -> std::result::Result<(), Self::Error> {
+        self.enter_node(node)?;
+        self.try_do_visit_node(node)?;
+        self.leave_node(node)
+    }
+
+    /// Called for a node before the files it opens are visited
+    fn enter_node(&mut self, _node: &'a Node) -> std::result::Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Called for a node after the files it opens are visited
+    fn leave_node(&mut self, _node: &'a Node) -> std::result::Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Visit the files opened by a node, stopping at the first error
+    fn try_do_visit_node(&mut self, node: &'a Node) -> std::result::Result<(), Self::Error> {
+        for other_node in &node.calls {
+            self.try_visit_node(other_node)?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints the file inclusion tree as indented lines, with the number of warnings and errors
+/// emitted in every file
+#[derive(Default)]
 pub struct Printer {
-    /// Debth in tree
+    /// Depth in tree
     level: usize,
 
-    /// Source text
-    text: SourceText,
+    /// Files nested deeper than this are left out
+    max_depth: Option<usize>,
+
+    /// Leave out files from the TeX distribution, showing the files they open in their place
+    user_files_only: bool,
+
+    /// Print a Graphviz DOT graph instead of an indented list
+    dot: bool,
+
+    /// DOT node ids of the printed files above the current one
+    parents: Vec<usize>,
+
+    /// DOT node id of the next printed file
+    next_id: usize,
+
+    /// Levels and counts of the diagnostics to count, by their span in the log. Every
+    /// diagnostic of a file is counted if not set.
+    counted: Option<HashMap<Span, (DiagnosticLevel, usize)>>,
+
+    /// The printed tree
+    output: String,
 }
 
 impl Printer {
-    pub fn new(text: SourceText) -> Self {
-        Self { text, level: 0 }
+    pub fn new() -> Self {
+        Self::default()
     }
+
+    /// Leave out files nested deeper than `max_depth`, where the root file has depth 0
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Only print files that are part of the document, and not of the TeX distribution
+    pub fn with_user_files_only(mut self, user_files_only: bool) -> Self {
+        self.user_files_only = user_files_only;
+        self
+    }
+
+    /// Print a Graphviz DOT graph, with files colored by their number of errors and warnings
+    pub fn with_dot(mut self, dot: bool) -> Self {
+        self.dot = dot;
+        self
+    }
+
+    /// Only count these diagnostics, as returned by `Log::get_diagnostics`, so that the filter
+    /// and level overrides of the log apply
+    pub fn with_diagnostics(mut self, diagnostics: &[TracedTexDiagnostic]) -> Self {
+        self.counted = Some(
+            diagnostics
+                .iter()
+                .map(|d| (d.diagnostic().span(), (d.diagnostic().level(), d.count())))
+                .collect(),
+        );
+        self
+    }
+
+    /// The tree below `root`, with one line per file
+    pub fn print(mut self, root: &Node) -> String {
+        if self.dot {
+            self.output += "digraph inclusions {\n    node [shape=box, style=filled];\n";
+        }
+        self.visit_node(root);
+        if self.dot {
+            self.output += "}\n";
+        }
+        self.output
+    }
+
+    /// Fill color of a file in the DOT graph. Darker colors mean more diagnostics.
+    fn dot_color(warnings: usize, errors: usize) -> &'static str {
+        match (warnings, errors) {
+            (0, 0) => "white",
+            (1..=2, 0) => "lightyellow",
+            (3..=9, 0) => "yellow",
+            (_, 0) => "orange",
+            (_, 1..=2) => "lightpink",
+            (_, 3..=9) => "salmon",
+            _ => "red",
+        }
+    }
+}
+
+/// Collects the nodes of a tree with their depth
+struct Walker<'a> {
+    nodes: Vec<(usize, &'a Node)>,
+    depth: usize,
 }
 
-impl Visitor for Printer {
+impl<'a> Visitor<'a> for Walker<'a> {
+    fn enter_node(&mut self, node: &'a Node) {
+        self.nodes.push((self.depth, node));
+        self.depth += 1;
+    }
+
+    fn leave_node(&mut self, _node: &'a Node) {
+        self.depth -= 1;
+    }
+}
+
+impl Visitor<'_> for Printer {
     fn visit_node(&mut self, node: &Node) {
-        println!(
-            "{}{:?} at {:?} - {:?}",
-            "  ".repeat(self.level),
-            node.file,
-            self.text.row_col(node.start_pos),
-            self.text.row_col(node.end_pos),
-        );
+        if self.user_files_only && !node.is_user_file() {
+            self.do_visit_node(node);
+            return;
+        }
+        if self.max_depth.is_some_and(|max| self.level > max) {
+            return;
+        }
+
+        let (warnings, errors) = match &self.counted {
+            Some(counted) => node
+                .diagnostics()
+                .iter()
+                .filter_map(|d| counted.get(&d.span))
+                .fold((0, 0), |(w, e), (level, n)| match level {
+                    DiagnosticLevel::Warning => (w + n, e),
+                    DiagnosticLevel::Error => (w, e + n),
+                    DiagnosticLevel::Info => (w, e),
+                }),
+            None => (node.warnings().len(), node.errors().len()),
+        };
+        let counts = match (warnings, errors) {
+            (0, 0) => String::new(),
+            (w, 0) => format!(" ({} warning{})", w, if w == 1 { "" } else { "s" }),
+            (0, e) => format!(" ({} error{})", e, if e == 1 { "" } else { "s" }),
+            (w, e) => format!(" ({} warnings, {} errors)", w, e),
+        };
+        if self.dot {
+            let id = self.next_id;
+            self.next_id += 1;
+            let label = format!("{}{}", node.file, counts)
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"");
+            self.output += &format!(
+                "    n{} [label=\"{}\", fillcolor={}];\n",
+                id,
+                label,
+                Self::dot_color(warnings, errors)
+            );
+            if let Some(parent) = self.parents.last() {
+                self.output += &format!("    n{} -> n{};\n", parent, id);
+            }
+            self.parents.push(id);
+        } else {
+            self.output += &format!("{}{}{}\n", "  ".repeat(self.level), node.file, counts);
+        }
         self.level += 1;
         self.do_visit_node(node);
         self.level -= 1;
+        if self.dot {
+            self.parents.pop();
+        }
     }
 }
 
@@ -471,13 +2050,632 @@ mod tests {
     use std::path::PathBuf;
 
     use super::*;
+    use crate::filter::DiagnosticFilter;
 
     #[test]
     fn trace() {
         let source = SourceText::from_file("./test/main.log").unwrap();
-        let log = parse_source(source.clone());
+        let log = parse_source(source.clone()).unwrap();
         let trace = log.trace_at(source.index(7, 1));
         dbg!(&trace);
         assert_eq!(trace, vec![PathBuf::from("./main.tex")])
     }
+
+    #[test]
+    fn source_location() {
+        let location = SourceLocation::from_message(
+            "! Undefined control sequence.\nl.6 \\dtae\n{December 2004}",
+        )
+        .unwrap();
+        assert_eq!(location.line, 6);
+        assert_eq!(location.columns, Some((1, 6)));
+
+        let location =
+            SourceLocation::from_message("! Too many }'s.\nl.6 \\date December 2004}").unwrap();
+        assert_eq!(location.columns, Some((20, 21)));
+
+        let location = SourceLocation::from_message("l.12 ...very long line \\foo").unwrap();
+        assert_eq!(location.line, 12);
+        assert_eq!(location.columns, None);
+    }
+
+    #[test]
+    fn box_metrics() {
+        let d = TexDiagnostic::new(
+            TexDiagnosticKind::OverfullHbox {
+                overflow_pt: 0.0,
+                lines: None,
+            },
+            "Overfull \\hbox (18.61958pt too wide) in paragraph at lines 66--66".to_string(),
+            0,
+        );
+        assert_eq!(d.overflow_pt(), Some(18.61958));
+        assert_eq!(d.badness(), None);
+
+        let d = TexDiagnostic::new(
+            TexDiagnosticKind::UnderfullHbox {
+                badness: 0,
+                lines: None,
+            },
+            "Underfull \\hbox (badness 10000) in paragraph at lines 12--14".to_string(),
+            0,
+        );
+        assert_eq!(
+            d.kind,
+            TexDiagnosticKind::UnderfullHbox {
+                badness: 10000,
+                lines: Some((12, 14))
+            }
+        );
+        assert_eq!(d.overflow_pt(), None);
+        assert_eq!(d.source_line(), Some(12));
+
+        let d = TexDiagnostic::new(
+            TexDiagnosticKind::Font {
+                requested: String::new(),
+                substituted: String::new(),
+            },
+            "LaTeX Font Warning: Font shape `OT1/cmr/m/scit' undefined\n(Font)              using `OT1/cmr/m/sc' instead on input line 3.".to_string(),
+            0,
+        );
+        assert_eq!(
+            d.kind,
+            TexDiagnosticKind::Font {
+                requested: "OT1/cmr/m/scit".to_string(),
+                substituted: "OT1/cmr/m/sc".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn classes() {
+        let text = "(./main.tex\n\nClass scrartcl Warning: Usage of package `fancyhdr'\n(scrartcl)              together with a KOMA-Script class is not recommended.\n\n\n! Class memoir Error: Font command \\rm is not defined.\n\nSee the memoir class documentation for explanation.\n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 2);
+        assert_eq!(
+            ds[0].diagnostic().kind,
+            TexDiagnosticKind::Class("scrartcl".to_string())
+        );
+        assert_eq!(
+            ds[1].diagnostic().kind,
+            TexDiagnosticKind::ClassError("memoir".to_string())
+        );
+        assert_eq!(ds[1].diagnostic().level(), DiagnosticLevel::Error);
+    }
+
+    #[test]
+    fn package_errors() {
+        let text = "(./main.tex\n\n! Package inputenc Error: Unicode character \u{2212} (U+2212)\n(inputenc)                not set up for use with LaTeX.\n\nSee the inputenc package documentation for explanation.\nType  H <return>  for immediate help.\n ...                                              \n                                                  \nl.42 $a \u{2212}\n               b$\n\nSome other text\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 1);
+        let d = ds[0].diagnostic();
+        assert_eq!(
+            d.kind,
+            TexDiagnosticKind::PackageError("inputenc".to_string())
+        );
+        assert_eq!(d.level(), DiagnosticLevel::Error);
+        assert!(d.message.contains("See the inputenc package documentation"));
+        assert!(!d.message.contains("Some other text"));
+        assert_eq!(d.source_line(), Some(42));
+    }
+
+    #[test]
+    fn pdftex_errors() {
+        let text = "(./main.tex\n\n[3 <./figures/plot.png>]!pdfTeX error: pdflatex (file ./figures/missing.pdf): cannot find image file\n ==> Fatal error occurred, no output PDF file produced!\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 1);
+        assert_eq!(
+            ds[0].diagnostic().kind,
+            TexDiagnosticKind::PdfTexError(
+                "pdflatex (file ./figures/missing.pdf): cannot find image file".to_string()
+            )
+        );
+        assert!(ds[0].diagnostic().message.starts_with("!pdfTeX error"));
+    }
+
+    #[test]
+    fn missing_fonts() {
+        let text = "(./main.tex\n\n! fontspec error: \"font-not-found\"\n! \n! The font \"Foo Sans\" cannot be found.\n! \n! See the fontspec documentation for further information.\n\n\n! Font \\TU/BarSerif(0)/m/n/10=Bar Serif at 10.0pt not loadable: Metric (TFM) file or installed font not found.\n<to be read again> \n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let kinds: Vec<_> = log
+            .get_diagnostics()
+            .iter()
+            .map(|d| d.diagnostic().kind.clone())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TexDiagnosticKind::MissingFont("Foo Sans".to_string()),
+                TexDiagnosticKind::MissingFont("Bar Serif".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn hyperref() {
+        let text = "(./main.tex\n\nPackage hyperref Warning: Token not allowed in a PDF string (Unicode):\n(hyperref)                removing `\\textbf' on input line 12.\n\n\npdfTeX warning (ext4): destination with the same identifier (name{page.1}) has been already used, duplicate ignored\n\n\nPackage hyperref Warning: Rerun to get /PageLabels entry.\n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let kinds: Vec<_> = log
+            .get_diagnostics()
+            .iter()
+            .map(|d| d.diagnostic().kind.clone())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TexDiagnosticKind::PdfStringToken("\\textbf".to_string()),
+                TexDiagnosticKind::DuplicateDestination("page.1".to_string()),
+                TexDiagnosticKind::Package("hyperref".to_string()),
+            ]
+        );
+        assert_eq!(kinds[0].package(), Some("hyperref"));
+    }
+
+    #[test]
+    fn runaway_arguments() {
+        let text = "(./main.tex\n\nRunaway argument?\n{December 2004 \\maketitle\n! Paragraph ended before \\date was complete.\n<to be read again>\n\\par\nl.8\n\n\nRunaway argument?\n{some bold text\n! File ended while scanning use of \\textbf.\n<inserted text>\n\\par\n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 2);
+        assert_eq!(
+            ds[0].diagnostic().kind,
+            TexDiagnosticKind::Runaway("\\date".to_string())
+        );
+        assert!(ds[0].diagnostic().message.starts_with("Runaway argument?"));
+        assert_eq!(
+            ds[1].diagnostic().kind,
+            TexDiagnosticKind::Runaway("\\textbf".to_string())
+        );
+    }
+
+    #[test]
+    fn fatal_errors() {
+        let text = "(./main.tex\n\n! Emergency stop.\n<*> main.tex\n\n*** (job aborted, no legal \\end found)\n\n\n!  ==> Fatal error occurred, no output PDF file produced!\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 2);
+        assert!(ds
+            .iter()
+            .all(|d| d.diagnostic().kind == TexDiagnosticKind::Fatal));
+        assert!(!log.compilation_succeeded());
+
+        let log = parse_source(SourceText::from_file("./test/main.log").unwrap()).unwrap();
+        assert!(log.compilation_succeeded());
+    }
+
+    #[test]
+    fn undefined_control_sequences() {
+        let text = "(./main.tex\n\n! Undefined control sequence.\nl.6 \\dtae\n          {December 2004}\n\n\n! Undefined control sequence.\n<argument> \\foo\n               bar\nl.12 \\section{\\foo bar}\n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let ds = log.get_diagnostics();
+        let kinds: Vec<_> = ds.iter().map(|d| d.diagnostic().kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TexDiagnosticKind::UndefinedControlSequence("\\dtae".to_string()),
+                TexDiagnosticKind::UndefinedControlSequence("\\foo".to_string()),
+            ]
+        );
+        assert_eq!(
+            ds[0].diagnostic().suggestion(),
+            Some("did you mean `\\date`?")
+        );
+    }
+
+    #[test]
+    fn full_line_before_error() {
+        // A line of exactly `MAX_PRINT_LINE` characters is not joined to the error after it
+        let text = format!(
+            "(./main.tex\n{}\n! Undefined control sequence.\nl.3 \\foo\n\n)",
+            "a".repeat(lexer::MAX_PRINT_LINE)
+        );
+        let log = parse_source(SourceText::new(text)).unwrap();
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 1);
+        assert_eq!(
+            ds[0].diagnostic().kind,
+            TexDiagnosticKind::UndefinedControlSequence("\\foo".to_string())
+        );
+    }
+
+    #[test]
+    fn latex_warnings() {
+        let text = "(./main.tex\n\nLaTeX Warning: `h' float specifier changed to `ht'.\n\n\nLaTeX Warning: Marginpar on page 2 moved.\n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let kinds: Vec<_> = log
+            .get_diagnostics()
+            .iter()
+            .map(|d| d.diagnostic().kind.clone())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TexDiagnosticKind::LatexWarning("`h' float specifier changed to `ht'.".to_string()),
+                TexDiagnosticKind::LatexWarning("Marginpar on page 2 moved.".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn infos() {
+        let text = "(./main.tex\nLaTeX Info: Redefining \\setlength on input line 80.\nPackage hyperref Info: Option `colorlinks' set `true' on input line 12.\n(hyperref)                Link coloring ON on input line 12.\n(./chapter.tex\n\nLaTeX Warning: Reference `a' on page 1 undefined on input line 3.\n\n))";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        assert_eq!(log.get_diagnostics().len(), 1);
+
+        let infos = log.root_node().diagnostics();
+        assert_eq!(infos.len(), 2);
+        assert_eq!(infos[0].kind, TexDiagnosticKind::LatexInfo);
+        assert_eq!(infos[1].level(), DiagnosticLevel::Info);
+        assert!(infos[1]
+            .message
+            .ends_with("Link coloring ON on input line 12."));
+        assert_eq!(log.root_node().calls.len(), 1);
+    }
+
+    #[test]
+    fn capacity_exceeded() {
+        let text = "(./main.tex\n\n! TeX capacity exceeded, sorry [input stack size=5000].\n\\foo ->\\foo \n\nl.4 \\foo\n\nNo pages of output.\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let kind = log.get_diagnostics()[0].diagnostic().kind.clone();
+        assert_eq!(
+            kind,
+            TexDiagnosticKind::CapacityExceeded("input stack size".to_string())
+        );
+        assert!(kind.hint().unwrap().contains("infinite loop"));
+
+        let kind = TexDiagnosticKind::CapacityExceeded("main memory size".to_string());
+        assert!(kind.hint().unwrap().contains("`main_memory`"));
+    }
+
+    #[test]
+    fn missing_files() {
+        let text = "(./main.tex\n\n! LaTeX Error: File `paralisy.sty' not found.\n\nType X to quit or <RETURN> to proceed,\nor enter new name. (Default extension: sty)\n\nEnter file name: \n! Emergency stop.\n<read > \n\nl.3 \\usepackage\n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let kind = log.get_diagnostics()[0].diagnostic().kind.clone();
+        assert_eq!(
+            kind,
+            TexDiagnosticKind::MissingFile("paralisy.sty".to_string())
+        );
+        assert!(kind.hint().unwrap().contains("tlmgr install paralisy"));
+
+        let kind = Parser::missing_file("I can't find file `chapter1'.");
+        assert_eq!(kind, Some("chapter1".to_string()));
+    }
+
+    #[test]
+    fn luatex() {
+        let text = "This is LuaHBTeX, Version 1.15.0 (TeX Live 2022)  1 MAR 2023 10:00\n(./main.tex\n\nModule luaotfload Warning: Font \"Foo\" not found.\n\n\n! LuaTeX error [\\directlua]:1: attempt to call a nil value (global 'foo').\nl.5 \\directlua{foo()}\n\n)\nHere is how much of LuaTeX's memory you used:\n 1049 words of node memory still in use:\n   6 hlist, 1 vlist, 1 rule, 2 glue, 3 kern, 1 glyph, 10 attribute nodes\n   avail lists: 2:10,3:3,4:1,5:21\n";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        assert_eq!(log.engine(), Engine::LuaTex);
+        assert_eq!(log.meta().node_memory, Some(1049));
+        let kinds: Vec<_> = log
+            .get_diagnostics()
+            .iter()
+            .map(|d| d.diagnostic().kind.clone())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TexDiagnosticKind::Package("luaotfload".to_string()),
+                TexDiagnosticKind::LuaError(
+                    "[\\directlua]:1: attempt to call a nil value (global 'foo').".to_string()
+                ),
+            ]
+        );
+        let log = parse_source(SourceText::from_file("./test/main.log").unwrap()).unwrap();
+        assert_eq!(log.engine(), Engine::PdfTex);
+        assert_eq!(log.output_file(), Some(PathBuf::from("main.pdf")));
+    }
+
+    #[test]
+    fn xetex() {
+        let text = "This is XeTeX, Version 3.141592653-2.6-0.999994 (TeX Live 2022)  1 MAR 2023 10:00\n(./main.tex\n\nMissing character: There is no \u{2318} (U+2318) in font [lmroman10-regular]:mapping=tex-text;!\n\n\n** WARNING ** Could not locate a virtual/physical font for TFM \"foo\".\n\n\nxdvipdfmx:fatal: Cannot proceed without the font: foo\n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        assert_eq!(log.engine(), Engine::XeTex);
+        assert_eq!(log.output_file(), None);
+        let kinds: Vec<_> = log
+            .get_diagnostics()
+            .iter()
+            .map(|d| d.diagnostic().kind.clone())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TexDiagnosticKind::MissingCharacter("\u{2318}".to_string()),
+                TexDiagnosticKind::Xdvipdfmx,
+                TexDiagnosticKind::XdvipdfmxError,
+            ]
+        );
+    }
+
+    #[test]
+    fn vbox() {
+        let text = "(./main.tex\n\nUnderfull \\vbox (badness 10000) has occurred while \\output is active []\n\n\nOverfull \\vbox (12.0pt too high) detected at line 20\n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 2);
+        assert_eq!(
+            ds[0].diagnostic().kind,
+            TexDiagnosticKind::UnderfullVbox {
+                badness: 10000,
+                lines: None
+            }
+        );
+        assert_eq!(
+            ds[1].diagnostic().kind,
+            TexDiagnosticKind::OverfullVbox {
+                overflow_pt: 12.0,
+                lines: Some((20, 20))
+            }
+        );
+        assert_eq!(ds[1].diagnostic().source_line(), Some(20));
+    }
+
+    #[test]
+    fn references() {
+        let text = "(./main.tex\n\nLaTeX Warning: Reference `fig:my-plot' on page 3 undefined on input line 42.\n\n)";
+        let source = SourceText::new(text.to_string());
+        let log = parse_source(source.clone()).unwrap();
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 1);
+        let d = ds[0].diagnostic();
+        assert_eq!(
+            source.span_text(d.span()),
+            "LaTeX Warning: Reference `fig:my-plot' on page 3 undefined on input line 42.\n"
+        );
+        assert_eq!(
+            d.kind,
+            TexDiagnosticKind::Reference("fig:my-plot".to_string())
+        );
+        assert_eq!(d.source_line(), Some(42));
+    }
+
+    #[test]
+    fn rerun() {
+        let text = "(./main.tex\n\nLaTeX Warning: Label(s) may have changed. Rerun to get cross-references right.\n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        assert_eq!(
+            log.get_diagnostics()[0].diagnostic().kind,
+            TexDiagnosticKind::Rerun
+        );
+        assert!(log.needs_rerun());
+
+        let text = "(./main.tex\n\nPackage rerunfilecheck Warning: File `main.out' has changed.\n(rerunfilecheck)                Rerun to get outlines right\n\n)";
+        assert!(parse_source(SourceText::new(text.to_string()))
+            .unwrap()
+            .needs_rerun());
+
+        let log = parse_source(SourceText::from_file("./test/main.log").unwrap()).unwrap();
+        assert!(!log.needs_rerun());
+    }
+
+    #[test]
+    fn multiply_defined_labels() {
+        let text = "(./main.tex\n\nLaTeX Warning: Label `sec:intro' multiply defined.\n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 1);
+        assert_eq!(
+            ds[0].diagnostic().kind,
+            TexDiagnosticKind::MultiplyDefinedLabel("sec:intro".to_string())
+        );
+    }
+
+    #[test]
+    fn pages() {
+        let text = "(./main.tex\n\nOverfull \\hbox (1.0pt too wide) in paragraph at lines 3--3\n\n[1\n{/usr/share/pdftex.map}] [2 <./fig.png>]\nOverfull \\hbox (2.0pt too wide) in paragraph at lines 9--9\n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let pages: Vec<_> = log
+            .get_diagnostics()
+            .iter()
+            .map(|d| d.diagnostic().page())
+            .collect();
+        assert_eq!(pages, vec![Some(1), Some(3)]);
+    }
+
+    #[test]
+    fn source_line() {
+        let d = TexDiagnostic::new(
+            TexDiagnosticKind::Package("wrapfig".to_string()),
+            "Package wrapfig Warning: wrapfigure used inside a conflicting environment on input line 35.".to_string(),
+            0,
+        );
+        assert_eq!(d.source_line(), Some(35));
+
+        let source = SourceText::from_file("./test/main.log").unwrap();
+        let log = parse_source(source).unwrap();
+        assert!(log
+            .get_diagnostics()
+            .iter()
+            .all(|d| d.diagnostic().source_file().is_some()));
+    }
+
+    #[test]
+    fn runs() {
+        let run = |line| {
+            format!("This is pdfTeX, Version 3.141592653-2.6-1.40.24 (TeX Live 2022)\n(./main.tex\n\nLaTeX Warning: Reference `a' on page 1 undefined on input line {}.\n\n)\n", line)
+        };
+        let text = format!(
+            "Latexmk: Run number 1 of rule 'pdflatex'\n{}{}",
+            run(3),
+            run(7)
+        );
+        assert_eq!(run_starts(&text).len(), 2);
+
+        let log = parse_source(SourceText::new(text.clone())).unwrap();
+        assert_eq!(log.run_count(), 2);
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 1);
+        assert_eq!(ds[0].diagnostic().source_line(), Some(7));
+        assert!(text[ds[0].diagnostic().pos()..].starts_with("LaTeX Warning"));
+
+        let log = log.with_all_runs(true);
+        assert_eq!(log.get_diagnostics().len(), 2);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let parse = |text: &str| parse_source(SourceText::new(text.to_string()));
+        assert!(matches!(parse("no files here\n"), Err(Error::NoRootFile)));
+
+        // Unclosed files are closed at the end of the log
+        let log = parse("(./main.tex (./intro.tex\n\nLaTeX Warning: Reference `a' on page 1 undefined on input line 3.\n\n").unwrap();
+        assert_eq!(log.get_diagnostics().len(), 1);
+        assert_eq!(log.parse_warnings().len(), 2);
+
+        // A stray `(` does not swallow the `)` closing the file
+        let log = parse("(./main.tex (./intro.tex (see note\n\n)\n(./outro.tex\n\nLaTeX Warning: Reference `a' on page 1 undefined on input line 3.\n\n))").unwrap();
+        let ds = log.get_diagnostics();
+        assert_eq!(
+            ds[0].call_stack(),
+            &[PathBuf::from("./main.tex"), PathBuf::from("./outro.tex")]
+        );
+        assert_eq!(
+            log.parse_warnings(),
+            &["unbalanced '(' in ./intro.tex near line 1"]
+        );
+
+        // A stray `)` closes the root file early
+        let log = parse("(./main.tex ) (./outro.tex\n\nLaTeX Warning: Reference `a' on page 1 undefined on input line 3.\n\n))").unwrap();
+        assert_eq!(log.get_diagnostics().len(), 1);
+        assert_eq!(log.root_node().children().len(), 1);
+    }
+
+    #[test]
+    fn walk() {
+        let text = "(./main.tex (./a.tex (./b.tex)) (./c.tex))";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let files: Vec<_> = log
+            .root_node()
+            .walk()
+            .map(|(depth, node)| (depth, node.file()))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                (0, "./main.tex"),
+                (1, "./a.tex"),
+                (2, "./b.tex"),
+                (1, "./c.tex")
+            ]
+        );
+        assert_eq!(log.root_node().span(), Span::new(0, text.len() - 1));
+        assert_eq!(log.root_node().children()[0].span(), Span::new(12, 30));
+    }
+
+    #[test]
+    fn print_tree() {
+        let text = "(./main.tex (/usr/share/texmf/article.cls (/usr/share/texmf/size10.clo)) (./intro.tex\n\nLaTeX Warning: Reference `a' on page 1 undefined on input line 3.\n\n (./deep.tex)))";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let tree = Printer::new().print(log.root_node());
+        assert_eq!(
+            tree,
+            "./main.tex\n  /usr/share/texmf/article.cls\n    /usr/share/texmf/size10.clo\n  ./intro.tex (1 warning)\n    ./deep.tex\n"
+        );
+        let tree = Printer::new()
+            .with_user_files_only(true)
+            .with_max_depth(Some(1))
+            .print(log.root_node());
+        assert_eq!(tree, "./main.tex\n  ./intro.tex (1 warning)\n");
+
+        let filter = DiagnosticFilter {
+            level_overrides: vec![("reference".parse().unwrap(), DiagnosticLevel::Error)],
+            ..Default::default()
+        };
+        let log = log.with_filter(filter);
+        let tree = Printer::new()
+            .with_diagnostics(&log.get_diagnostics())
+            .with_max_depth(Some(1))
+            .print(log.root_node());
+        assert!(tree.contains("./intro.tex (1 error)\n"));
+    }
+
+    #[test]
+    fn print_dot() {
+        let text = "(./main.tex (/usr/share/texmf/article.cls) (./intro.tex\n\nLaTeX Warning: Reference `a' on page 1 undefined on input line 3.\n\n))";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let dot = Printer::new()
+            .with_dot(true)
+            .with_user_files_only(true)
+            .print(log.root_node());
+        assert_eq!(
+            dot,
+            "digraph inclusions {\n    node [shape=box, style=filled];\n    n0 [label=\"./main.tex\", fillcolor=white];\n    n1 [label=\"./intro.tex (1 warning)\", fillcolor=lightyellow];\n    n0 -> n1;\n}\n"
+        );
+    }
+
+    #[test]
+    fn timing() {
+        let text = "(./main.tex (./chapter.tex) (./appendix.tex))";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let timing = log.timing();
+        assert_eq!(timing.nodes, 3);
+        assert!(timing.tokens > 0);
+        assert_eq!(timing.reading, Duration::ZERO);
+    }
+
+    #[test]
+    fn malformed_logs_terminate() {
+        // An error cut off by the end of the log, and a `)` between an error and the line
+        // it is looked up from
+        for text in ["(./main.tex\n! Package foo Error: cut", "(/(/)(!pdfTeX"] {
+            let log = parse_source(SourceText::new(text.to_string())).unwrap();
+            assert_eq!(log.get_diagnostics().len(), 1);
+        }
+
+        let text = "(./main.tex ".repeat(MAX_FILE_DEPTH + 10);
+        let log = parse_source(SourceText::new(text)).unwrap();
+        assert_eq!(log.root_node().walk().count(), MAX_FILE_DEPTH + 1);
+        assert!(log.parse_warnings()[0].contains("nested more than"));
+    }
+
+    #[test]
+    fn crlf() {
+        let text = std::fs::read_to_string("./test/main.log").unwrap();
+        let log = parse_source(SourceText::new(text.clone())).unwrap();
+        let crlf_log = parse_source(SourceText::new(text.replace('\n', "\r\n"))).unwrap();
+        assert_eq!(log.get_diagnostics(), crlf_log.get_diagnostics());
+        assert!(crlf_log.parse_warnings().is_empty());
+
+        let bytes = "(./main.tex\r\n\r\nLaTeX Warning: Reference `a' on page 1 undefined on input line 3.\r\n\r\n)";
+        let log = parse_source(SourceText::new(crate::text::decode(bytes.as_bytes()))).unwrap();
+        let ds = log.get_diagnostics();
+        assert_eq!(ds.len(), 1);
+        assert!(ds[0].diagnostic().message().ends_with("line 3."));
+    }
+
+    #[test]
+    fn try_visitor() {
+        /// Finds the first file with an error, and the files that were left before it
+        #[derive(Default)]
+        struct FirstError {
+            left: Vec<String>,
+        }
+
+        impl<'a> TryVisitor<'a> for FirstError {
+            type Error = &'a Node;
+
+            fn enter_node(&mut self, node: &'a Node) -> std::result::Result<(), &'a Node> {
+                match node.errors().is_empty() {
+                    true => Ok(()),
+                    false => Err(node),
+                }
+            }
+
+            fn leave_node(&mut self, node: &'a Node) -> std::result::Result<(), &'a Node> {
+                self.left.push(node.file().to_string());
+                Ok(())
+            }
+        }
+
+        let text = "(./main.tex (./a.tex) (./b.tex\n! Undefined control sequence.\nl.3 \\foo\n\n) (./c.tex))";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let mut visitor = FirstError::default();
+        let found = visitor.try_visit_node(log.root_node());
+        assert_eq!(found.unwrap_err().file(), "./b.tex");
+        assert_eq!(visitor.left, vec!["./a.tex"]);
+    }
 }
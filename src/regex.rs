@@ -0,0 +1,486 @@
+use std::fmt::Display;
+
+/// A compiled regular expression. Supports literals, `.`, character classes, `^`, `$`, groups,
+/// alternation, the `*`, `+`, `?` and `{n,m}` quantifiers, the `\d`, `\w` and `\s` classes and a
+/// leading `(?i)` for case-insensitive matching.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Regex {
+    pattern: String,
+    program: Vec<Inst>,
+    case_insensitive: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+/// Deepest nesting of groups and quantifiers, so that parsing and compiling do not overflow the
+/// stack
+const MAX_NESTING: usize = 100;
+
+struct RegexParser {
+    chars: Vec<char>,
+    cursor: usize,
+
+    /// Number of groups around the cursor
+    depth: usize,
+}
+
+impl RegexParser {
+    fn current(&self) -> Option<char> {
+        self.chars.get(self.cursor).copied()
+    }
+
+    fn consume(&mut self) -> Option<char> {
+        let c = self.current();
+        self.cursor += 1;
+        c
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("invalid regex at position {}: {}", self.cursor, message)
+    }
+
+    fn parse_alternation(&mut self) -> Result<Node, String> {
+        let mut alternatives = vec![self.parse_concat()?];
+        while self.current() == Some('|') {
+            self.consume();
+            alternatives.push(self.parse_concat()?);
+        }
+        Ok(match alternatives.len() {
+            1 => alternatives.pop().expect("There is one alternative"),
+            _ => Node::Alternation(alternatives),
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, String> {
+        let mut nodes = vec![];
+        while let Some(c) = self.current() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            nodes.push(self.parse_repeat()?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn parse_number(&mut self) -> Option<usize> {
+        let start = self.cursor;
+        while self.current().is_some_and(|c| c.is_ascii_digit()) {
+            self.consume();
+        }
+        self.chars[start..self.cursor]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    fn parse_repeat(&mut self) -> Result<Node, String> {
+        let mut node = self.parse_atom()?;
+        let mut depth = self.depth;
+        loop {
+            let (min, max) = match self.current() {
+                Some('*') => (0, None),
+                Some('+') => (1, None),
+                Some('?') => (0, Some(1)),
+                Some('{') => {
+                    let start = self.cursor;
+                    self.consume();
+                    let Some(min) = self.parse_number() else {
+                        // Not a quantifier, so treat the brace literally
+                        self.cursor = start;
+                        return Ok(node);
+                    };
+                    let max = match self.current() {
+                        Some(',') => {
+                            self.consume();
+                            self.parse_number()
+                        }
+                        _ => Some(min),
+                    };
+                    if self.current() != Some('}') {
+                        return Err(self.error("expected '}'"));
+                    }
+                    if max.is_some_and(|max| max < min) {
+                        return Err(self.error("minimum is larger than maximum"));
+                    }
+                    (min, max)
+                }
+                _ => return Ok(node),
+            };
+            depth += 1;
+            if depth > MAX_NESTING {
+                return Err(self.error("too many nested quantifiers"));
+            }
+            self.consume();
+            node = Node::Repeat {
+                node: Box::new(node),
+                min,
+                max,
+            };
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<Node, String> {
+        let c = self
+            .consume()
+            .ok_or_else(|| self.error("trailing backslash"))?;
+        let class = |ranges: Vec<(char, char)>, negated| Node::Class { ranges, negated };
+        let word = vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')];
+        let space = vec![(' ', ' '), ('\t', '\t'), ('\n', '\n'), ('\r', '\r')];
+        Ok(match c {
+            'd' => class(vec![('0', '9')], false),
+            'D' => class(vec![('0', '9')], true),
+            'w' => class(word, false),
+            'W' => class(word, true),
+            's' => class(space, false),
+            'S' => class(space, true),
+            'n' => Node::Char('\n'),
+            't' => Node::Char('\t'),
+            c => Node::Char(c),
+        })
+    }
+
+    fn parse_class(&mut self) -> Result<Node, String> {
+        let negated = self.current() == Some('^');
+        if negated {
+            self.consume();
+        }
+        let mut ranges = vec![];
+        let mut first = true;
+        loop {
+            let c = self.consume().ok_or_else(|| self.error("expected ']'"))?;
+            let c = match c {
+                ']' if !first => return Ok(Node::Class { ranges, negated }),
+                '\\' => match self.parse_escape()? {
+                    Node::Char(c) => c,
+                    Node::Class { ranges: r, .. } => {
+                        ranges.extend(r);
+                        first = false;
+                        continue;
+                    }
+                    _ => unreachable!("Escapes are characters or classes"),
+                },
+                c => c,
+            };
+            first = false;
+            if self.current() == Some('-') && self.chars.get(self.cursor + 1) != Some(&']') {
+                self.consume();
+                let end = self.consume().ok_or_else(|| self.error("expected ']'"))?;
+                ranges.push((c, end));
+            } else {
+                ranges.push((c, c));
+            }
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, String> {
+        let c = self.consume().ok_or_else(|| self.error("unexpected end"))?;
+        match c {
+            '(' => {
+                if self.chars[self.cursor..].starts_with(&['?', ':']) {
+                    self.cursor += 2;
+                }
+                if self.depth == MAX_NESTING {
+                    return Err(self.error("too many nested groups"));
+                }
+                self.depth += 1;
+                let node = self.parse_alternation()?;
+                self.depth -= 1;
+                if self.consume() != Some(')') {
+                    return Err(self.error("expected ')'"));
+                }
+                Ok(node)
+            }
+            '[' => self.parse_class(),
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '\\' => self.parse_escape(),
+            '*' | '+' | '?' => Err(self.error("nothing to repeat")),
+            c => Ok(Node::Char(c)),
+        }
+    }
+}
+
+/// Largest number of instructions a pattern compiles to, as `{n,m}` repeats copy their node
+const MAX_PROGRAM: usize = 10_000;
+
+/// An instruction of the compiled pattern, run by a Pike VM that follows all possible matches at
+/// once. This takes time linear in the input, and no recursion.
+#[derive(Clone, Debug, PartialEq)]
+enum Inst {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    /// Continue at both, preferring the first
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+/// Append the instructions of a node to a program
+fn compile(node: &Node, program: &mut Vec<Inst>) -> Result<(), String> {
+    if program.len() > MAX_PROGRAM {
+        return Err("invalid regex: pattern is too large".to_string());
+    }
+    match node {
+        Node::Char(c) => program.push(Inst::Char(*c)),
+        Node::Any => program.push(Inst::Any),
+        Node::Class { ranges, negated } => program.push(Inst::Class {
+            ranges: ranges.clone(),
+            negated: *negated,
+        }),
+        Node::Start => program.push(Inst::Start),
+        Node::End => program.push(Inst::End),
+        Node::Concat(nodes) => {
+            for node in nodes {
+                compile(node, program)?;
+            }
+        }
+        Node::Alternation(alternatives) => {
+            let mut jumps = vec![];
+            let (last, rest) = alternatives
+                .split_last()
+                .expect("An alternation has alternatives");
+            for alternative in rest {
+                let split = program.len();
+                program.push(Inst::Split(split + 1, 0));
+                compile(alternative, program)?;
+                jumps.push(program.len());
+                program.push(Inst::Jump(0));
+                program[split] = Inst::Split(split + 1, program.len());
+            }
+            compile(last, program)?;
+            for jump in jumps {
+                program[jump] = Inst::Jump(program.len());
+            }
+        }
+        Node::Repeat { node, min, max } => {
+            for _ in 0..*min {
+                compile(node, program)?;
+            }
+            match max {
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(node, program)?;
+                    program.push(Inst::Jump(split));
+                    program[split] = Inst::Split(split + 1, program.len());
+                }
+                Some(max) => {
+                    let mut splits = vec![];
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(program.len() + 1, 0));
+                        compile(node, program)?;
+                    }
+                    for split in splits {
+                        program[split] = Inst::Split(split + 1, program.len());
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let (case_insensitive, body) = match pattern.strip_prefix("(?i)") {
+            Some(body) => (true, body),
+            None => (false, pattern),
+        };
+        let mut parser = RegexParser {
+            chars: body.chars().collect(),
+            cursor: 0,
+            depth: 0,
+        };
+        let node = parser.parse_alternation()?;
+        if parser.cursor < parser.chars.len() {
+            return Err(parser.error("unmatched ')'"));
+        }
+        let mut program = vec![];
+        compile(&node, &mut program)?;
+        program.push(Inst::Match);
+        Ok(Self {
+            pattern: pattern.to_string(),
+            program,
+            case_insensitive,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    fn chars_equal(&self, a: char, b: char) -> bool {
+        a == b || (self.case_insensitive && a.to_lowercase().eq(b.to_lowercase()))
+    }
+
+    fn in_class(&self, c: char, ranges: &[(char, char)]) -> bool {
+        ranges.iter().any(|(lo, hi)| {
+            (lo..=hi).contains(&&c)
+                || (self.case_insensitive
+                    && c.to_lowercase()
+                        .chain(c.to_uppercase())
+                        .any(|c| (*lo..=*hi).contains(&c)))
+        })
+    }
+
+    /// Add the thread at `pc` to a list, following jumps, splits and assertions at `pos`. Threads
+    /// are added in order of preference, and `seen` keeps a later thread from taking the place of
+    /// an earlier one.
+    fn add_thread(
+        &self,
+        threads: &mut Vec<(usize, usize)>,
+        seen: &mut [usize],
+        (pc, start): (usize, usize),
+        pos: usize,
+        len: usize,
+    ) {
+        let mut stack = vec![pc];
+        while let Some(pc) = stack.pop() {
+            if seen[pc] == pos {
+                continue;
+            }
+            seen[pc] = pos;
+            match &self.program[pc] {
+                Inst::Jump(to) => stack.push(*to),
+                Inst::Split(first, second) => {
+                    stack.push(*second);
+                    stack.push(*first);
+                }
+                Inst::Start if pos == 0 => stack.push(pc + 1),
+                Inst::End if pos == len => stack.push(pc + 1),
+                Inst::Start | Inst::End => {}
+                _ => threads.push((pc, start)),
+            }
+        }
+    }
+
+    /// Find the leftmost match, returned as a char range. Of the matches at the leftmost
+    /// position, the one a backtracking matcher would find first is returned, so quantifiers
+    /// are greedy.
+    pub fn find_chars(&self, input: &[char]) -> Option<(usize, usize)> {
+        let mut threads = vec![];
+        let mut next = vec![];
+        let mut seen = vec![usize::MAX; self.program.len()];
+        let mut found = None;
+        for pos in 0..=input.len() {
+            // A match that starts here is preferred less than those that started before
+            if found.is_none() {
+                self.add_thread(&mut threads, &mut seen, (0, pos), pos, input.len());
+            }
+            if threads.is_empty() {
+                break;
+            }
+            for &(pc, start) in &threads {
+                let step = match &self.program[pc] {
+                    Inst::Match => {
+                        // Threads after this one are preferred less, so they are dropped
+                        found = Some((start, pos));
+                        break;
+                    }
+                    Inst::Char(c) => input.get(pos).is_some_and(|i| self.chars_equal(*i, *c)),
+                    Inst::Any => input.get(pos).is_some_and(|c| *c != '\n'),
+                    Inst::Class { ranges, negated } => input
+                        .get(pos)
+                        .is_some_and(|c| self.in_class(*c, ranges) != *negated),
+                    _ => unreachable!("Threads wait on characters or a match"),
+                };
+                if step {
+                    self.add_thread(&mut next, &mut seen, (pc + 1, start), pos + 1, input.len());
+                }
+            }
+            std::mem::swap(&mut threads, &mut next);
+            next.clear();
+        }
+        found
+    }
+
+    /// Find the leftmost match, returned as a byte range into `text`
+    pub fn find(&self, text: &str) -> Option<(usize, usize)> {
+        let chars: Vec<char> = text.chars().collect();
+        let (start, end) = self.find_chars(&chars)?;
+        let byte = |i: usize| chars[..i].iter().map(|c| c.len_utf8()).sum();
+        Some((byte(start), byte(end)))
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.find(text).is_some()
+    }
+}
+
+impl Display for Regex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching() {
+        let re = Regex::new(r"Font shape `[^']+' undefined").unwrap();
+        assert!(re.is_match("LaTeX Font Warning: Font shape `OMS/cmtt/m/n' undefined"));
+
+        let re = Regex::new(r"^(Over|Under)full \\hbox \(\d+(\.\d+)?pt too wide\)").unwrap();
+        assert!(re.is_match(r"Overfull \hbox (18.61958pt too wide) in paragraph"));
+        assert!(!re.is_match(r"Underfull \vbox (badness 10000) has occurred"));
+
+        let re = Regex::new("(?i)hyperref").unwrap();
+        assert_eq!(re.find("Package HyperRef Warning"), Some((8, 16)));
+
+        let re = Regex::new("a{2,3}b*$").unwrap();
+        assert_eq!(re.find("caaaab"), Some((2, 6)));
+
+        let re = Regex::new("(a|ab)(c|bcd)").unwrap();
+        assert_eq!(re.find("xabcd"), Some((1, 5)));
+        let re = Regex::new("(a*)*b|").unwrap();
+        assert_eq!(re.find("aac"), Some((0, 0)));
+
+        assert!(Regex::new("(unclosed").is_err());
+        assert!(Regex::new("(a{100}){200}").is_err());
+        assert!(Regex::new("*").is_err());
+        assert!(Regex::new("a{3,1}").is_err());
+    }
+
+    #[test]
+    fn linear_time() {
+        // Patterns that take exponential time or deep recursion when backtracking
+        let re = Regex::new("(a|aa)*c").unwrap();
+        assert!(!re.is_match(&"a".repeat(40)));
+
+        let re = Regex::new("a.*z").unwrap();
+        let message = format!("a{}z", "b".repeat(20_000));
+        assert_eq!(re.find(&message), Some((0, message.len())));
+
+        assert!(Regex::new(&"(".repeat(50_000)).is_err());
+        assert!(Regex::new(&format!("a{}", "*".repeat(50_000))).is_err());
+        assert!(Regex::new(&format!("{}a{}", "(".repeat(50), ")*".repeat(50))).is_ok());
+    }
+}
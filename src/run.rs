@@ -0,0 +1,195 @@
+use std::{
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, ExitCode, Stdio},
+    thread,
+};
+
+use texlog::{console, text::SourceText};
+
+use crate::{cli::Args, read_log, report};
+
+/// Options of the TeX engines that take their value as the next argument when not written as
+/// `-option=value`
+const OPTIONS_WITH_VALUE: [&str; 6] = [
+    "jobname",
+    "output-directory",
+    "aux-directory",
+    "interaction",
+    "fmt",
+    "output-format",
+];
+
+/// Value of an option given as `-name=value`, `--name=value` or `-name value`
+fn option_value(arg: &str, next: Option<&String>, name: &str) -> Option<String> {
+    let option = arg.trim_start_matches('-');
+    match option.split_once('=') {
+        Some((n, value)) if n == name => Some(value.to_string()),
+        None if option == name => next.cloned(),
+        _ => None,
+    }
+}
+
+/// The log that an engine writes for a command line like
+/// `pdflatex -output-directory=build main.tex`. The log is named after the job, which defaults to
+/// the name of the input file.
+pub(crate) fn log_path(command: &[String]) -> Option<PathBuf> {
+    let mut job_name = None;
+    let mut output_directory = None;
+    let mut input = None;
+
+    let mut args = command.iter().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') {
+            // Input given as TeX code, like `"\input{main}"`, does not name the job
+            if !arg.starts_with('\\') {
+                input = Some(arg.clone());
+            }
+            continue;
+        }
+        job_name = option_value(arg, args.peek().copied(), "jobname").or(job_name);
+        output_directory =
+            option_value(arg, args.peek().copied(), "output-directory").or(output_directory);
+        let option = arg.trim_start_matches('-');
+        if OPTIONS_WITH_VALUE.contains(&option) {
+            args.next();
+        }
+    }
+
+    let job_name = job_name.or_else(|| {
+        Path::new(&input?)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+    })?;
+    let directory = output_directory.map(PathBuf::from).unwrap_or_default();
+    Some(directory.join(job_name).with_extension("log"))
+}
+
+/// Whether the command runs Tectonic, which only keeps the log of the engine when asked to, and
+/// writes its messages to standard error
+fn is_tectonic(command: &[String]) -> bool {
+    Path::new(&command[0]).file_stem().and_then(|s| s.to_str()) == Some("tectonic")
+}
+
+/// Copy the output of the engine to `to` as it arrives, and return all of it. The output keeps
+/// being read if it can not be written, so that the engine does not block.
+fn tee(mut from: impl Read, mut to: impl Write) -> io::Result<Vec<u8>> {
+    let mut output = vec![];
+    let mut buffer = [0; 4096];
+    loop {
+        let n = match from.read(&mut buffer) {
+            Ok(0) => return Ok(output),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let _ = to.write_all(&buffer[..n]).and_then(|_| to.flush());
+        output.extend_from_slice(&buffer[..n]);
+    }
+}
+
+/// Run the engine and report the diagnostics in its log. The terminal output of the engine is
+/// shown on standard error while it runs, so that standard output only holds the report, and
+/// is parsed instead of the log if the log cannot be found. Standard input is closed, so that
+/// the engine stops at errors instead of waiting for the user.
+pub(crate) fn run(args: &Args, command: &[String]) -> ExitCode {
+    let tectonic = is_tectonic(command);
+    let mut child = match Command::new(&command[0])
+        .args(&command[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(match tectonic {
+            true => Stdio::piped(),
+            false => Stdio::inherit(),
+        })
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("Error: could not run {}: {}", command[0], e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let stderr = child
+        .stderr
+        .take()
+        .map(|stderr| thread::spawn(move || tee(stderr, io::stderr())));
+    let stdout = child.stdout.take().expect("Standard output is piped");
+    let output = tee(stdout, io::stderr()).and_then(|mut output| {
+        if let Some(stderr) = stderr {
+            output.extend(
+                stderr
+                    .join()
+                    .expect("Reading standard error does not panic")?,
+            );
+        }
+        Ok(output)
+    });
+    let (status, output) = match output.and_then(|output| Ok((child.wait()?, output))) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Error: could not read the output of {}: {}", command[0], e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let log = match log_path(command).filter(|p| p.is_file() && !tectonic) {
+        Some(path) => read_log(args, path.to_str()),
+        None => SourceText::from_reader(&output[..])
+            .map_err(|e| e.to_string())
+            .and_then(|source| console::parse_console(source).map_err(|e| e.to_string())),
+    };
+    let code = match log {
+        Ok(log) => report(args, vec![(command[0].clone(), log)]),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    };
+
+    match status.code() {
+        Some(0) => code,
+        Some(c) => ExitCode::from(u8::try_from(c).unwrap_or(1)),
+        None => ExitCode::FAILURE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(command: &str) -> Option<PathBuf> {
+        let command: Vec<String> = command.split(' ').map(str::to_string).collect();
+        log_path(&command)
+    }
+
+    #[test]
+    fn log_paths() {
+        assert_eq!(path("pdflatex main.tex"), Some(PathBuf::from("main.log")));
+        assert_eq!(
+            path("lualatex -interaction nonstopmode -output-directory=build thesis"),
+            Some(PathBuf::from("build/thesis.log"))
+        );
+        assert_eq!(
+            path("xelatex --jobname draft -output-directory build main.tex"),
+            Some(PathBuf::from("build/draft.log"))
+        );
+        assert_eq!(path("pdflatex \\input{main}"), None);
+    }
+
+    #[test]
+    fn tectonic() {
+        let command = |c: &str| c.split(' ').map(str::to_string).collect::<Vec<_>>();
+        assert!(is_tectonic(&command("/usr/bin/tectonic main.tex")));
+        assert!(!is_tectonic(&command("xelatex main.tex")));
+    }
+
+    #[test]
+    fn tee() {
+        let mut shown = vec![];
+        let output = super::tee(&b"This is pdfTeX\n(./main.tex"[..], &mut shown).unwrap();
+        assert_eq!(output, b"This is pdfTeX\n(./main.tex");
+        assert_eq!(shown, output);
+    }
+}
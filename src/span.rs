@@ -0,0 +1,40 @@
+/// A region of the log, from the character at `start` up to but not including `end`. Positions
+/// count characters from the start of the log.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Number of characters in the span
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the position is inside the span
+    pub fn contains(&self, pos: usize) -> bool {
+        self.start <= pos && pos < self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn span() {
+        let span = Span::new(3, 7);
+        assert_eq!(span.len(), 4);
+        assert!(span.contains(3) && !span.contains(7));
+        assert!(Span::new(5, 5).is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{log::Log, parser::Node};
+
+/// Extensions of files that are written by the user, and not by the latex run itself
+const SOURCE_EXTENSIONS: [&str; 3] = ["tex", "sty", "cls"];
+
+/// The banner time has no timezone, so comparisons against it allow for this much slack
+const BANNER_TIME_SLACK: Duration = Duration::from_secs(14 * 60 * 60);
+
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+
+/// Parse the run timestamp at the end of the banner line (`... 14 JUN 2023 11:42`). The time is
+/// interpreted as UTC.
+pub(crate) fn banner_time(banner: &str) -> Option<SystemTime> {
+    let fields: Vec<&str> = banner.split_whitespace().collect();
+    let [day, month, year, time] = fields.get(fields.len().checked_sub(4)?..)? else {
+        return None;
+    };
+
+    let day: i64 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|m| m == month)? as i64 + 1;
+    let year: i64 = year.parse().ok()?;
+    let (hour, minute) = time.split_once(':')?;
+    let hour: i64 = hour.parse().ok()?;
+    let minute: i64 = minute.parse().ok()?;
+
+    let secs = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+/// Number of days since 1970-01-01 for a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn collect_source_files(node: &Node, files: &mut Vec<PathBuf>) {
+    let path = PathBuf::from(&node.file);
+    let is_source = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| SOURCE_EXTENSIONS.contains(&e));
+    if is_source && !files.contains(&path) {
+        files.push(path);
+    }
+    for call in &node.calls {
+        collect_source_files(call, files);
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl Log {
+    /// Directory that relative paths in the log are resolved against
+    pub(crate) fn base_dir(&self) -> PathBuf {
+        match self.path.as_ref().and_then(|p| p.parent()) {
+            Some(dir) => dir.to_path_buf(),
+            None => PathBuf::from("."),
+        }
+    }
+
+    /// Source files referenced by the log that have been modified after the log was written.
+    /// The log file's modification time is used when available, otherwise the banner timestamp.
+    pub fn stale_files(&self) -> Vec<PathBuf> {
+        let run_time = match self.path.as_deref().and_then(modified) {
+            Some(t) => t,
+            None => match self.source.as_str().lines().next().and_then(banner_time) {
+                Some(t) => t + BANNER_TIME_SLACK,
+                None => return vec![],
+            },
+        };
+
+        let mut files = vec![];
+        collect_source_files(&self.root_node, &mut files);
+
+        let base_dir = self.base_dir();
+        files
+            .into_iter()
+            .filter(|f| modified(&base_dir.join(f)).is_some_and(|t| t > run_time))
+            .collect()
+    }
+
+    /// A warning describing stale source files, if there are any
+    pub fn staleness_warning(&self) -> Option<String> {
+        let stale = self.stale_files();
+        let first = stale.first()?;
+        let name = first
+            .file_name()
+            .unwrap_or(first.as_os_str())
+            .to_string_lossy();
+        let others = match stale.len() - 1 {
+            0 => "".to_string(),
+            1 => " (and 1 other file)".to_string(),
+            n => format!(" (and {} other files)", n),
+        };
+        Some(format!(
+            "log is older than {}{} — results may be stale",
+            name, others
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_banner_time() {
+        let banner = "This is pdfTeX, Version 3.141592653-2.6-1.40.22 (TeX Live 2021) (preloaded format=pdflatex 2023.1.22)  14 JUN 2023 11:42";
+        let time = banner_time(banner).unwrap();
+        let secs = time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert_eq!(secs, 1686742920);
+    }
+}
@@ -0,0 +1,104 @@
+use std::fmt::Display;
+
+use crate::{log::Log, parser::DiagnosticLevel};
+
+/// Number of diagnostics in a log by level, kind and file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub infos: usize,
+    pub warnings: usize,
+    pub errors: usize,
+
+    /// Number of diagnostics of every kind, most common first
+    pub by_kind: Vec<(String, usize)>,
+
+    /// Number of diagnostics in every file, most common first
+    pub by_file: Vec<(String, usize)>,
+
+    /// Number of files opened in the final run
+    pub files: usize,
+
+    /// Number of those files that are part of the document
+    pub user_files: usize,
+}
+
+/// Add `n` to the count of `key`
+fn add(counts: &mut Vec<(String, usize)>, key: String, n: usize) {
+    match counts.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, count)) => *count += n,
+        None => counts.push((key, n)),
+    }
+}
+
+impl Stats {
+    pub fn new(log: &Log) -> Self {
+        let mut stats = Stats::default();
+        for d in log.get_diagnostics() {
+            match d.diagnostic().level() {
+                DiagnosticLevel::Info => stats.infos += d.count(),
+                DiagnosticLevel::Warning => stats.warnings += d.count(),
+                DiagnosticLevel::Error => stats.errors += d.count(),
+            }
+            add(
+                &mut stats.by_kind,
+                d.diagnostic().kind().name().to_string(),
+                d.count(),
+            );
+            let file = match d.source_file() {
+                Some(f) => f.display().to_string(),
+                None => "<unknown>".to_string(),
+            };
+            add(&mut stats.by_file, file, d.count());
+        }
+        for counts in [&mut stats.by_kind, &mut stats.by_file] {
+            counts.sort_by(|(a, n), (b, m)| m.cmp(n).then(a.cmp(b)));
+        }
+        for (_, node) in log.root_node().walk() {
+            stats.files += 1;
+            stats.user_files += node.is_user_file() as usize;
+        }
+        stats
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Files:    {} ({} from the document)",
+            self.files, self.user_files
+        )?;
+        if self.infos > 0 {
+            writeln!(f, "Infos:    {}", self.infos)?;
+        }
+        writeln!(f, "Warnings: {}", self.warnings)?;
+        writeln!(f, "Errors:   {}", self.errors)?;
+        for (title, counts) in [("By kind", &self.by_kind), ("By file", &self.by_file)] {
+            if counts.is_empty() {
+                continue;
+            }
+            writeln!(f, "\n{}:", title)?;
+            for (key, count) in counts {
+                writeln!(f, "{:>6}  {}", count, key)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::parse_source, text::SourceText};
+
+    #[test]
+    fn stats() {
+        let text = "(./main.tex (/usr/share/texmf/article.cls) (./intro.tex\n\nLaTeX Warning: Reference `a' on page 1 undefined on input line 3.\n\nLaTeX Warning: Reference `b' on page 1 undefined on input line 4.\n\n)\n\nOverfull \\hbox (1.0pt too wide) in paragraph at lines 3--3\n\n)";
+        let log = parse_source(SourceText::new(text.to_string())).unwrap();
+        let stats = Stats::new(&log);
+        assert_eq!((stats.warnings, stats.errors), (3, 0));
+        assert_eq!((stats.files, stats.user_files), (3, 2));
+        assert_eq!(stats.by_kind[0], ("reference".to_string(), 2));
+        assert_eq!(stats.by_file.len(), 2);
+    }
+}
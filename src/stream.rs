@@ -0,0 +1,259 @@
+use std::{io::Read, path::PathBuf};
+
+use crate::{
+    error::{Error, Result},
+    lexer::{self, TokenKind},
+    log::{Log, PathStyle, TracedTexDiagnostic},
+    parser::{Engine, Node, OpenFile, Parser},
+    text::{self, SourceText},
+};
+
+/// Size of the chunks read by `Log::from_reader`
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Parses a log a block of text at a time, so that only the tokens of one block are kept in
+/// memory. Bytes are added with `feed` as they arrive, `scan` returns the diagnostics of every
+/// complete block, and the files that have been closed can be taken with `take_nodes`.
+pub struct LogStream {
+    /// Bytes read after the last complete line
+    undecoded: Vec<u8>,
+
+    /// Text that has been read but not scanned yet
+    pending: String,
+
+    /// Length of the start of `pending` that is known to hold no blank line, so that it is not
+    /// searched again
+    searched: usize,
+
+    /// Character position of the start of `pending` in the log
+    pending_pos: usize,
+
+    /// Number of lines before `pending` in the log
+    pending_lines: usize,
+
+    /// Text that has been scanned
+    scanned: String,
+
+    /// Files open at the start of `pending`
+    stack: Vec<OpenFile>,
+
+    /// Root files that have been closed
+    completed: Vec<Node>,
+
+    /// Whether the root file of the document has been opened
+    started: bool,
+
+    /// Number of the last page shipped out
+    page: u32,
+
+    /// Engine that writes the log, detected from the first line
+    engine: Engine,
+
+    /// Directory of the log file, which paths are resolved against
+    base_dir: PathBuf,
+
+    /// How file paths are reported
+    paths: PathStyle,
+
+    /// Keep `^^` escapes in messages
+    raw_escapes: bool,
+}
+
+impl LogStream {
+    pub fn new(base_dir: PathBuf) -> Self {
+        Self {
+            base_dir,
+            paths: PathStyle::default(),
+            raw_escapes: false,
+            undecoded: vec![],
+            pending: String::new(),
+            searched: 0,
+            pending_pos: 0,
+            pending_lines: 0,
+            scanned: String::new(),
+            stack: vec![],
+            completed: vec![],
+            started: false,
+            page: 0,
+            engine: Engine::default(),
+        }
+    }
+
+    /// Report file paths in the given style
+    pub fn with_paths(mut self, paths: PathStyle) -> Self {
+        self.paths = paths;
+        self
+    }
+
+    /// Keep `^^` escaped characters in messages as TeX wrote them
+    pub fn with_raw_escapes(mut self, raw_escapes: bool) -> Self {
+        self.raw_escapes = raw_escapes;
+        self
+    }
+
+    /// Add newly read bytes. Only complete lines are decoded, so that characters are never split.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        // Only the new bytes are searched, so that a long line is not searched over and over
+        let start = self.undecoded.len();
+        self.undecoded.extend_from_slice(bytes);
+        let Some(end) = self.undecoded[start..].iter().rposition(|b| *b == b'\n') else {
+            return;
+        };
+        let end = start + end;
+        self.pending += &text::decode(&self.undecoded[..=end]);
+        self.undecoded.drain(..=end);
+    }
+
+    /// Scan text up to the last blank line, or all of it if `all` is set. Diagnostics end at
+    /// blank lines, so every message before one is complete.
+    pub fn scan(&mut self, all: bool) -> Vec<TracedTexDiagnostic> {
+        let from = self.searched.saturating_sub(1);
+        let end = match self.pending[from..].rfind("\n\n") {
+            _ if all => self.pending.len(),
+            Some(i) => from + i + 2,
+            None => {
+                self.searched = self.pending.len();
+                return vec![];
+            }
+        };
+        self.searched = 0;
+        if end == 0 {
+            return vec![];
+        }
+
+        let chunk: String = self.pending.drain(..end).collect();
+        if self.pending_pos == 0 {
+            self.engine = Engine::detect(&chunk);
+        }
+
+        // Diagnostics are only recognized at the start of a line
+        let text = format!("\n{}", chunk);
+        let mut tokens = lexer::tokenize_for(&text, self.engine);
+        for token in &mut tokens {
+            token.span.start = (token.span.start + self.pending_pos).saturating_sub(1);
+            token.span.end = (token.span.end + self.pending_pos).saturating_sub(1);
+            token.line = usize::max(1, token.line + self.pending_lines - 1);
+        }
+        self.started |= tokens
+            .windows(2)
+            .any(|w| w[0].kind == TokenKind::LeftParen && matches!(w[1].kind, TokenKind::Path(_)));
+        self.pending_pos += chunk.chars().count();
+        self.pending_lines += chunk.matches('\n').count();
+        self.scanned += &chunk;
+
+        let mut parser = Parser::new(tokens);
+        parser.page = self.page;
+        parser.engine = self.engine;
+        let mut diagnostics = parser.scan_diagnostics(&mut self.stack, &mut self.completed);
+        self.page = parser.page;
+        for d in &mut diagnostics {
+            d.resolve_paths(&self.base_dir, self.paths);
+            if !self.raw_escapes {
+                d.decode_escapes();
+            }
+        }
+        diagnostics
+    }
+
+    /// The root file has been closed, so the compilation is done
+    pub fn finished(&self) -> bool {
+        self.started && self.stack.is_empty()
+    }
+
+    /// Root files that have been closed since the last call, with the files they opened
+    pub fn take_nodes(&mut self) -> Vec<Node> {
+        std::mem::take(&mut self.completed)
+    }
+
+    /// Scan the rest of the text and build a `Log` of everything that was fed. Files that are
+    /// still open are closed at the end of the log.
+    pub fn finish(mut self) -> Result<Log> {
+        self.pending += &text::decode(&std::mem::take(&mut self.undecoded));
+        self.scan(true);
+        let mut parse_warnings = vec![];
+        while let Some(open) = self.stack.pop() {
+            let mut node = open.node;
+            parse_warnings.push(format!(
+                "{} is not closed before the end of the log",
+                node.file
+            ));
+            node.span.end = self.pending_pos;
+            match self.stack.last_mut() {
+                Some(parent) => parent.node.calls.push(node),
+                None => self.completed.push(node),
+            }
+        }
+
+        let mut nodes = self.completed.into_iter();
+        let mut root_node = nodes.next().ok_or(Error::NoRootFile)?;
+        for node in nodes {
+            parse_warnings.push(format!(
+                "{} is opened after {} is closed",
+                node.file, root_node.file
+            ));
+            root_node.calls.push(node);
+        }
+
+        let mut log = Log::new(SourceText::new(self.scanned), root_node)
+            .with_paths(self.paths)
+            .with_raw_escapes(self.raw_escapes);
+        log.engine = self.engine;
+        log.parse_warnings = parse_warnings;
+        Ok(log)
+    }
+}
+
+impl Log {
+    /// Read and parse a log a chunk at a time, which keeps memory use low for very large logs.
+    /// Unlike `parse_source`, runs are not split.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Log> {
+        let mut stream = LogStream::new(PathBuf::from("."));
+        let mut buf = vec![0; CHUNK_SIZE];
+        loop {
+            let n = reader.read(&mut buf).map_err(|source| Error::Io {
+                path: PathBuf::from("-"),
+                source,
+            })?;
+            if n == 0 {
+                break;
+            }
+            stream.feed(&buf[..n]);
+            stream.scan(false);
+        }
+        stream.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream() {
+        let mut stream = LogStream::new(PathBuf::from("."));
+        stream.feed(b"(./main.tex\n\nLaTeX Warning: Reference `a' on page 1 undef");
+        assert!(stream.scan(false).is_empty());
+        stream.feed(b"ined on input line 3.\n\n");
+        let ds = stream.scan(false);
+        assert_eq!(ds.len(), 1);
+        assert_eq!(ds[0].diagnostic().source_line(), Some(3));
+        assert!(!stream.finished());
+        stream.feed(b")\n");
+        stream.scan(true);
+        assert!(stream.finished());
+        assert_eq!(stream.take_nodes()[0].file(), "./main.tex");
+    }
+
+    #[test]
+    fn from_reader() {
+        let path = "./test/main.log";
+        let log = Log::from_reader(std::fs::File::open(path).unwrap()).unwrap();
+        let expected = crate::parser::parse_source(SourceText::from_file(path).unwrap()).unwrap();
+        assert_eq!(log.get_diagnostics(), expected.get_diagnostics());
+        assert_eq!(
+            log.root_node().children().len(),
+            expected.root_node().children().len()
+        );
+        assert!(log.parse_warnings().is_empty());
+    }
+}
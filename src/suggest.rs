@@ -0,0 +1,258 @@
+use crate::parser::TexDiagnosticKind;
+
+/// Commands that are often misspelled, to suggest when an undefined one is close to them
+const COMMON_COMMANDS: [&str; 48] = [
+    "\\author",
+    "\\begin",
+    "\\caption",
+    "\\centering",
+    "\\chapter",
+    "\\cite",
+    "\\date",
+    "\\documentclass",
+    "\\emph",
+    "\\end",
+    "\\footnote",
+    "\\frac",
+    "\\hline",
+    "\\hspace",
+    "\\include",
+    "\\includegraphics",
+    "\\input",
+    "\\item",
+    "\\label",
+    "\\ldots",
+    "\\left",
+    "\\maketitle",
+    "\\mathbf",
+    "\\mathrm",
+    "\\newcommand",
+    "\\newline",
+    "\\noindent",
+    "\\pageref",
+    "\\paragraph",
+    "\\par",
+    "\\ref",
+    "\\renewcommand",
+    "\\right",
+    "\\section",
+    "\\small",
+    "\\sqrt",
+    "\\subsection",
+    "\\subsubsection",
+    "\\tableofcontents",
+    "\\textbf",
+    "\\textit",
+    "\\texttt",
+    "\\textwidth",
+    "\\title",
+    "\\underline",
+    "\\usepackage",
+    "\\vspace",
+    "\\linewidth",
+];
+
+/// Packages that define commands which are often used without loading them
+const COMMAND_PACKAGES: [(&str, &str); 26] = [
+    ("\\autoref", "hyperref"),
+    ("\\bottomrule", "booktabs"),
+    ("\\checkmark", "amssymb"),
+    ("\\citep", "natbib"),
+    ("\\citet", "natbib"),
+    ("\\color", "xcolor"),
+    ("\\cref", "cleveref"),
+    ("\\Cref", "cleveref"),
+    ("\\eqref", "amsmath"),
+    ("\\euro", "eurosym"),
+    ("\\href", "hyperref"),
+    ("\\includegraphics", "graphicx"),
+    ("\\lipsum", "lipsum"),
+    ("\\mathbb", "amssymb"),
+    ("\\mathscr", "mathrsfs"),
+    ("\\midrule", "booktabs"),
+    ("\\multirow", "multirow"),
+    ("\\operatorname", "amsmath"),
+    ("\\SI", "siunitx"),
+    ("\\si", "siunitx"),
+    ("\\text", "amsmath"),
+    ("\\textcolor", "xcolor"),
+    ("\\tikz", "tikz"),
+    ("\\toprule", "booktabs"),
+    ("\\url", "url"),
+    ("\\xspace", "xspace"),
+];
+
+/// Packages whose names are often misspelled
+const COMMON_PACKAGES: [&str; 24] = [
+    "amsmath",
+    "amssymb",
+    "amsthm",
+    "babel",
+    "biblatex",
+    "booktabs",
+    "caption",
+    "cleveref",
+    "enumitem",
+    "fontenc",
+    "fontspec",
+    "geometry",
+    "graphicx",
+    "hyperref",
+    "inputenc",
+    "listings",
+    "microtype",
+    "natbib",
+    "siunitx",
+    "subcaption",
+    "tikz",
+    "xcolor",
+    "parskip",
+    "float",
+];
+
+/// Number of single character insertions, deletions, substitutions and swaps of neighbouring
+/// characters needed to turn one text into the other
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// The candidate closest to a misspelled name, if it is close enough to be a typo
+fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = match name.chars().count() {
+        0..=4 => 1,
+        _ => 2,
+    };
+    candidates
+        .into_iter()
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, c)| c)
+}
+
+/// The text of the `l.N` context line of an error, which ends with the token TeX stopped at
+fn context_line(message: &str) -> Option<(&str, &str)> {
+    message.lines().find_map(|line| {
+        let rest = line.strip_prefix("l.")?;
+        let (number, text) = rest.split_once(' ').unwrap_or((rest, ""));
+        number
+            .chars()
+            .all(|c| c.is_ascii_digit())
+            .then_some((number, text.trim_end()))
+    })
+}
+
+fn undefined_command(name: &str) -> String {
+    if let Some((_, package)) = COMMAND_PACKAGES.iter().find(|(c, _)| *c == name) {
+        return format!(
+            "`{}` is defined by the {} package; add `\\usepackage{{{}}}` to the preamble",
+            name, package, package
+        );
+    }
+    if let Some(command) = closest(name, COMMON_COMMANDS) {
+        return format!("did you mean `{}`?", command);
+    }
+    format!(
+        "check the spelling of `{}`, load the package that defines it, or define it with \
+         `\\newcommand`",
+        name
+    )
+}
+
+fn missing_math(message: &str) -> String {
+    let last = context_line(message).and_then(|(_, text)| text.chars().last());
+    match last {
+        Some(c @ ('_' | '^' | '&' | '#')) => format!(
+            "`{}` only works in math mode; write `\\{}` for the character in text, or put the \
+             formula in `$...$`",
+            c, c
+        ),
+        _ => "math commands like `\\alpha`, `^` and `_` only work in math mode; put the formula \
+              in `$...$`, and check that every `$` is closed"
+            .to_string(),
+    }
+}
+
+fn too_many_braces(message: &str) -> String {
+    match context_line(message) {
+        Some((line, _)) => format!(
+            "remove the extra `}}` on line {}, or add the `{{` it was meant to close",
+            line
+        ),
+        None => "remove the extra `}`, or add the `{` it was meant to close".to_string(),
+    }
+}
+
+fn missing_file(file: &str) -> Option<String> {
+    let (name, extension) = file.rsplit_once('.').unwrap_or((file, ""));
+    if !matches!(extension, "sty" | "cls") {
+        return Some(format!(
+            "check the path of `{}`; it is looked up relative to the directory LaTeX runs in",
+            file
+        ));
+    }
+    let package = closest(name, COMMON_PACKAGES).filter(|p| *p != name)?;
+    Some(format!("did you mean `{}`?", package))
+}
+
+/// A fix for a well-known error, more specific than the hint of its kind
+pub(crate) fn suggestion(kind: &TexDiagnosticKind, message: &str) -> Option<String> {
+    match kind {
+        TexDiagnosticKind::UndefinedControlSequence(name) => Some(undefined_command(name)),
+        TexDiagnosticKind::MissingFile(file) => missing_file(file),
+        TexDiagnosticKind::GenericError(title) if title.starts_with("Missing $ inserted") => {
+            Some(missing_math(message))
+        }
+        TexDiagnosticKind::GenericError(title) if title.starts_with("Too many }'s") => {
+            Some(too_many_braces(message))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggestions() {
+        assert_eq!(edit_distance("\\dtae", "\\date"), 1);
+        let suggest = |kind, message: &str| suggestion(&kind, message).unwrap();
+
+        let kind = TexDiagnosticKind::UndefinedControlSequence("\\dtae".to_string());
+        assert_eq!(suggest(kind, ""), "did you mean `\\date`?");
+        let kind = TexDiagnosticKind::UndefinedControlSequence("\\toprule".to_string());
+        assert!(suggest(kind, "").contains("\\usepackage{booktabs}"));
+
+        let kind = TexDiagnosticKind::GenericError("Missing $ inserted.".to_string());
+        let message = "! Missing $ inserted.\n<inserted text>\n$\nl.12 the file my_";
+        assert!(suggest(kind, message).starts_with("`_` only works in math mode"));
+
+        let kind = TexDiagnosticKind::GenericError("Too many }'s.".to_string());
+        let message = "! Too many }'s.\nl.6 \\date December 2004}";
+        assert!(suggest(kind, message).contains("on line 6"));
+
+        let kind = TexDiagnosticKind::MissingFile("graphix.sty".to_string());
+        assert_eq!(suggest(kind, ""), "did you mean `graphicx`?");
+        let kind = TexDiagnosticKind::MissingFile("paralisy.sty".to_string());
+        assert_eq!(suggestion(&kind, ""), None);
+    }
+}
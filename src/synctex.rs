@@ -0,0 +1,254 @@
+use std::{
+    fmt::Display,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    error::{Error, Result},
+    inflate,
+    log::Log,
+    text,
+};
+
+/// Scaled points per TeX point
+const SP_PER_PT: f64 = 65536.0;
+
+/// PDF points (big points) per TeX point
+const BP_PER_PT: f64 = 72.0 / 72.27;
+
+/// A place in the PDF. Coordinates are in PDF points (1/72 inch) from the top left corner of the
+/// page.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PdfPosition {
+    /// Page of the PDF, counting from 1 regardless of the page numbering of the document
+    pub page: u32,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Display for PdfPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PDF page {} at {:.1}, {:.1}", self.page, self.x, self.y)
+    }
+}
+
+/// A box or node that SyncTeX recorded the source line of
+#[derive(Clone, Debug, PartialEq)]
+struct Record {
+    /// Tag of the input file
+    tag: u32,
+    line: usize,
+    page: u32,
+    x: i64,
+    y: i64,
+
+    /// Horizontal boxes hold lines of text, which are what box warnings are about
+    hbox: bool,
+}
+
+/// The positions in the PDF of the source lines, from the `.synctex.gz` file that TeX writes when
+/// run with `-synctex=1`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SyncTex {
+    /// Input files by their tag
+    inputs: Vec<(u32, PathBuf)>,
+
+    records: Vec<Record>,
+
+    /// Scaled points per coordinate unit
+    unit: f64,
+
+    /// Magnification of the document, where 1000 is none
+    magnification: f64,
+
+    x_offset: i64,
+    y_offset: i64,
+}
+
+/// Path without `.` components, so that `/home/user/./main.tex` matches `main.tex`
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| *c != Component::CurDir)
+        .collect()
+}
+
+/// The tag, line and coordinates of a record like `(1,12:4736286,9830400:26673152,655360,0`,
+/// without the leading type character
+fn parse_record(record: &str) -> Option<(u32, usize, i64, i64)> {
+    let mut parts = record.split(':');
+    let mut link = parts.next()?.split(',');
+    let tag = link.next()?.parse().ok()?;
+    let line = link.next()?.parse().ok()?;
+    let (x, y) = parts.next()?.split_once(',')?;
+    Some((tag, line, x.parse().ok()?, y.parse().ok()?))
+}
+
+impl SyncTex {
+    pub fn parse(source: &str) -> Self {
+        let mut synctex = SyncTex {
+            unit: 1.0,
+            magnification: 1000.0,
+            ..Default::default()
+        };
+        let mut page = 0;
+        let mut in_content = false;
+        for line in source.lines() {
+            if let Some(input) = line.strip_prefix("Input:") {
+                if let Some((tag, path)) = input.split_once(':') {
+                    if let Ok(tag) = tag.parse() {
+                        synctex.inputs.push((tag, PathBuf::from(path)));
+                    }
+                }
+                continue;
+            }
+            if !in_content {
+                let number = |s: &str| s.trim().parse::<f64>().ok();
+                if let Some(unit) = line.strip_prefix("Unit:").and_then(number) {
+                    synctex.unit = unit;
+                } else if let Some(mag) = line.strip_prefix("Magnification:").and_then(number) {
+                    synctex.magnification = mag;
+                } else if let Some(x) = line.strip_prefix("X Offset:").and_then(number) {
+                    synctex.x_offset = x as i64;
+                } else if let Some(y) = line.strip_prefix("Y Offset:").and_then(number) {
+                    synctex.y_offset = y as i64;
+                }
+                in_content = line.starts_with("Content:");
+                continue;
+            }
+
+            let mut chars = line.chars();
+            match chars.next() {
+                Some('{') => page = chars.as_str().parse().unwrap_or(page),
+                Some(kind @ ('[' | '(' | 'v' | 'h' | 'x' | 'k' | 'g' | '$')) => {
+                    if let Some((tag, line, x, y)) = parse_record(chars.as_str()) {
+                        synctex.records.push(Record {
+                            tag,
+                            line,
+                            page,
+                            x,
+                            y,
+                            hbox: kind == '(' || kind == 'h',
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        synctex
+    }
+
+    /// Read a `.synctex.gz` file, or an uncompressed `.synctex` file
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let bytes = match path.extension().is_some_and(|e| e == "gz") {
+            true => inflate::gunzip(&bytes).map_err(|message| Error::Parse {
+                path: path.to_path_buf(),
+                message,
+            })?,
+            false => bytes,
+        };
+        Ok(Self::parse(&text::decode(&bytes)))
+    }
+
+    /// Convert a coordinate to PDF points
+    fn to_bp(&self, value: i64, offset: i64) -> f32 {
+        let sp = value as f64 * self.unit + offset as f64;
+        (sp / SP_PER_PT * self.magnification / 1000.0 * BP_PER_PT) as f32
+    }
+
+    /// Where a line of a source file ended up in the PDF. The first line of text from the source
+    /// line is preferred over other kinds of boxes.
+    pub fn locate(&self, file: &Path, line: usize) -> Option<PdfPosition> {
+        let file = normalize(file);
+        let tags: Vec<u32> = self
+            .inputs
+            .iter()
+            .filter(|(_, input)| {
+                let input = normalize(input);
+                input.ends_with(&file) || file.ends_with(&input)
+            })
+            .map(|(tag, _)| *tag)
+            .collect();
+        let mut records = self
+            .records
+            .iter()
+            .filter(|r| r.line == line && tags.contains(&r.tag));
+        let record = records
+            .clone()
+            .find(|r| r.hbox)
+            .or_else(|| records.next())?;
+        Some(PdfPosition {
+            page: record.page,
+            x: self.to_bp(record.x, self.x_offset),
+            y: self.to_bp(record.y, self.y_offset),
+        })
+    }
+}
+
+impl Log {
+    /// The SyncTeX file next to the log, if TeX was run with `-synctex`
+    pub fn synctex_path(&self) -> Option<PathBuf> {
+        let path = self.path.as_ref()?;
+        ["synctex.gz", "synctex"]
+            .into_iter()
+            .map(|extension| path.with_extension(extension))
+            .find(|p| p.is_file())
+    }
+
+    /// Find the position in the PDF of every diagnostic that points to a source line
+    pub fn with_synctex(mut self, synctex: SyncTex) -> Self {
+        self.synctex = Some(synctex);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::parse_source, text::SourceText};
+
+    const SYNCTEX: &str = "SyncTeX Version:1
+Input:1:/home/user/thesis/./main.tex
+Input:2:/home/user/thesis/./sections/intro.tex
+Output:pdf
+Magnification:1000
+Unit:1
+X Offset:0
+Y Offset:0
+Content:
+!250
+{1
+[1,10:4736286,49045002:26673152,41517121,0
+(2,12:4736286,9830400:26673152,655360,0
+g2,12:4736286,9830400
+)
+]
+}1
+{2
+(2,14:4736286,13107200:26673152,655360,0
+}2
+Postamble:
+";
+
+    #[test]
+    fn locate() {
+        let synctex = SyncTex::parse(SYNCTEX);
+        let position = synctex
+            .locate(Path::new("./sections/intro.tex"), 14)
+            .unwrap();
+        assert_eq!(position.page, 2);
+        assert!((position.y - 199.25).abs() < 0.01);
+        assert_eq!(synctex.locate(Path::new("main.tex"), 12), None);
+
+        let text = "(./main.tex (./sections/intro.tex\n\nOverfull \\hbox (1.0pt too wide) in paragraph at lines 12--12\n\n))";
+        let log = parse_source(SourceText::new(text.to_string()))
+            .unwrap()
+            .with_synctex(synctex);
+        let ds = log.get_diagnostics();
+        assert_eq!(ds[0].pdf_position().map(|p| p.page), Some(1));
+    }
+}
@@ -0,0 +1,167 @@
+use std::path::PathBuf;
+
+use crate::{
+    lexer,
+    log::Log,
+    parser::{Engine, Node, Parser, TexDiagnostic, TexDiagnosticKind},
+    text::SourceText,
+};
+
+/// Name of the root file if the output does not name any file, as TeX names jobs without one
+const UNNAMED_JOB: &str = "texput";
+
+/// Whether text is the terminal output of Tectonic, which starts every message with `note: `,
+/// `warning: ` or `error: ` instead of writing the log of the engine
+pub fn is_tectonic_output(text: &str) -> bool {
+    text.lines()
+        .find(|l| !l.trim().is_empty())
+        .is_some_and(|l| level(l).is_some())
+}
+
+/// The level and the rest of a line like `warning: main.tex:12: Overfull \hbox ...`
+fn level(line: &str) -> Option<(&str, &str)> {
+    ["note", "warning", "error"].iter().find_map(|level| {
+        let rest = line.strip_prefix(level)?.strip_prefix(": ")?;
+        Some((*level, rest))
+    })
+}
+
+/// The file, line and message of `main.tex:12: Overfull \hbox ...`
+fn location(message: &str) -> Option<(&str, usize, &str)> {
+    let (file, rest) = message.split_once(':')?;
+    let (number, message) = rest.split_once(": ")?;
+    let is_file = file.contains('.') && !file.contains(char::is_whitespace);
+    match (is_file, number.parse()) {
+        (true, Ok(line)) => Some((file, line, message)),
+        _ => None,
+    }
+}
+
+/// The kind of a message, found by parsing it as the engine would have written it to the log.
+/// Errors that Tectonic reports without a location are its own, and mean that it stopped.
+fn kind(level: &str, message: &str, located: bool) -> TexDiagnosticKind {
+    let text = match level {
+        "error" => format!("\n! {}\n\n", message),
+        _ => format!("\n{}\n\n", message),
+    };
+    let mut parser = Parser::new(lexer::tokenize_unwrapped(&text));
+    parser.engine = Engine::XeTex;
+    let kind = parser
+        .scan_diagnostics(&mut vec![], &mut vec![])
+        .into_iter()
+        .next()
+        .map(|d| d.diagnostic().kind().clone());
+    let title = message.lines().next().unwrap_or_default().to_string();
+    match (level, kind) {
+        ("error", Some(TexDiagnosticKind::GenericError(_)) | None) if !located => {
+            TexDiagnosticKind::Fatal
+        }
+        (_, Some(kind)) => kind,
+        ("error", None) => TexDiagnosticKind::GenericError(title),
+        _ => TexDiagnosticKind::LatexWarning(title),
+    }
+}
+
+/// Parse the terminal output of Tectonic. Tectonic reports the warnings and errors of the engine
+/// as `warning: main.tex:12: ...` and `error: main.tex:3: ...` lines, followed by lines of its
+/// own like `caused by: ...`. It does not report which files were opened, so the tree only
+/// holds the first file that is named, and the other files with diagnostics below it.
+pub fn parse_tectonic(source: SourceText) -> Log {
+    let mut messages: Vec<(usize, &str, String)> = vec![];
+    let mut pos = 0;
+    for line in source.as_str().lines() {
+        let line_pos = pos;
+        pos += line.chars().count() + 1;
+
+        match level(line) {
+            Some(("note", _)) => {}
+            Some((level, message)) => messages.push((line_pos, level, message.to_string())),
+            None if line.trim().is_empty() => {}
+            None => {
+                if let Some((_, _, message)) = messages.last_mut() {
+                    message.push('\n');
+                    *message += line;
+                }
+            }
+        }
+    }
+
+    let mut root = None;
+    let mut files: Vec<Node> = vec![];
+    let mut root_diagnostics = vec![];
+    for (pos, level, message) in messages {
+        let (diagnostic, file) = match location(&message) {
+            Some((file, line, text)) => {
+                let mut diagnostic =
+                    TexDiagnostic::new(kind(level, text, true), text.to_string(), pos);
+                diagnostic.source_file = Some(PathBuf::from(file));
+                diagnostic.source_line = Some(line);
+                (diagnostic, Some(file.to_string()))
+            }
+            None => (
+                TexDiagnostic::new(kind(level, &message, false), message.clone(), pos),
+                None,
+            ),
+        };
+        let root = root.get_or_insert_with(|| file.clone());
+        match file {
+            Some(file) if Some(&file) != root.as_ref() => {
+                match files.iter_mut().find(|n| n.file() == file) {
+                    Some(node) => node.diagnostics.push(diagnostic),
+                    None => files.push(Node::new(file, vec![diagnostic])),
+                }
+            }
+            _ => root_diagnostics.push(diagnostic),
+        }
+    }
+
+    let root = root.flatten().unwrap_or(UNNAMED_JOB.to_string());
+    let mut root_node = Node::new(root, root_diagnostics);
+    root_node.calls = files;
+    let mut log = Log::new(source, root_node);
+    log.engine = Engine::XeTex;
+    log
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::DiagnosticLevel;
+
+    #[test]
+    fn tectonic() {
+        let text = "note: Running TeX ...\n\
+             warning: main.tex:12: Overfull \\hbox (3.5pt too wide) in paragraph at lines 12--13\n\
+             warning: chapters/intro.tex:4: LaTeX Warning: Reference `fig:a' on page 1 undefined on input line 4.\n\
+             error: main.tex:20: Undefined control sequence.\n\
+             error: halted on potentially-recoverable error as specified\n\
+             note: Writing `main.pdf` (12.3 KiB)\n";
+        assert!(is_tectonic_output(text));
+        assert!(!is_tectonic_output("This is XeTeX, Version 3.14\n"));
+
+        let log = parse_tectonic(SourceText::new(text.to_string()));
+        assert_eq!(log.root_node().file(), "main.tex");
+        assert_eq!(log.root_node().children()[0].file(), "chapters/intro.tex");
+
+        let ds = log.get_diagnostics();
+        let kinds: Vec<_> = ds.iter().map(|d| d.diagnostic().kind().clone()).collect();
+        assert!(matches!(kinds[0], TexDiagnosticKind::OverfullHbox { .. }));
+        assert_eq!(
+            kinds[1],
+            TexDiagnosticKind::GenericError("Undefined control sequence.".to_string())
+        );
+        assert_eq!(kinds[2], TexDiagnosticKind::Fatal);
+        assert_eq!(kinds[3], TexDiagnosticKind::Reference("fig:a".to_string()));
+
+        assert_eq!(ds[1].diagnostic().source_line(), Some(20));
+        assert_eq!(ds[1].diagnostic().level(), DiagnosticLevel::Error);
+        assert_eq!(ds[3].diagnostic().source_line(), Some(4));
+        assert_eq!(
+            ds[3].call_stack(),
+            &[
+                PathBuf::from("main.tex"),
+                PathBuf::from("chapters/intro.tex")
+            ]
+        );
+    }
+}
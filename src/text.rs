@@ -1,14 +1,168 @@
-use std::{fs, io, path::Path, rc::Rc};
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+    rc::Rc,
+};
+
+use crate::span::Span;
+
+/// Decode the bytes of a log. TeX writes the input as it reads it, so a log can contain Latin-1
+/// or broken bytes when the input encoding is wrong. Lines that are not valid UTF-8 are decoded
+/// as Latin-1, which maps every byte to a character.
+pub(crate) fn decode(bytes: &[u8]) -> String {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return normalize_newlines(text.to_string());
+    }
+    let mut text = String::with_capacity(bytes.len());
+    for line in bytes.split_inclusive(|b| *b == b'\n') {
+        match std::str::from_utf8(line) {
+            Ok(line) => text.push_str(line),
+            Err(_) => text.extend(line.iter().map(|b| *b as char)),
+        }
+    }
+    normalize_newlines(text)
+}
+
+/// Replace the `\r\n` line endings of logs written on Windows with `\n`, so that a blank line
+/// is always two `\n` in a row and a carriage return never ends up in a message or path
+fn normalize_newlines(text: String) -> String {
+    match text.contains("\r\n") {
+        true => text.replace("\r\n", "\n"),
+        false => text,
+    }
+}
+
+/// Decode the `^^` notation that TeX uses for characters it considers unprintable, like `^^I`
+/// for a tab and `^^c3^^a9` for the UTF-8 bytes of `é`. Escapes of control characters other
+/// than tabs are kept as they are, since they would garble the output.
+pub fn decode_caret_escapes(text: &str) -> String {
+    if !text.contains("^^") {
+        return text.to_string();
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '^' && chars.get(i + 1) == Some(&'^') {
+            if let Some((byte, len)) = caret_escape(&chars[i + 2..]) {
+                if byte >= 0x80 || byte == b'\t' || (0x20..0x7f).contains(&byte) {
+                    bytes.push(byte);
+                    i += 2 + len;
+                    continue;
+                }
+            }
+        }
+        let mut buf = [0; 4];
+        bytes.extend_from_slice(chars[i].encode_utf8(&mut buf).as_bytes());
+        i += 1;
+    }
+    decode(&bytes)
+}
+
+/// The byte of the escape following a `^^`, and the number of characters it takes up
+fn caret_escape(rest: &[char]) -> Option<(u8, usize)> {
+    let is_hex = |c: &char| c.is_ascii_digit() || ('a'..='f').contains(c);
+    match rest {
+        [a, b, ..] if is_hex(a) && is_hex(b) => {
+            let hex: String = [*a, *b].iter().collect();
+            Some((u8::from_str_radix(&hex, 16).ok()?, 2))
+        }
+        [c, ..] if c.is_ascii() => {
+            let code = *c as u8;
+            Some((if code < 64 { code + 64 } else { code - 64 }, 1))
+        }
+        _ => None,
+    }
+}
+
+/// Characters that take up no column of their own, because they combine with the character
+/// before them, like accents, or are invisible, like zero width spaces and variation selectors
+const ZERO_WIDTH: [(u32, u32); 9] = [
+    (0x0300, 0x036f),
+    (0x1ab0, 0x1aff),
+    (0x1dc0, 0x1dff),
+    (0x200b, 0x200f),
+    (0x202a, 0x202e),
+    (0x2060, 0x2064),
+    (0x20d0, 0x20ff),
+    (0xfe00, 0xfe0f),
+    (0xfe20, 0xfe2f),
+];
+
+/// Characters that take up two columns, like CJK characters and emoji
+const WIDE: [(u32, u32); 15] = [
+    (0x1100, 0x115f),
+    (0x2e80, 0x303e),
+    (0x3041, 0x33ff),
+    (0x3400, 0x4dbf),
+    (0x4e00, 0x9fff),
+    (0xa000, 0xa4cf),
+    (0xac00, 0xd7a3),
+    (0xf900, 0xfaff),
+    (0xfe30, 0xfe4f),
+    (0xff00, 0xff60),
+    (0xffe0, 0xffe6),
+    (0x1f300, 0x1f64f),
+    (0x1f900, 0x1f9ff),
+    (0x20000, 0x2fffd),
+    (0x30000, 0x3fffd),
+];
+
+/// Number of columns a character takes up in an editor or terminal
+pub(crate) fn char_width(c: char) -> usize {
+    let in_ranges = |ranges: &[(u32, u32)]| {
+        ranges
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&(c as u32)))
+    };
+    if c == '\u{feff}' || in_ranges(&ZERO_WIDTH) {
+        0
+    } else if in_ranges(&WIDE) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Number of columns a text takes up in an editor or terminal
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
 
 #[derive(Clone)]
 pub struct SourceText {
     text: Rc<String>,
+
+    /// Character position of the start of every line, for fast position lookups
+    line_starts: Rc<Vec<usize>>,
+
+    /// Byte offset of the start of every line
+    line_byte_starts: Rc<Vec<usize>>,
+
+    /// Number of characters in the text
+    len: usize,
 }
 
 impl SourceText {
     pub fn new(text: String) -> Self {
+        let text = normalize_newlines(text);
+        let mut line_starts = vec![0];
+        let mut line_byte_starts = vec![0];
+        let mut len = 0;
+        for (i, c) in text.char_indices() {
+            len += 1;
+            if c == '\n' {
+                line_starts.push(len);
+                line_byte_starts.push(i + 1);
+            }
+        }
         Self {
             text: Rc::new(text),
+            line_starts: Rc::new(line_starts),
+            line_byte_starts: Rc::new(line_byte_starts),
+            len,
         }
     }
 
@@ -16,7 +170,13 @@ impl SourceText {
     where
         P: AsRef<Path>,
     {
-        Ok(Self::new(fs::read_to_string(path)?))
+        Ok(Self::new(decode(&fs::read(path)?)))
+    }
+
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, io::Error> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        Ok(Self::new(decode(&bytes)))
     }
 
     pub fn as_str(&self) -> &str {
@@ -27,39 +187,76 @@ impl SourceText {
         self.text.clone()
     }
 
+    /// The row of a character position, and the text of the row up to the position
+    fn row_prefix(&self, index: usize) -> (usize, &str) {
+        let row = self.line_starts.partition_point(|start| *start <= index);
+        let line = &self.text[self.line_byte_starts[row - 1]..];
+        let chars = index - self.line_starts[row - 1];
+        let end = line
+            .char_indices()
+            .nth(chars)
+            .map_or(line.len(), |(i, _)| i);
+        (row, &line[..end])
+    }
+
+    /// The 1-based row and column of a character position. Columns are counted as an editor
+    /// shows them: accents and other combining characters take up no column, and wide
+    /// characters like CJK take up two.
     pub fn row_col(&self, index: usize) -> (usize, usize) {
-        let mut row = 1;
-        let mut last_line_start = 0;
-        for (i, c) in self.text[..index].chars().enumerate() {
-            if c == '\n'  {
-                row += 1;
-                last_line_start = i + 1;
-            }
-        }
-        (row, index - last_line_start + 1)
+        let (row, prefix) = self.row_prefix(index);
+        (row, display_width(prefix) + 1)
     }
 
+    /// The 1-based row and column of a character position, with the column counted in bytes
+    pub fn row_byte_col(&self, index: usize) -> (usize, usize) {
+        let (row, prefix) = self.row_prefix(index);
+        (row, prefix.len() + 1)
+    }
+
+    /// The character position of a 1-based row and column, counted as by `row_col`. Rows after
+    /// the last line are taken to start at the end of the text, and columns after the end of a
+    /// line continue into the next.
     pub fn index(&self, row: usize, col: usize) -> usize {
         let row = usize::max(1, row);
         let col = usize::max(1, col);
-
-        let mut index = 0;
-        let mut chars = self.text.chars();
-
-        // Find row
-        for _ in 1..row {
-            for c in &mut chars {
-                index += 1;
-                if c == '\n' {
-                    break;
-                }
+        let Some(line_start) = self.line_starts.get(row - 1).copied() else {
+            return self.len + col - 1;
+        };
+        let line = self.text[self.line_byte_starts[row - 1]..]
+            .split('\n')
+            .next()
+            .unwrap_or("");
+        let mut width = 0;
+        for (i, c) in line.chars().enumerate() {
+            if width + char_width(c) >= col {
+                return line_start + i;
             }
+            width += char_width(c);
         }
+        line_start + line.chars().count() + (col - 1 - width)
+    }
 
-        // Add col
-        index += col - 1;
+    /// The text of a span
+    pub fn span_text(&self, span: Span) -> &str {
+        let byte = |pos: usize| {
+            self.text
+                .char_indices()
+                .nth(pos)
+                .map_or(self.text.len(), |(i, _)| i)
+        };
+        let start = byte(span.start);
+        &self.text[start..start.max(byte(span.end))]
+    }
 
-        index
+    /// The text of a 1-based row, without the newline
+    pub fn line(&self, row: usize) -> Option<&str> {
+        let start = *self.line_byte_starts.get(row.checked_sub(1)?)?;
+        self.text[start..].split('\n').next()
+    }
+
+    /// Number of lines in the text
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
     }
 }
 
@@ -76,5 +273,46 @@ mod tests {
             println!("{} -> {:?} -> {}", input_index, (&row, &col), output_index);
             assert_eq!(*input_index, output_index)
         }
+
+        // Positions count characters, not bytes
+        let source = SourceText::new("é\nab\n".to_string());
+        assert_eq!(source.row_col(0), (1, 1));
+        assert_eq!(source.row_col(3), (2, 2));
+        assert_eq!(source.index(2, 2), 3);
+        assert_eq!(source.line_count(), 3);
+
+        // Columns are counted as an editor shows them, or in bytes
+        let source = SourceText::new("e\u{301}x 中文 y\nz".to_string());
+        assert_eq!(source.row_col(2), (1, 2));
+        assert_eq!(source.row_col(6), (1, 8));
+        assert_eq!(source.row_byte_col(6), (1, 12));
+        assert_eq!(source.row_col(9), (2, 1));
+        assert_eq!(source.index(1, 2), 2);
+        assert_eq!(source.index(1, 4), 4);
+        assert_eq!(source.index(1, 5), 4);
+        assert_eq!(source.index(1, 6), 5);
+        assert_eq!(source.index(2, 1), 9);
+        assert_eq!(display_width("中文 e\u{301}"), 6);
+    }
+
+    #[test]
+    fn caret_escapes() {
+        assert_eq!(
+            decode_caret_escapes("Invalid UTF-8 byte sequence (^^c3^^a9)"),
+            "Invalid UTF-8 byte sequence (\u{e9})"
+        );
+        assert_eq!(decode_caret_escapes("a^^Ib"), "a\tb");
+        assert_eq!(decode_caret_escapes("line^^M"), "line^^M");
+        assert_eq!(decode_caret_escapes("x^2"), "x^2");
+    }
+
+    #[test]
+    fn decode_latin1() {
+        let bytes =
+            b"Package inputenc Error: Invalid UTF-8 byte \"E9 in caf\xe9.\n\xc3\xa9t\xc3\xa9\n";
+        assert_eq!(
+            decode(bytes),
+            "Package inputenc Error: Invalid UTF-8 byte \"E9 in caf\u{e9}.\n\u{e9}t\u{e9}\n"
+        );
     }
 }
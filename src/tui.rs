@@ -0,0 +1,286 @@
+use std::io::{self, Write};
+
+use termion::{
+    clear,
+    color::{self, Fg},
+    cursor,
+    event::Key,
+    input::TermRead,
+    raw::IntoRawMode,
+    screen::IntoAlternateScreen,
+    style,
+};
+
+use crate::{
+    log::Log,
+    parser::{DiagnosticLevel, Node},
+};
+
+/// A node in the flattened inclusion tree
+struct Row<'a> {
+    node: &'a Node,
+    depth: usize,
+}
+
+/// Pad or cut a string to exactly `width` characters
+fn fit(s: &str, width: usize) -> String {
+    let mut out: String = s.chars().take(width).collect();
+    let len = out.chars().count();
+    out += &" ".repeat(width - len);
+    out
+}
+
+struct Tui<'a> {
+    rows: Vec<Row<'a>>,
+
+    /// Indices of the rows that match the filter
+    visible: Vec<usize>,
+
+    /// Selected position in `visible`
+    selected: usize,
+
+    /// First visible row of the tree pane
+    scroll: usize,
+
+    /// First visible line of the diagnostics pane
+    detail_scroll: usize,
+
+    /// Filter on file paths and diagnostic text
+    query: String,
+
+    /// Whether the filter query is being typed
+    editing: bool,
+
+    /// Hide nodes without diagnostics
+    only_with_diagnostics: bool,
+}
+
+impl<'a> Tui<'a> {
+    fn new(log: &'a Log) -> Self {
+        let mut tui = Self {
+            rows: log
+                .root_node()
+                .walk()
+                .map(|(depth, node)| Row { node, depth })
+                .collect(),
+            visible: vec![],
+            selected: 0,
+            scroll: 0,
+            detail_scroll: 0,
+            query: String::new(),
+            editing: false,
+            only_with_diagnostics: false,
+        };
+        tui.refilter();
+        tui
+    }
+
+    fn row_matches(&self, row: &Row) -> bool {
+        let diagnostics = row.node.diagnostics();
+        if self.only_with_diagnostics && diagnostics.is_empty() {
+            return false;
+        }
+        if self.query.is_empty() {
+            return true;
+        }
+        let query = self.query.to_lowercase();
+        row.node.file().to_lowercase().contains(&query)
+            || diagnostics.iter().any(|d| {
+                d.kind().to_string().to_lowercase().contains(&query)
+                    || d.message().to_lowercase().contains(&query)
+            })
+    }
+
+    fn refilter(&mut self) {
+        let selected_row = self.visible.get(self.selected).copied();
+        self.visible = (0..self.rows.len())
+            .filter(|i| self.row_matches(&self.rows[*i]))
+            .collect();
+        self.selected = selected_row
+            .and_then(|r| self.visible.iter().position(|i| *i == r))
+            .unwrap_or(0);
+        self.detail_scroll = 0;
+    }
+
+    fn selected_node(&self) -> Option<&'a Node> {
+        self.visible.get(self.selected).map(|i| self.rows[*i].node)
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let max = self.visible.len() as isize - 1;
+        self.selected = (self.selected as isize + delta).clamp(0, max) as usize;
+        self.detail_scroll = 0;
+    }
+
+    /// Lines of the diagnostics pane with their color
+    fn detail_lines(&self) -> Vec<(String, Option<String>)> {
+        let Some(node) = self.selected_node() else {
+            return vec![("No matching files".to_string(), None)];
+        };
+        let mut lines = vec![
+            (node.file().to_string(), Some(Fg(color::Blue).to_string())),
+            ("".to_string(), None),
+        ];
+        if node.diagnostics().is_empty() {
+            lines.push(("No diagnostics".to_string(), None));
+        }
+        for d in node.diagnostics() {
+            let title_color = match d.level() {
+                DiagnosticLevel::Info => Fg(color::Cyan).to_string(),
+                DiagnosticLevel::Warning => Fg(color::Yellow).to_string(),
+                DiagnosticLevel::Error => Fg(color::Red).to_string(),
+            };
+            lines.push((d.kind().to_string(), Some(title_color)));
+            for line in d.message().lines() {
+                lines.push((line.to_string(), None));
+            }
+            lines.push(("".to_string(), None));
+        }
+        lines
+    }
+
+    fn draw<W: Write>(&mut self, out: &mut W) -> io::Result<()> {
+        let (width, height) = termion::terminal_size().unwrap_or((80, 24));
+        let (width, height) = (width as usize, height as usize);
+        let tree_width = (width * 2 / 5).max(10).min(width.saturating_sub(11));
+        let detail_width = width.saturating_sub(tree_width + 1);
+        let pane_height = height.saturating_sub(1);
+
+        // Keep selection in view
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + pane_height {
+            self.scroll = self.selected + 1 - pane_height;
+        }
+
+        let details = self.detail_lines();
+        self.detail_scroll = self.detail_scroll.min(details.len().saturating_sub(1));
+
+        write!(out, "{}", clear::All)?;
+        for y in 0..pane_height {
+            write!(out, "{}", cursor::Goto(1, y as u16 + 1))?;
+
+            match self.visible.get(self.scroll + y) {
+                Some(i) => {
+                    let row = &self.rows[*i];
+                    let warnings = row.node.warnings().len();
+                    let errors = row.node.errors().len();
+                    let counts = match (warnings, errors) {
+                        (0, 0) => "".to_string(),
+                        (w, 0) => format!(" [{}w]", w),
+                        (0, e) => format!(" [{}e]", e),
+                        (w, e) => format!(" [{}w {}e]", w, e),
+                    };
+                    let text = fit(
+                        &format!("{}{}{}", "  ".repeat(row.depth), row.node.file(), counts),
+                        tree_width,
+                    );
+                    let count_color = match errors {
+                        0 if warnings > 0 => Fg(color::Yellow).to_string(),
+                        0 => "".to_string(),
+                        _ => Fg(color::Red).to_string(),
+                    };
+                    if self.scroll + y == self.selected {
+                        write!(out, "{}{}{}", style::Invert, text, style::Reset)?;
+                    } else {
+                        write!(out, "{}{}{}", count_color, text, Fg(color::Reset))?;
+                    }
+                }
+                None => write!(out, "{}", " ".repeat(tree_width))?,
+            }
+
+            write!(out, "│")?;
+
+            if let Some((line, line_color)) = details.get(self.detail_scroll + y) {
+                let text = fit(line, detail_width);
+                match line_color {
+                    Some(c) => write!(out, "{}{}{}", c, text, Fg(color::Reset))?,
+                    None => write!(out, "{}", text)?,
+                }
+            }
+        }
+
+        write!(out, "{}", cursor::Goto(1, height as u16))?;
+        let status = if self.editing {
+            format!("/{}", self.query)
+        } else {
+            format!(
+                "{}/{} files  j/k: move  J/K: scroll  /: filter  e: toggle files without diagnostics  q: quit{}",
+                self.visible.len(),
+                self.rows.len(),
+                match self.query.as_str() {
+                    "" => "".to_string(),
+                    q => format!("  filter: {}", q),
+                },
+            )
+        };
+        write!(
+            out,
+            "{}{}{}",
+            style::Invert,
+            fit(&status, width),
+            style::Reset
+        )?;
+        out.flush()
+    }
+
+    /// Handle a key press. Returns `false` when the browser should close.
+    fn handle_key(&mut self, key: Key) -> bool {
+        if self.editing {
+            match key {
+                Key::Char('\n') | Key::Esc => self.editing = false,
+                Key::Backspace => {
+                    self.query.pop();
+                    self.refilter();
+                }
+                Key::Char(c) => {
+                    self.query.push(c);
+                    self.refilter();
+                }
+                _ => {}
+            }
+            return true;
+        }
+
+        match key {
+            Key::Char('q') | Key::Esc | Key::Ctrl('c') => return false,
+            Key::Char('j') | Key::Down => self.move_selection(1),
+            Key::Char('k') | Key::Up => self.move_selection(-1),
+            Key::PageDown => self.move_selection(10),
+            Key::PageUp => self.move_selection(-10),
+            Key::Char('g') | Key::Home => self.move_selection(isize::MIN / 2),
+            Key::Char('G') | Key::End => self.move_selection(isize::MAX / 2),
+            Key::Char('J') => self.detail_scroll += 1,
+            Key::Char('K') => self.detail_scroll = self.detail_scroll.saturating_sub(1),
+            Key::Char('/') => self.editing = true,
+            Key::Char('e') => {
+                self.only_with_diagnostics = !self.only_with_diagnostics;
+                self.refilter();
+            }
+            _ => {}
+        }
+        true
+    }
+}
+
+/// Run the interactive diagnostic browser until the user quits
+pub(crate) fn run(log: &Log) -> io::Result<()> {
+    let mut tui = Tui::new(log);
+    let mut out = io::stdout().into_raw_mode()?.into_alternate_screen()?;
+    write!(out, "{}", cursor::Hide)?;
+    tui.draw(&mut out)?;
+
+    // Keys come from the terminal, as stdin may be the log itself
+    for key in termion::get_tty()?.keys() {
+        if !tui.handle_key(key?) {
+            break;
+        }
+        tui.draw(&mut out)?;
+    }
+
+    write!(out, "{}", cursor::Show)?;
+    out.flush()
+}
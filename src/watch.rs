@@ -0,0 +1,65 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    process::ExitCode,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use termion::{clear, cursor};
+
+use crate::{check, cli::Args};
+
+/// Time between checks of the log file
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The log must be unchanged for this long before it is parsed, so that a running compilation
+/// has time to finish writing it
+const SETTLE_TIME: Duration = Duration::from_millis(300);
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Wait until the modification time differs from `last` and has settled
+fn wait_for_change(path: &Path, last: Option<SystemTime>) -> Option<SystemTime> {
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let current = modified(path);
+        if current.is_none() || current == last {
+            continue;
+        }
+        thread::sleep(SETTLE_TIME);
+        if modified(path) == current {
+            return current;
+        }
+    }
+}
+
+/// Check the log every time it is rewritten. Runs until interrupted.
+pub(crate) fn watch(args: &Args) -> ExitCode {
+    let path = match args.log_file() {
+        Some("-") | None => {
+            eprintln!("Error: watch requires a log file.");
+            return ExitCode::FAILURE;
+        }
+        Some(path) => Path::new(path),
+    };
+
+    let mut last = modified(path);
+    loop {
+        print!("{}{}", clear::All, cursor::Goto(1, 1));
+        let _ = io::stdout().flush();
+
+        if path.exists() {
+            // Errors, like those of a half written log, are printed by check. Keep watching.
+            check(args, &[path.display().to_string()]);
+        } else {
+            eprintln!("Waiting for {} to be created...", path.display());
+        }
+
+        println!("\nWatching {} for changes...", path.display());
+        last = wait_for_change(path, last);
+    }
+}